@@ -0,0 +1,122 @@
+/*!
+Encoding of a big scan. `read_then_encode` is the way the images used to be encoded, it holds the
+content of the file and its encoding at the same time. `load` streams the file into the encoding.
+`request_*` build the _text_ request of the scan three times, with a new image for every request and
+with one image that is encoded only once.
+
+The peak memory of every variant is printed before the timings.
+*/
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use mathpixapi::endpoint::text::{Base64Image, Text};
+use mathpixapi::endpoint::MathpixEndpoint;
+use mathpixapi::header::AuthHeader;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const SCAN_SIZE: usize = 16 * 1024 * 1024;
+
+// Allocator {{{
+/// Allocator that keeps track of the peak of the allocated memory
+struct PeakAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+        if !ptr.is_null() {
+            let allocated = ALLOCATED.fetch_add(layout.size(), Ordering::SeqCst) + layout.size();
+            PEAK.fetch_max(allocated, Ordering::SeqCst);
+        }
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        ALLOCATED.fetch_sub(layout.size(), Ordering::SeqCst);
+    }
+}
+
+#[global_allocator]
+static GLOBAL: PeakAllocator = PeakAllocator;
+
+/// Memory that is allocated by `f` at its peak
+fn peak_memory<T, F: FnOnce() -> T>(f: F) -> usize {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    PEAK.store(before, Ordering::SeqCst);
+    drop(f());
+    PEAK.load(Ordering::SeqCst) - before
+} // }}}
+
+/// JPEG file of `SCAN_SIZE` bytes that does not compress
+fn scan(dir: &Path) -> PathBuf {
+    let mut state: u32 = 0x2545_f491;
+    let mut data = vec![0xff, 0xd8, 0xff, 0xe0];
+    data.extend((4..SCAN_SIZE).map(|_| {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+        state as u8
+    }));
+    let path = dir.join("scan.jpg");
+    std::fs::write(&path, data).unwrap();
+    path
+}
+
+fn read_then_encode(path: &Path) -> String {
+    let data = std::fs::read(path).unwrap();
+    format!("data:image/jpeg;base64,{}", base64::encode(data))
+}
+
+fn request_new_images(path: &Path) {
+    for _ in 0..3 {
+        let text = Text::new(None, path.to_path_buf()).unwrap();
+        black_box(text.to_request(AuthHeader::new("id", "key")).unwrap());
+    }
+}
+
+fn request_one_image(path: &Path) {
+    let text = Text::new(None, Base64Image::load(path).unwrap()).unwrap();
+    for _ in 0..3 {
+        black_box(text.to_request(AuthHeader::new("id", "key")).unwrap());
+    }
+}
+
+fn encoding(c: &mut Criterion) {
+    //{{{
+    let dir = tempfile::tempdir().unwrap();
+    let path = scan(dir.path());
+    let mib = |bytes: usize| bytes as f64 / (1024.0 * 1024.0);
+    println!(
+        "peak memory for a {:.0} MiB scan: read_then_encode {:.1} MiB, load {:.1} MiB, \
+         request_new_images {:.1} MiB, request_one_image {:.1} MiB",
+        mib(SCAN_SIZE),
+        mib(peak_memory(|| read_then_encode(&path))),
+        mib(peak_memory(|| Base64Image::load(&path).unwrap())),
+        mib(peak_memory(|| request_new_images(&path))),
+        mib(peak_memory(|| request_one_image(&path))),
+    );
+
+    let mut group = c.benchmark_group("base64image");
+    group
+        .sample_size(10)
+        .throughput(Throughput::Bytes(SCAN_SIZE as u64));
+    group.bench_function("read_then_encode", |b| {
+        b.iter(|| read_then_encode(black_box(&path)))
+    });
+    group.bench_function("load", |b| {
+        b.iter(|| Base64Image::load(black_box(&path)).unwrap())
+    });
+    group.bench_function("request_new_images", |b| {
+        b.iter(|| request_new_images(black_box(&path)))
+    });
+    group.bench_function("request_one_image", |b| {
+        b.iter(|| request_one_image(black_box(&path)))
+    });
+    group.finish();
+} //}}}
+
+criterion_group!(benches, encoding);
+criterion_main!(benches);
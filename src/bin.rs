@@ -1,11 +1,43 @@
 extern crate mathpixapi;
 
-use clap::{crate_authors, crate_version, App, Arg, ArgGroup};
+use anyhow::{anyhow, Context};
+use clap::{crate_authors, crate_version, App, AppSettings, Arg, ArgGroup, ArgMatches};
+use futures::StreamExt;
+use mathpixapi::endpoint::batch::{BatchBody, BatchOptions, LaTeXFormats, LaTeXResponse};
+use mathpixapi::endpoint::converter::{ConversionFormat, Converter, ConverterOptions};
+use mathpixapi::endpoint::latex::{
+    CallBack, FormatOptions, ImageSrc, LaTeX, LaTeXOptions, Ocr, Region, TemplateVars, Transforms,
+};
+use mathpixapi::endpoint::pdf::{
+    ExtractOptions, PDFOptions, PDFOutputFormat, PDFResultsQuery, PDFSrc, PageRanges, PdfJob,
+    PollOptions, PDF,
+};
+use mathpixapi::endpoint::strokes::{
+    read_xopp, Data, DataOptions, PreprocessOptions, RenderOptions, StrokeSet, Strokes,
+    StrokesFormats, StrokesOptions, XoppOptions,
+};
+use mathpixapi::endpoint::text::{Text, TextOptions};
+use mathpixapi::endpoint::MathpixEndpoint;
+use mathpixapi::header::AuthHeader;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::convert::{TryFrom, TryInto};
+use std::io::Write;
+use std::num::NonZeroUsize;
+use std::path::{Path, PathBuf};
 
-fn main() {
+#[tokio::main]
+async fn main() -> anyhow::Result<()> {
     // Text endpoint{{{
     let text_subcommand = App::new("text")
                 .about("Text endpoint for the Mathpix API")
+                .arg(
+                    // Text.src {{{
+                    Arg::new("Text.src")
+                        .about("image file or URL of the image to process (`-` for stdin)")
+                        .value_name("IMAGE")
+                        .required(true)
+                )//}}}
                 .arg(
                     // TextBodyOptions.formats {{{
                     Arg::new("TextBodyOptions.formats")
@@ -25,7 +57,7 @@ fn main() {
                         // TODO: What is the default <29-05-21, kunzaatko> //
                         .about("list of data options for the outputs")
                         .value_name("OPTION")
-                        .possible_values(&["include_svg", "include_table_html", "include_latex", "include_tsv", "include_acsiimath", "include_mathml", "all"])
+                        .possible_values(&["include_svg", "include_table_html", "include_latex", "include_tsv", "include_asciimath", "include_mathml", "all"])
                         .multiple_values(true)
                 )//}}}
                 .arg(
@@ -103,12 +135,90 @@ fn main() {
                     Arg::new("TextBodyOptions.include_geometry_data")
                         .long("include_geometry_data")
                         .about("Include data extraction for geometry diagrams")
-                ); //}}}
-                   //}}}
+                )//}}}
+                .arg(
+                    // TextBodyOptions.math_inline_delimiters {{{
+                    Arg::new("TextBodyOptions.math_inline_delimiters")
+                        .long("math_inline_delimiters")
+                        .about("delimiters to be used in inline math")
+                        .number_of_values(2)
+                        .value_names(&["LDELIM", "RDELIM"])
+                )//}}}
+                .arg(
+                    // TextBodyOptions.math_display_delimiters {{{
+                    Arg::new("TextBodyOptions.math_display_delimiters")
+                        .long("math_display_delimiters")
+                        .about("delimiters to be used in displayed math")
+                        .number_of_values(2)
+                        .value_names(&["LDELIM", "RDELIM"])
+                )//}}}
+                .arg(
+                    // TextBodyOptions.include_equation_tags {{{
+                    Arg::new("TextBodyOptions.include_equation_tags")
+                        .long("include_equation_tags")
+                        .about("Include the equation numbers as `\\tag{...}` in the equations")
+                )//}}}
+                .arg(
+                    // TextBodyOptions.enable_tables_fallback {{{
+                    Arg::new("TextBodyOptions.enable_tables_fallback")
+                        .long("enable_tables_fallback")
+                        .about("Enable the advanced table processing algorithm for large and complex tables")
+                )//}}}
+                .arg(
+                    // TextBodyOptions.fullwidth_punctuation {{{
+                    Arg::new("TextBodyOptions.fullwidth_punctuation")
+                        .long("fullwidth_punctuation")
+                        // NOTE: The API default depends on the language of the text
+                        .about("Whether to use fullwidth (CJK) or halfwidth (Latin) punctuation [default: by the language]")
+                        .value_name("BOOL")
+                        .possible_values(&["true", "false"])
+                        .takes_value(true)
+                )//}}}
+                .arg(
+                    // TextBodyOptions.idiomatic_eqn_arrays {{{
+                    Arg::new("TextBodyOptions.idiomatic_eqn_arrays")
+                        .long("idiomatic_eqn_arrays")
+                        .about("Use `aligned`, `gathered` or `cases` instead of `array` for lists of equations")
+                )//}}}
+                .arg(
+                    // TextBodyOptions.include_diagram_text {{{
+                    Arg::new("TextBodyOptions.include_diagram_text")
+                        .long("include_diagram_text")
+                        .about("Include the text of diagrams in the line and word data")
+                )//}}}
+                .arg(
+                    // TextBodyOptions.include_table_html {{{
+                    Arg::new("TextBodyOptions.include_table_html")
+                        .long("include_table_html")
+                        .about("Include the HTML of the tables in the line data")
+                )//}}}
+                .arg(
+                    // TextBodyOptions.tags {{{
+                    Arg::new("TextBodyOptions.tags")
+                        .long("tags")
+                        .about("tags to identify the result with when querying the results")
+                        .value_name("TAG")
+                        .multiple_values(true)
+                        .takes_value(true)
+                )//}}}
+                .arg(
+                    // TextBodyOptions.enable_spell_check {{{
+                    Arg::new("TextBodyOptions.enable_spell_check")
+                        .long("enable_spell_check")
+                        .about("Take the word frequencies into account for English handwriting")
+                )//}}}
+                .args(callback_args()); //}}}
 
     // LaTeX endpoint {{{
     let latex_subcommand = App::new("latex")
         .about("LaTeX endpoint for the Mathpix API")
+        .arg(
+            // LaTeX.src {{{
+            Arg::new("LaTeX.src")
+                .about("image file or URL of the image to process (`-` for stdin)")
+                .value_name("IMAGE")
+                .required(true),
+        ) //}}}
         .arg(
             // LaTeXBodyOptions.format_options {{{
             Arg::new("LaTeXBodyOptions.formats")
@@ -230,37 +340,13 @@ fn main() {
         .group(
             // LaTeXBodyOptions.callback {{{
             ArgGroup::new("LaTeXBodyOptions.callback")
-                .arg("LaTeXBodyOptions.callback.post")
-                .arg("LaTeXBodyOptions.callback.headers")
-                .arg("LaTeXBodyOptions.callback.reply"),
-        ) //}}}
-        .arg(
-            // LaTeXBodyOptions.callback.post {{{
-            Arg::new("LaTeXBodyOptions.callback.post")
-                .long("post")
-                .about("URL where to post callback")
-                .value_name("URL"),
-        ) //}}}
-        // TODO: Make custom verification for having even number of arguments <29-05-21, kunzaatko> //
-        // TODO: Make parsing of $date and $image and so on for the strings in headers <29-05-21, kunzaatko> //
-        // TODO: Use custom delimiters for the headers using KEY : VALUE <29-05-21, kunzaatko> //
-        .arg(
-            // LaTeXBodyOptions.callback.headers {{{
-            Arg::new("LaTeXBodyOptions.callback.headers")
-                .long("headers")
-                .about("key value pairs of headers to make callback post")
-                .multiple_values(true)
-                .value_names(&["KEY", "VALUE"]),
-        ) //}}}
-        // TODO: What are the possible values for this <29-05-21, kunzaatko> //
-        .arg(
-            // LaTeXBodyOptions.callback.reply {{{
-            Arg::new("LaTeXBodyOptions.callback.reply")
-                .long("reply")
-                .about("values for `reply` field of callback object")
-                .multiple_values(true)
-                .value_name("VALUE"),
+                .arg("CallBack.post")
+                .arg("CallBack.headers")
+                .arg("CallBack.reply")
+                .arg("CallBack.tag")
+                .multiple(true),
         ) //}}}
+        .args(callback_args())
         .arg(
             // LaTeXBodyOptions.include_detected_alphabets {{{
             Arg::new("LaTeXBodyOptions.include_detected_alphabets")
@@ -272,15 +358,24 @@ fn main() {
     // Strokes endpoint {{{
     let strokes_subcommand = App::new("strokes")
         .about("Strokes endpoint for the Mathpix API")
+        .arg(
+            // Strokes.src {{{
+            Arg::new("Strokes.src")
+                .about(
+                    "file with the strokes (`.inkml`, `.xopp`, `.svg` or the `.json` strokes format)",
+                )
+                .value_name("FILE")
+                .required(true),
+        ) //}}}
         .arg(
             // StrokesBodyOptions.formats {{{
             Arg::new("StrokesBodyOptions.formats")
                 .long("format")
                 .short('f')
-                // TODO: What is the default <29-05-21, kunzaatko> //
                 .about("list of formats required in the output")
                 .value_name("FORMAT")
                 .possible_values(&["text", "data", "html", "all"])
+                .default_value("text")
                 .multiple_values(true),
         ) //}}}
         .arg(
@@ -294,19 +389,469 @@ fn main() {
                     "include_table_html",
                     "include_latex",
                     "include_tsv",
-                    "include_acsiimath",
+                    "include_asciimath",
                     "include_mathml",
                     "all",
                 ])
                 .multiple_values(true),
+        ) //}}}
+        .arg(
+            // Strokes.tolerance {{{
+            Arg::new("Strokes.tolerance")
+                .long("tolerance")
+                .about("maximal distance of the strokes from the curves of the SVG paths")
+                .value_name("DISTANCE")
+                .default_value("0.5")
+                .validator(|tolerance| match tolerance.parse::<f64>() {
+                    Ok(tolerance) if tolerance > 0. && tolerance.is_finite() => Ok(()),
+                    _ => Err("the tolerance has to be a positive number"),
+                }),
+        ) //}}}
+        .arg(
+            // PreprocessOptions.normalize {{{
+            Arg::new("PreprocessOptions.normalize")
+                .long("normalize")
+                .about("scale the strokes so that the larger side of their bounding box has the SIZE")
+                .value_name("SIZE")
+                .validator(|tolerance| match tolerance.parse::<f64>() {
+                    Ok(tolerance) if tolerance > 0. && tolerance.is_finite() => Ok(()),
+                    _ => Err("the value has to be a positive number"),
+                }),
+        ) //}}}
+        .arg(
+            // PreprocessOptions.dedup {{{
+            Arg::new("PreprocessOptions.dedup")
+                .long("dedup")
+                .about("remove the points at most DISTANCE away from the previous point")
+                .value_name("DISTANCE")
+                .validator(|tolerance| match tolerance.parse::<f64>() {
+                    Ok(tolerance) if tolerance >= 0. && tolerance.is_finite() => Ok(()),
+                    _ => Err("the value has to be a non-negative number"),
+                }),
+        ) //}}}
+        .arg(
+            // PreprocessOptions.simplify {{{
+            Arg::new("PreprocessOptions.simplify")
+                .long("simplify")
+                .about("simplify the strokes keeping them at most DISTANCE away from the original ones")
+                .value_name("DISTANCE")
+                .validator(|tolerance| match tolerance.parse::<f64>() {
+                    Ok(tolerance) if tolerance >= 0. && tolerance.is_finite() => Ok(()),
+                    _ => Err("the value has to be a non-negative number"),
+                }),
+        ) //}}}
+        .arg(
+            // PreprocessOptions.resample {{{
+            Arg::new("PreprocessOptions.resample")
+                .long("resample")
+                .about("place the points of the strokes uniformly DISTANCE apart")
+                .value_name("DISTANCE")
+                .validator(|tolerance| match tolerance.parse::<f64>() {
+                    Ok(tolerance) if tolerance > 0. && tolerance.is_finite() => Ok(()),
+                    _ => Err("the value has to be a positive number"),
+                }),
+        ) //}}}
+        .arg(
+            // RenderOptions.fallback {{{
+            Arg::new("RenderOptions.fallback")
+                .long("fallback")
+                .about("send the rendered strokes to the text endpoint when the strokes endpoint fails"),
+        ) //}}}
+        .arg(
+            // RenderOptions.line_width {{{
+            Arg::new("RenderOptions.line_width")
+                .long("line_width")
+                .about("width of the lines of the rendered strokes in pixels")
+                .value_name("PIXELS")
+                .default_value("3")
+                .validator(|width| match width.parse::<f64>() {
+                    Ok(width) if width > 0. && width.is_finite() => Ok(()),
+                    _ => Err("the width has to be a positive number"),
+                }),
+        ) //}}}
+        .arg(
+            // RenderOptions.padding {{{
+            Arg::new("RenderOptions.padding")
+                .long("padding")
+                .about("empty space around the rendered strokes in pixels")
+                .value_name("PIXELS")
+                .default_value("10")
+                .validator(|padding| padding.parse::<u32>()),
+        ) //}}}
+        .arg(
+            // RenderOptions.scale {{{
+            Arg::new("RenderOptions.scale")
+                .long("scale")
+                .about("pixels per a unit of the stroke coordinates in the rendered strokes")
+                .value_name("SCALE")
+                .default_value("1")
+                .validator(|scale| match scale.parse::<f64>() {
+                    Ok(scale) if scale > 0. && scale.is_finite() => Ok(()),
+                    _ => Err("the scale has to be a positive number"),
+                }),
+        ) //}}}
+        .arg(
+            // XoppOptions.pages {{{
+            Arg::new("XoppOptions.pages")
+                .long("pages")
+                .about("pages of the Xournal++ notes to send (all by default)")
+                .value_name("PAGE")
+                .validator(|page| page.parse::<NonZeroUsize>())
+                .multiple_values(true),
+        ) //}}}
+        .arg(
+            // XoppOptions.layers {{{
+            Arg::new("XoppOptions.layers")
+                .long("layers")
+                .about("layers of the Xournal++ pages to send counted from the bottom (all by default)")
+                .value_name("LAYER")
+                .validator(|layer| layer.parse::<NonZeroUsize>())
+                .multiple_values(true),
+        ) //}}}
+        .arg(
+            // XoppOptions.highlighter {{{
+            Arg::new("XoppOptions.highlighter")
+                .long("highlighter")
+                .about("send also the highlighter strokes of the Xournal++ notes"),
+        ) //}}}
+        .arg(
+            // XoppOptions.images {{{
+            Arg::new("XoppOptions.images")
+                .long("images")
+                .about("save the images of the Xournal++ notes to DIR and link them in the output")
+                .value_name("DIR"),
         ); //}}}
            //}}}
 
     // PDF endpoint {{{
-    let pdf_subcommand = App::new("pdf").about("PDF endpoint for for the Mathpix API");
-    // }}}
+    let pdf_subcommand = App::new("pdf")
+        .about("PDF endpoint for for the Mathpix API")
+        .setting(AppSettings::SubcommandsNegateReqs)
+        .arg(
+            // PDF.src {{{
+            Arg::new("PDF.src")
+                .about("URL or path of the PDF to send")
+                .value_name("SRC")
+                .required(true),
+        ) //}}}
+        .arg(
+            // PDFOptions.streaming {{{
+            Arg::new("PDFOptions.streaming")
+                .long("stream")
+                .about("Print the Mathpix Markdown of the pages as they are processed"),
+        ) //}}}
+        .arg(
+            // PDFOptions.conversion_formats {{{
+            Arg::new("PDFOptions.conversion_formats")
+                .long("conversion_formats")
+                .short('f')
+                .about("formats to convert the PDF to in addition to `mmd`")
+                .value_name("FORMAT")
+                .possible_values(&["docx", "tex.zip", "html", "md"])
+                .multiple_values(true)
+                .takes_value(true),
+        ) //}}}
+        .arg(
+            // PDFOptions.math_inline_delimiters {{{
+            Arg::new("PDFOptions.math_inline_delimiters")
+                .long("math_inline_delimiters")
+                .about("delimiters to be used in inline math")
+                .number_of_values(2)
+                .value_names(&["LDELIM", "RDELIM"]),
+        ) //}}}
+        .arg(
+            // PDFOptions.math_display_delimiters {{{
+            Arg::new("PDFOptions.math_display_delimiters")
+                .long("math_display_delimiters")
+                .about("delimiters to be used in displayed math")
+                .number_of_values(2)
+                .value_names(&["LDELIM", "RDELIM"]),
+        ) //}}}
+        .arg(
+            // PDFOptions.rm_spaces {{{
+            Arg::new("PDFOptions.rm_spaces")
+                // NOTE: `true` is the API default
+                .long("keep_spaces")
+                .about("Keep extra white space in equations"),
+        ) //}}}
+        .arg(
+            // PDFOptions.rm_fonts {{{
+            Arg::new("PDFOptions.rm_fonts")
+                .long("rm_fonts")
+                .about("Remove font commands such as `\\mathbf` and `\\mathrm` from equations"),
+        ) //}}}
+        .arg(
+            // PDFOptions.alphabets_allowed {{{
+            Arg::new("PDFOptions.alphabets_allowed")
+                .about("Specify which alphabets are allowed for the OCR (prefix with `no` to disallow)")
+                .long("alphabets_allowed")
+                .value_name("ALPHABET")
+                .takes_value(true)
+                .multiple_values(true),
+        ) //}}}
+        .arg(
+            // PDFOptions.page_ranges {{{
+            Arg::new("PDFOptions.page_ranges")
+                .long("pages")
+                .alias("page_ranges")
+                .about("Comma separated pages and page ranges to process (e.g. `1-3,7,10-` or `-2` for the last two pages)")
+                .value_name("RANGES")
+                .validator(|pages| pages.parse::<PageRanges>())
+                .allow_hyphen_values(true)
+                .takes_value(true),
+        ) //}}}
+        .arg(
+            // PDFOptions.enable_tables_fallback {{{
+            Arg::new("PDFOptions.enable_tables_fallback")
+                .long("enable_tables_fallback")
+                .about("Enable the advanced table processing algorithm for large and complex tables"),
+        ) //}}}
+        .arg(
+            // PDFOptions.numbers_default_to_math {{{
+            Arg::new("PDFOptions.numbers_default_to_math")
+                .long("numbers_default_to_math")
+                .about("Always treat numbers as math"),
+        ) //}}}
+        .arg(
+            // PDFOptions.auto_number_sections {{{
+            Arg::new("PDFOptions.auto_number_sections")
+                .long("auto_number_sections")
+                .about("Number sections and subsections automatically"),
+        ) //}}}
+        .subcommand(
+            // pdf status {{{
+            App::new("status")
+                .about("Print the processing status of a PDF sent earlier")
+                .arg(
+                    Arg::new("PDF.pdf_id")
+                        .about("ID of the PDF returned when it was sent")
+                        .value_name("ID")
+                        .required(true),
+                ),
+        ) //}}}
+        .subcommand(
+            // pdf fetch {{{
+            App::new("fetch")
+                .about("Wait for a PDF sent earlier to be processed and download the results")
+                .arg(
+                    Arg::new("PDF.pdf_id")
+                        .about("ID of the PDF returned when it was sent")
+                        .value_name("ID")
+                        .required(true),
+                )
+                .arg(
+                    Arg::new("PDF.output_formats")
+                        .long("format")
+                        .short('f')
+                        .about("formats of the results to download")
+                        .value_name("FORMAT")
+                        .possible_values(&["mmd", "md", "docx", "tex.zip", "html", "lines.json"])
+                        .default_value("mmd")
+                        .multiple_values(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDF.output")
+                        .long("output")
+                        .short('o')
+                        .about("file to write the result to or a directory when multiple formats are downloaded [default: stdout or `ID.FORMAT` files]")
+                        .value_name("PATH")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDF.timeout")
+                        .long("timeout")
+                        .about("seconds to wait for the processing before giving up [default: 1800]")
+                        .value_name("SECONDS")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDF.extract")
+                        .long("extract")
+                        .about("extract the `tex.zip` result into a LaTeX project in DIR")
+                        .value_name("DIR")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDF.force")
+                        .long("force")
+                        .requires("PDF.extract")
+                        .about("overwrite existing files when extracting"),
+                )
+                .arg(
+                    Arg::new("PDF.rename_images")
+                        .long("rename_images")
+                        .requires("PDF.extract")
+                        .about("rename the extracted images to `image-001.EXT`, ... in the order of their use"),
+                ),
+        ) //}}}
+        .subcommand(
+            // pdf list {{{
+            App::new("list")
+                .about("List the PDFs processed on the server")
+                .arg(
+                    Arg::new("PDFResultsQuery.page")
+                        .long("page")
+                        .about("page of the results starting at 1")
+                        .value_name("PAGE")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDFResultsQuery.per_page")
+                        .long("per_page")
+                        .about("number of results per page")
+                        .value_name("COUNT")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDFResultsQuery.from_date")
+                        .long("from")
+                        .about("list only the PDFs sent since the ISO date (included)")
+                        .value_name("DATE")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDFResultsQuery.to_date")
+                        .long("to")
+                        .about("list only the PDFs sent before the ISO date (excluded)")
+                        .value_name("DATE")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("PDF.local")
+                        .long("local")
+                        .conflicts_with_all(&[
+                            "PDFResultsQuery.page",
+                            "PDFResultsQuery.per_page",
+                            "PDFResultsQuery.from_date",
+                            "PDFResultsQuery.to_date",
+                        ])
+                        .about("list the PDFs in the local record of the sent PDFs instead"),
+                ),
+        ) //}}}
+        .subcommand(
+            // pdf delete {{{
+            App::new("delete")
+                .about("Delete PDFs and their results from the server")
+                .arg(
+                    Arg::new("PDF.pdf_id")
+                        .about("IDs of the PDFs to delete")
+                        .value_name("ID")
+                        .multiple_values(true),
+                )
+                .arg(
+                    Arg::new("PDF.recorded")
+                        .long("recorded")
+                        .about("delete all of the PDFs in the local record of the sent PDFs"),
+                )
+                .group(
+                    ArgGroup::new("PDF.delete")
+                        .arg("PDF.pdf_id")
+                        .arg("PDF.recorded")
+                        .required(true),
+                ),
+        ); //}}}
+           // }}}
 
-    // TODO: Batch endpoint <03-07-21, kunzaatko> //
+    // Batch endpoint {{{
+    let batch_subcommand = App::new("batch")
+        .about("Batch endpoint for the Mathpix API")
+        .arg(
+            // BatchBody.urls {{{
+            Arg::new("BatchBody.urls")
+                .about("file with an image URL on every line optionally preceded by its KEY (`-` for stdin)")
+                .value_name("FILE")
+                .required(true),
+        ) //}}}
+        .arg(
+            // BatchOptions.formats {{{
+            Arg::new("BatchOptions.formats")
+                .long("format")
+                .short('f')
+                .about("list of formats required in the output")
+                .value_name("FORMAT")
+                .possible_values(&[
+                    "text",
+                    "text_display",
+                    "latex_normal",
+                    "latex_styled",
+                    "latex_simplified",
+                    "latex_list",
+                    "mathml",
+                    "asciimath",
+                    "wolfram",
+                ])
+                .default_value("text")
+                .multiple_values(true)
+                .takes_value(true),
+        ) //}}}
+        .arg(
+            // LaTeXOptions.ocr {{{
+            Arg::new("LaTeXOptions.ocr")
+                .long("ocr")
+                .about("Whether to process only `math` or both `math` and `text`")
+                .possible_values(&["math", "text"])
+                .value_name("OCR")
+                .multiple_values(true)
+                .takes_value(true),
+        ) //}}}
+        .arg(
+            // LaTeXOptions.skip_recrop {{{
+            Arg::new("LaTeXOptions.skip_recrop")
+                .long("skip_recrop")
+                .about("skip the recroping before OCR of the images"),
+        ) //}}}
+        .args(callback_args())
+        .arg(
+            // Batch.timeout {{{
+            Arg::new("Batch.timeout")
+                .long("timeout")
+                .about("seconds to wait for the processing before giving up [default: 1800]")
+                .value_name("SECONDS")
+                .takes_value(true),
+        ); //}}}
+           // }}}
+
+    // Converter endpoint {{{
+    let convert_subcommand = App::new("convert")
+        .about("Converter endpoint for the Mathpix API (Mathpix Markdown to other formats)")
+        .arg(
+            // Converter.src {{{
+            Arg::new("Converter.src")
+                .about("Mathpix Markdown file to convert (`-` for stdin)")
+                .value_name("FILE")
+                .required(true),
+        ) //}}}
+        .arg(
+            // ConverterOptions.formats {{{
+            Arg::new("ConverterOptions.formats")
+                .long("to")
+                .short('t')
+                .about("formats to convert the Mathpix Markdown to")
+                .value_name("FORMAT")
+                .possible_values(&["docx", "tex.zip", "html", "md"])
+                .multiple_values(true)
+                .required(true)
+                .takes_value(true),
+        ) //}}}
+        .arg(
+            // Converter.output {{{
+            Arg::new("Converter.output")
+                .long("output")
+                .short('o')
+                .about("file to write the result to or a directory when multiple formats are converted [default: `FILE.FORMAT` next to the source]")
+                .value_name("PATH")
+                .takes_value(true),
+        ) //}}}
+        .arg(
+            // Converter.timeout {{{
+            Arg::new("Converter.timeout")
+                .long("timeout")
+                .about("seconds to wait for the conversion before giving up [default: 1800]")
+                .value_name("SECONDS")
+                .takes_value(true),
+        ); //}}}
+           // }}}
 
     let args = App::new("MathpixCLI")
         .version(crate_version!())
@@ -334,5 +879,1110 @@ fn main() {
         .subcommand(latex_subcommand)
         .subcommand(strokes_subcommand)
         .subcommand(pdf_subcommand)
+        .subcommand(batch_subcommand)
+        .subcommand(convert_subcommand)
         .get_matches();
+
+    match args.subcommand() {
+        Some(("text", text_args)) => text(&args, text_args).await,
+        Some(("latex", latex_args)) => latex(&args, latex_args).await,
+        Some(("pdf", pdf_args)) => pdf(&args, pdf_args).await,
+        Some(("batch", batch_args)) => batch(&args, batch_args).await,
+        Some(("strokes", strokes_args)) => strokes(&args, strokes_args).await,
+        Some(("convert", convert_args)) => convert(&args, convert_args).await,
+        _ => Ok(()),
+    }
+}
+
+/// Arguments of the callback of the _latex_ options that are shared by the subcommands
+fn callback_args() -> Vec<Arg<'static>> {
+    vec![
+        // CallBack.post {{{
+        Arg::new("CallBack.post")
+            .long("post")
+            .about("URL where to post callback")
+            .value_name("URL")
+            .validator(|url| CallBack::new(url).map(|_| ())),
+        // }}}
+        // CallBack.headers {{{
+        Arg::new("CallBack.headers")
+            .long("headers")
+            .about(
+                "headers of the callback post as `KEY: VALUE`, the values can contain `$date`, \
+                 `$image` and `$tag` that are replaced before sending",
+            )
+            .value_name("KEY: VALUE")
+            .multiple_values(true)
+            .validator(|line| CallBack::default().header_line(line).map(|_| ())),
+        // }}}
+        // CallBack.reply {{{
+        Arg::new("CallBack.reply")
+            .long("reply")
+            .about("JSON value of the `reply` field of the callback object")
+            .value_name("JSON")
+            .validator(|reply| serde_json::from_str::<serde_json::Value>(reply).map(|_| ())),
+        // }}}
+        // CallBack.tag {{{
+        Arg::new("CallBack.tag")
+            .long("tag")
+            .about("value of `$tag` in the callback headers")
+            .value_name("TAG"),
+        // }}}
+    ]
+}
+
+/// The callback from the arguments created by [callback_args]
+fn callback(sub_args: &ArgMatches) -> anyhow::Result<Option<CallBack>> {
+    let post = match sub_args.value_of("CallBack.post") {
+        Some(post) => post,
+        None if sub_args.is_present("CallBack.headers")
+            || sub_args.is_present("CallBack.reply") =>
+        {
+            return Err(anyhow!("the callback needs a URL (use --post)"))
+        }
+        None => return Ok(None),
+    };
+    let mut callback = CallBack::new(post)?;
+    for line in sub_args.values_of("CallBack.headers").into_iter().flatten() {
+        callback.header_line(line)?;
+    }
+    if let Some(reply) = sub_args.value_of("CallBack.reply") {
+        callback.reply(serde_json::from_str(reply)?);
+    }
+    if let Some(tag) = sub_args.value_of("CallBack.tag") {
+        let mut vars = TemplateVars::default();
+        vars.tag(tag);
+        callback = callback.expand(&vars)?;
+    }
+    Ok(Some(callback))
+}
+
+/// Create the request header from the `--id` and `--key` arguments
+fn auth_header(args: &ArgMatches) -> anyhow::Result<AuthHeader> {
+    let app_id = args
+        .value_of("Header.app_id")
+        .ok_or_else(|| anyhow!("the API ID is missing (use --id or MATHPIX_APP_ID)"))?;
+    let app_key = args
+        .value_of("Header.app_key")
+        .ok_or_else(|| anyhow!("the API key is missing (use --key or MATHPIX_APP_KEY)"))?;
+    Ok(AuthHeader::new(app_id, app_key))
+}
+
+// text {{{
+/// Setter of an option of the _text_ endpoint that is set by a flag
+type TextFlag = fn(&mut TextOptions, bool) -> &mut TextOptions;
+
+async fn text(args: &ArgMatches, text_args: &ArgMatches) -> anyhow::Result<()> {
+    let mut formats: Vec<&str> = text_args
+        .values_of("TextBodyOptions.formats")
+        .into_iter()
+        .flatten()
+        .collect();
+    if formats.contains(&"all") {
+        formats = vec!["text", "data", "html", "latex_styled"];
+    }
+    let mut options = TextOptions::default();
+    if !formats.is_empty() {
+        options.add_formats_from_strings(&formats)?;
+    }
+    if let Some(data_options) = text_args.values_of("TextBodyOptions.data_options") {
+        let mut data_options: Vec<&str> = data_options.collect();
+        if data_options.contains(&"all") {
+            data_options = vec![
+                "include_svg",
+                "include_table_html",
+                "include_latex",
+                "include_tsv",
+                "include_asciimath",
+                "include_mathml",
+            ];
+        }
+        options.add_data_options_from_strings(data_options)?;
+    }
+    if let Some(alphabets) = text_args.values_of("TextBodyOptions.alphabets_allowed") {
+        options.set_alphabets_allowed(&alphabets.collect::<Vec<_>>())?;
+    }
+    if let Some(threshold) = text_args.value_of("TextBodyOptions.confidence_threshold") {
+        options.confidence_threshold(number(threshold)?)?;
+    }
+    if let Some(threshold) = text_args.value_of("TextBodyOptions.confidence_rate_threshold") {
+        options.confidence_rate_threshold(number::<f32>(threshold)?)?;
+    }
+    let flags: [(&str, TextFlag); 12] = [
+        (
+            "TextBodyOptions.include_detected_alphabets",
+            TextOptions::include_detected_alphabets,
+        ),
+        (
+            "TextBodyOptions.include_line_data",
+            TextOptions::include_line_data,
+        ),
+        (
+            "TextBodyOptions.include_word_data",
+            TextOptions::include_word_data,
+        ),
+        (
+            "TextBodyOptions.include_smiles",
+            TextOptions::include_smiles,
+        ),
+        ("TextBodyOptions.include_inchi", TextOptions::include_inchi),
+        (
+            "TextBodyOptions.include_geometry_data",
+            TextOptions::include_geometry_data,
+        ),
+        (
+            "TextBodyOptions.include_equation_tags",
+            TextOptions::include_equation_tags,
+        ),
+        (
+            "TextBodyOptions.enable_tables_fallback",
+            TextOptions::enable_tables_fallback,
+        ),
+        (
+            "TextBodyOptions.idiomatic_eqn_arrays",
+            TextOptions::idiomatic_eqn_arrays,
+        ),
+        (
+            "TextBodyOptions.include_diagram_text",
+            TextOptions::include_diagram_text,
+        ),
+        (
+            "TextBodyOptions.include_table_html",
+            TextOptions::include_table_html,
+        ),
+        (
+            "TextBodyOptions.enable_spell_check",
+            TextOptions::enable_spell_check,
+        ),
+    ];
+    for (id, set) in flags.iter() {
+        if text_args.is_present(id) {
+            set(&mut options, true);
+        }
+    }
+    if let Some(delimiters) = delimiters(text_args, "TextBodyOptions.math_inline_delimiters") {
+        options.math_inline_delimiters(delimiters.0, delimiters.1)?;
+    }
+    if let Some(delimiters) = delimiters(text_args, "TextBodyOptions.math_display_delimiters") {
+        options.math_display_delimiters(delimiters.0, delimiters.1)?;
+    }
+    if let Some(fullwidth) = text_args.value_of("TextBodyOptions.fullwidth_punctuation") {
+        options.fullwidth_punctuation(fullwidth == "true");
+    }
+    if let Some(tags) = text_args.values_of("TextBodyOptions.tags") {
+        options.add_tags(tags);
+    }
+    if let Some(callback) = callback(text_args)? {
+        options.callback(callback);
+    }
+
+    let src = image_src(text_args.value_of("Text.src").unwrap())?;
+    let response = Text::new(Some(options), src)?
+        .send_request(auth_header(args)?)
+        .await?;
+    if let Some(error) = response.error {
+        return Err(anyhow!(error));
+    }
+    // NOTE: The server returns only the text when no format is requested
+    if formats.is_empty() {
+        formats = vec!["text"];
+    }
+    let recognized = Recognized {
+        text: response.text,
+        html: response.html,
+        data: response.data,
+    };
+    for format in &formats {
+        match *format {
+            "latex_styled" => println!("{}", response.latex_styled.as_deref().unwrap_or_default()),
+            format => print_recognized(&[format], &recognized),
+        }
+    }
+    Ok(())
+}
+
+/// Image given as a path, as a URL or `-` for stdin
+fn image_src(src: &str) -> anyhow::Result<ImageSrc> {
+    if src == "-" {
+        Ok(ImageSrc::from_reader(std::io::stdin())?)
+    } else {
+        ImageSrc::try_from(src).with_context(|| format!("invalid image {:?}", src))
+    }
+}
+
+fn number<T: std::str::FromStr>(value: &str) -> anyhow::Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .parse()
+        .with_context(|| format!("{:?} is not a number", value))
+}
+
+/// The begin and end delimiters of the argument `id`
+fn delimiters<'a>(sub_args: &'a ArgMatches, id: &str) -> Option<(&'a str, &'a str)> {
+    let mut values = sub_args.values_of(id)?;
+    Some((values.next()?, values.next()?))
+}
+// }}}
+
+// latex {{{
+async fn latex(args: &ArgMatches, latex_args: &ArgMatches) -> anyhow::Result<()> {
+    let mut formats: Vec<&str> = latex_args
+        .values_of("LaTeXBodyOptions.formats")
+        .unwrap()
+        .collect();
+    if formats.contains(&"all") {
+        formats = vec![
+            "text",
+            "text_display",
+            "latex_styled",
+            "latex_simplified",
+            "latex_list",
+            "mathml",
+            "asciimath",
+            "wolfram",
+        ];
+    }
+    let formats = formats
+        .into_iter()
+        .map(str::parse::<LaTeXFormats>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut options = LaTeXOptions::default();
+    if let Some(ocr) = latex_args.values_of("LaTeXBodyOptions.ocr") {
+        let ocr: Vec<&str> = ocr.collect();
+        let ocr = if ocr.contains(&"all") {
+            vec![Ocr::Math, Ocr::Text]
+        } else {
+            ocr.into_iter()
+                .map(str::parse::<Ocr>)
+                .collect::<Result<Vec<_>, _>>()?
+        };
+        options.ocr(ocr);
+    }
+    let transforms = latex_args
+        .values_of("LaTeXBodyOptions.format_options.transforms")
+        .map(|transforms| {
+            let transforms: Vec<&str> = transforms.collect();
+            let all = transforms.contains(&"all");
+            let selected = |name: &str| all || transforms.contains(&name);
+            vec![
+                ("rm_spaces", Transforms::RmSpaces),
+                ("rm_newlines", Transforms::RmNewlines),
+                ("rm_fonts", Transforms::RmFonts),
+                ("rm_style_syms", Transforms::RmStyleSyms),
+                ("rm_text", Transforms::RmText),
+                ("long_frac", Transforms::LongFrac),
+            ]
+            .into_iter()
+            .filter(|(name, _)| selected(name))
+            .map(|(_, transform)| transform)
+            .collect::<Vec<_>>()
+        });
+    let delimiters_list = |id: &str| {
+        delimiters(latex_args, id).map(|(begin, end)| vec![begin.to_string(), end.to_string()])
+    };
+    let format_options = FormatOptions {
+        transforms,
+        math_delims: delimiters_list("LaTeXBodyOptions.format_options.math_delimiters"),
+        displaymath_delims: delimiters_list("LaTeXBodyOptions.format_options.displaymath_delims"),
+    };
+    if format_options.transforms.is_some()
+        || format_options.math_delims.is_some()
+        || format_options.displaymath_delims.is_some()
+    {
+        options.format_options(format_options);
+    }
+    if latex_args.is_present("LaTeXBodyOptions.skip_recrop") {
+        options.skip_recrop(true);
+    }
+    if let Some(threshold) = latex_args.value_of("LaTeXBodyOptions.confidence_threshold") {
+        options.confidence_threshold(number::<f32>(threshold)?.try_into()?);
+    }
+    if let Some(beam_size) = latex_args.value_of("LaTeXBodyOptions.beam_size") {
+        options.beam_size(number(beam_size)?);
+    }
+    if let Some(n_best) = latex_args.value_of("LaTeXBodyOptions.n_best") {
+        options.n_best(number(n_best)?);
+    }
+    if let Some(region) = latex_args.values_of("LaTeXBodyOptions.region") {
+        let region = region.map(number).collect::<anyhow::Result<Vec<u32>>>()?;
+        options.region(Region {
+            top_left_x: Some(region[0]),
+            top_left_y: Some(region[1]),
+            width: Some(region[2]),
+            height: Some(region[3]),
+        });
+    }
+    if let Some(callback) = callback(latex_args)? {
+        options.callback(callback);
+    }
+    if latex_args.is_present("LaTeXBodyOptions.include_detected_alphabets") {
+        options.include_detected_alphabets(true);
+    }
+
+    let src = image_src(latex_args.value_of("LaTeX.src").unwrap())?;
+    let mut latex = LaTeX::new(Some(options), src)?;
+    latex.formats(formats.clone());
+    let response = latex.send_request(auth_header(args)?).await?;
+    if let Some(error) = &response.error {
+        return Err(anyhow!(error.clone()));
+    }
+    for format in &formats {
+        println!(
+            "{}",
+            latex_format_value(&response, format).unwrap_or_default()
+        );
+    }
+    Ok(())
+}
+// }}}
+
+// batch {{{
+/// The options of polling for a job with the timeout from the `timeout_arg` of the subcommand
+fn poll_options(sub_args: &ArgMatches, timeout_arg: &str) -> anyhow::Result<PollOptions> {
+    let mut poll = PollOptions::default();
+    if let Some(timeout) = sub_args.value_of(timeout_arg) {
+        let seconds: u64 = timeout
+            .parse()
+            .with_context(|| format!("invalid timeout {:?}", timeout))?;
+        poll.timeout = Some(std::time::Duration::from_secs(seconds));
+    }
+    Ok(poll)
+}
+
+async fn batch(args: &ArgMatches, batch_args: &ArgMatches) -> anyhow::Result<()> {
+    let urls = read_batch_urls(batch_args.value_of("BatchBody.urls").unwrap())?;
+    let formats = batch_args
+        .values_of("BatchOptions.formats")
+        .unwrap()
+        .map(str::parse::<LaTeXFormats>)
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut options = BatchOptions::default();
+    options.formats(formats.clone());
+    if let Some(ocr) = batch_args.values_of("LaTeXOptions.ocr") {
+        let ocr = ocr.map(str::parse::<Ocr>).collect::<Result<Vec<_>, _>>()?;
+        options.latex_options().ocr(ocr);
+    }
+    if batch_args.is_present("LaTeXOptions.skip_recrop") {
+        options.latex_options().skip_recrop(true);
+    }
+    if let Some(callback) = callback(batch_args)? {
+        options.latex_options().callback(callback);
+    }
+    let poll = poll_options(batch_args, "Batch.timeout")?;
+
+    let job = BatchBody::new(Some(options), urls)?
+        .start_job(auth_header(args)?)
+        .await?;
+    eprintln!("{}", job.batch_id());
+    let status = job
+        .wait(&poll, |status| {
+            eprint!(
+                "\r{}/{} images processed",
+                status.results.len(),
+                status.keys.len()
+            )
+        })
+        .await?;
+    eprintln!();
+
+    for key in &status.keys {
+        let response = &status.results[key];
+        if let Some(error) = &response.error {
+            println!("{}\terror\t{}", key, error);
+            continue;
+        }
+        for format in &formats {
+            if let Some(value) = latex_format_value(response, format) {
+                println!(
+                    "{}\t{}\t{}",
+                    key,
+                    serde_json::to_value(format)?.as_str().unwrap(),
+                    value
+                );
+            }
+        }
+    }
+    Ok(())
+}
+
+/**
+Read the URLs of the batch. Every non-empty line that does not start with `#` contains an URL
+optionally preceded by its key. The lines without a key are numbered from 1.
+*/
+fn read_batch_urls(path: &str) -> anyhow::Result<BTreeMap<String, reqwest::Url>> {
+    let content = if path == "-" {
+        std::io::read_to_string(std::io::stdin())?
+    } else {
+        std::fs::read_to_string(path).with_context(|| format!("can not read {:?}", path))?
+    };
+    let mut urls = BTreeMap::new();
+    let lines = content
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty() && !line.trim_start().starts_with('#'));
+    for (entry, (number, line)) in lines.enumerate() {
+        let (key, url) = match line.split_whitespace().collect::<Vec<_>>()[..] {
+            [url] => ((entry + 1).to_string(), url),
+            [key, url] => (key.to_string(), url),
+            _ => return Err(anyhow!("line {}: expected `[KEY] URL`", number + 1)),
+        };
+        let url = reqwest::Url::parse(url)
+            .with_context(|| format!("line {}: invalid URL {:?}", number + 1, url))?;
+        if urls.insert(key.clone(), url).is_some() {
+            return Err(anyhow!("line {}: duplicate key {:?}", number + 1, key));
+        }
+    }
+    Ok(urls)
+}
+
+/// The value of `format` in the `response`
+fn latex_format_value(response: &LaTeXResponse, format: &LaTeXFormats) -> Option<String> {
+    match format {
+        LaTeXFormats::Text => response.text.clone(),
+        LaTeXFormats::TextDisplay => response.text_display.clone(),
+        LaTeXFormats::LaTeXNormal => response.latex_normal.clone(),
+        LaTeXFormats::LaTeXStyled => response.latex_styled.clone(),
+        LaTeXFormats::LaTeXSimplified => response.latex_simplified.clone(),
+        LaTeXFormats::LaTeXList => response.latex_list.as_ref().map(|list| list.join("; ")),
+        LaTeXFormats::MathML => response.mathml.clone(),
+        LaTeXFormats::AsciiMath => response.asciimath.clone(),
+        LaTeXFormats::Wolfram => response.wolfram.clone(),
+    }
+}
+// }}}
+
+// strokes {{{
+async fn strokes(args: &ArgMatches, strokes_args: &ArgMatches) -> anyhow::Result<()> {
+    let path = Path::new(strokes_args.value_of("Strokes.src").unwrap());
+    let mut formats: Vec<&str> = strokes_args
+        .values_of("StrokesBodyOptions.formats")
+        .unwrap()
+        .collect();
+    if formats.contains(&"all") {
+        formats = vec!["text", "data", "html"];
+    }
+    let mut options = StrokesOptions::default();
+    options.formats(
+        formats
+            .iter()
+            .map(|format| match *format {
+                "data" => StrokesFormats::Data,
+                "html" => StrokesFormats::Html,
+                _ => StrokesFormats::Text,
+            })
+            .collect(),
+    );
+    if let Some(data_options) = strokes_args.values_of("StrokesBodyOptions.data_options") {
+        let data_options: Vec<&str> = data_options.collect();
+        let include = |option: &str| {
+            if data_options.contains(&option) || data_options.contains(&"all") {
+                Some(true)
+            } else {
+                None
+            }
+        };
+        options.data_options(DataOptions {
+            include_svg: include("include_svg"),
+            include_table_html: include("include_table_html"),
+            include_latex: include("include_latex"),
+            include_tsv: include("include_tsv"),
+            include_asciimath: include("include_asciimath"),
+            include_mathml: include("include_mathml"),
+        });
+    }
+    let preprocess_value = |id: &str| {
+        strokes_args
+            .value_of(id)
+            .map(|value| value.parse().unwrap())
+    };
+    let preprocess = PreprocessOptions {
+        normalize: preprocess_value("PreprocessOptions.normalize"),
+        dedup: preprocess_value("PreprocessOptions.dedup"),
+        simplify: preprocess_value("PreprocessOptions.simplify"),
+        resample: preprocess_value("PreprocessOptions.resample"),
+    };
+    let fallback = if strokes_args.is_present("RenderOptions.fallback") {
+        let mut render = RenderOptions::default();
+        let value = |id: &str| strokes_args.value_of(id).unwrap();
+        render
+            .line_width(value("RenderOptions.line_width").parse()?)
+            .padding(value("RenderOptions.padding").parse()?)
+            .scale(value("RenderOptions.scale").parse()?);
+        Some(render)
+    } else {
+        None
+    };
+    let header = auth_header(args)?;
+    let send = |strokes| send_strokes(&header, &options, &preprocess, fallback.as_ref(), strokes);
+
+    if !matches!(extension(path).as_deref(), Some("xopp") | Some("xoj")) {
+        let tolerance = strokes_args.value_of("Strokes.tolerance").unwrap();
+        let strokes = read_strokes(path, tolerance.parse().unwrap())?;
+        print_recognized(&formats, &send(strokes).await?);
+        return Ok(());
+    }
+
+    // NOTE: The notes are sent page by page and the pages are separated by an empty line
+    let numbers = |id: &str| {
+        strokes_args
+            .values_of(id)
+            .map(|values| values.map(|value| value.parse().unwrap()).collect())
+    };
+    let images = strokes_args.value_of("XoppOptions.images").map(Path::new);
+    let xopp_options = XoppOptions {
+        pages: numbers("XoppOptions.pages"),
+        layers: numbers("XoppOptions.layers"),
+        highlighter: strokes_args.is_present("XoppOptions.highlighter"),
+        images: images.is_some(),
+    };
+    let content = std::fs::read(path).with_context(|| format!("can not read {:?}", path))?;
+    let pages = read_xopp(&content, &xopp_options)?;
+    if let Some(images) = images {
+        std::fs::create_dir_all(images).with_context(|| format!("can not create {:?}", images))?;
+    }
+    for (index, page) in pages.into_iter().enumerate() {
+        if index > 0 {
+            println!();
+        }
+        let number = page.number;
+        if let Some(strokes) = page.strokes {
+            let recognized = send(strokes)
+                .await
+                .with_context(|| format!("page {} of {:?}", number, path))?;
+            print_recognized(&formats, &recognized);
+        }
+        for (index, image) in page.images.iter().enumerate() {
+            let image_path =
+                images
+                    .unwrap()
+                    .join(format!("page-{}-image-{}.png", page.number, index + 1));
+            std::fs::write(&image_path, &image.png)
+                .with_context(|| format!("can not write {:?}", image_path))?;
+            println!("![]({})", image_path.display());
+        }
+    }
+    Ok(())
+}
+
+/// Outputs of the _strokes_ endpoint or of the _text_ endpoint with the rendered strokes
+struct Recognized {
+    text: Option<String>,
+    html: Option<String>,
+    data: Option<Vec<Data>>,
+}
+
+/**
+Preprocess and send the strokes (the preprocessing is reported on the standard error). When the
+_strokes_ endpoint fails or recognizes no text and the `fallback` is given, the strokes are rendered
+with it and sent to the _text_ endpoint.
+*/
+async fn send_strokes(
+    header: &AuthHeader,
+    options: &StrokesOptions,
+    preprocess: &PreprocessOptions,
+    fallback: Option<&RenderOptions>,
+    mut strokes: StrokeSet,
+) -> anyhow::Result<Recognized> {
+    if !preprocess.is_empty() {
+        eprintln!("{}", strokes.preprocess(preprocess)?);
+    }
+    // NOTE: The strokes are only rendered when the strokes endpoint fails
+    let fallback = fallback.map(|render| (strokes.clone(), render));
+    let result = Strokes::new(Some(options.clone()), strokes)?
+        .send_request(header.clone())
+        .await;
+    let failure = match result {
+        Ok(response) => match response.error {
+            Some(error) => anyhow!(error),
+            None if fallback.is_some()
+                && response.text.as_deref().unwrap_or("").trim().is_empty() =>
+            {
+                anyhow!("no text was recognized in the strokes")
+            }
+            None => {
+                return Ok(Recognized {
+                    text: response.text,
+                    html: response.html,
+                    data: response.data,
+                })
+            }
+        },
+        Err(error) => error.into(),
+    };
+    let image = match fallback {
+        Some((strokes, render)) => strokes.to_image_src(render).map_err(|error| {
+            anyhow!(
+                "{}, and the strokes could not be rendered: {}",
+                failure,
+                error
+            )
+        })?,
+        None => return Err(failure),
+    };
+
+    eprintln!(
+        "{}, sending the rendered strokes to the text endpoint",
+        failure
+    );
+    let mut text_options = TextOptions::default();
+    let formats = options.formats.iter().flatten().map(|format| match format {
+        StrokesFormats::Text => "text",
+        StrokesFormats::Html => "html",
+        StrokesFormats::Data => "data",
+    });
+    text_options.add_formats_from_strings(formats)?;
+    text_options.data_options = options.data_options.clone();
+    let response = Text::new(Some(text_options), image)?
+        .send_request(header.clone())
+        .await?;
+    match response.error {
+        Some(error) => Err(anyhow!(error)),
+        None => Ok(Recognized {
+            text: response.text,
+            html: response.html,
+            data: response.data,
+        }),
+    }
+}
+
+fn print_recognized(formats: &[&str], recognized: &Recognized) {
+    for format in formats {
+        match *format {
+            "text" => println!("{}", recognized.text.as_deref().unwrap_or_default()),
+            "html" => println!("{}", recognized.html.as_deref().unwrap_or_default()),
+            _ => {
+                for data in recognized.data.iter().flatten() {
+                    println!("{}\t{}", data.r#type, data.value);
+                }
+            }
+        }
+    }
+}
+
+fn extension(path: &Path) -> Option<String> {
+    path.extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+}
+
+/**
+Read the strokes from the `path` in the format given by its extension. The curves of SVG paths are
+flattened with the `tolerance`.
+*/
+fn read_strokes(path: &Path, tolerance: f64) -> anyhow::Result<StrokeSet> {
+    let content =
+        std::fs::read_to_string(path).with_context(|| format!("can not read {:?}", path))?;
+    let strokes = match extension(path).as_deref() {
+        Some("inkml") => StrokeSet::from_inkml(&content)?,
+        Some("svg") => StrokeSet::from_svg(&content, tolerance)?,
+        Some("json") => serde_json::from_str(&content)?,
+        _ => {
+            return Err(anyhow!(
+                "unknown strokes format of {:?} (expected .inkml, .xopp, .svg or .json)",
+                path
+            ))
+        }
+    };
+    Ok(strokes)
+}
+// }}}
+
+// pdf {{{
+async fn pdf(args: &ArgMatches, pdf_args: &ArgMatches) -> anyhow::Result<()> {
+    match pdf_args.subcommand() {
+        Some(("status", status_args)) => {
+            let job = PdfJob::new(
+                status_args.value_of("PDF.pdf_id").unwrap(),
+                auth_header(args)?,
+            );
+            let status = job.status().await?;
+            println!(
+                "{}: {:?} ({}/{} pages, {:.0}%)",
+                job.pdf_id(),
+                status.status,
+                status.num_pages_completed.unwrap_or(0),
+                status.num_pages.unwrap_or(0),
+                status.percent_done.unwrap_or(0.),
+            );
+            Ok(())
+        }
+        Some(("fetch", fetch_args)) => {
+            let job = PdfJob::new(
+                fetch_args.value_of("PDF.pdf_id").unwrap(),
+                auth_header(args)?,
+            );
+            let poll = poll_options(fetch_args, "PDF.timeout")?;
+            job.wait(&poll, |status| {
+                eprint!(
+                    "\r{}/{} pages processed",
+                    status.num_pages_completed.unwrap_or(0),
+                    status.num_pages.unwrap_or(0)
+                )
+            })
+            .await?;
+            eprintln!();
+
+            if let Some(dir) = fetch_args.value_of("PDF.extract") {
+                let mut options = ExtractOptions::default();
+                options
+                    .force(fetch_args.is_present("PDF.force"))
+                    .rename_images(fetch_args.is_present("PDF.rename_images"));
+                let project = job.extract_tex_zip(dir, &options).await?;
+                println!("{}", project.main_tex_path().display());
+                for image in &project.images {
+                    eprintln!("{}", project.dir.join(image).display());
+                }
+                // NOTE: Only download the other formats when they are requested explicitly
+                if fetch_args.occurrences_of("PDF.output_formats") == 0 {
+                    return Ok(());
+                }
+            }
+
+            let formats: Vec<PDFOutputFormat> = fetch_args
+                .values_of("PDF.output_formats")
+                .unwrap()
+                .filter_map(PDFOutputFormat::from_extension)
+                .collect();
+            let output = fetch_args.value_of("PDF.output").map(PathBuf::from);
+            fetch_results(&job, &formats, output.as_deref()).await
+        }
+        Some(("list", list_args)) => {
+            if list_args.is_present("PDF.local") {
+                for record in JobRecord::read_all()? {
+                    println!("{}\t{}", record.pdf_id, record.src);
+                }
+                return Ok(());
+            }
+            let mut query = PDFResultsQuery::default();
+            if let Some(page) = list_args.value_of("PDFResultsQuery.page") {
+                query.page(page.parse().context("invalid page")?);
+            }
+            if let Some(per_page) = list_args.value_of("PDFResultsQuery.per_page") {
+                query.per_page(
+                    per_page
+                        .parse()
+                        .context("invalid number of results per page")?,
+                );
+            }
+            if let Some(from_date) = list_args.value_of("PDFResultsQuery.from_date") {
+                query.from_date(from_date);
+            }
+            if let Some(to_date) = list_args.value_of("PDFResultsQuery.to_date") {
+                query.to_date(to_date);
+            }
+            for pdf in query.send(auth_header(args)?).await?.pdfs {
+                println!(
+                    "{}\t{:?}\t{}/{}\t{}\t{}",
+                    pdf.id,
+                    pdf.status,
+                    pdf.num_pages_completed.unwrap_or(0),
+                    pdf.num_pages.unwrap_or(0),
+                    pdf.created_at.unwrap_or_default(),
+                    pdf.input_file.unwrap_or_default(),
+                );
+            }
+            Ok(())
+        }
+        Some(("delete", delete_args)) => {
+            let header = auth_header(args)?;
+            let recorded = delete_args.is_present("PDF.recorded");
+            // NOTE: Only `--recorded` needs the record, the given IDs are deleted without it
+            let mut records = match JobRecord::read_all() {
+                Ok(records) => Some(records),
+                Err(error) if !recorded => {
+                    eprintln!(
+                        "warning: the record of the sent PDFs is not updated: {:#}",
+                        error
+                    );
+                    None
+                }
+                Err(error) => return Err(error),
+            };
+            let pdf_ids: Vec<String> = if recorded {
+                records
+                    .iter()
+                    .flatten()
+                    .map(|record| record.pdf_id.clone())
+                    .collect()
+            } else {
+                delete_args
+                    .values_of("PDF.pdf_id")
+                    .unwrap()
+                    .map(String::from)
+                    .collect()
+            };
+            let mut result = Ok(());
+            for pdf_id in pdf_ids {
+                let deleted = PdfJob::new(pdf_id.as_str(), header.clone())
+                    .delete()
+                    .await
+                    .with_context(|| format!("failed to delete {}", pdf_id));
+                if let Err(error) = deleted {
+                    result = Err(error);
+                    break;
+                }
+                if let Some(records) = &mut records {
+                    records.retain(|record| record.pdf_id != pdf_id);
+                }
+                eprintln!("deleted {}", pdf_id);
+            }
+            // NOTE: The PDFs deleted before a failure are removed from the record as well
+            if let Some(records) = &records {
+                if let Err(error) = JobRecord::write_all(records) {
+                    match result {
+                        Ok(()) => result = Err(error),
+                        Err(_) => eprintln!(
+                            "warning: the record of the sent PDFs is not updated: {:#}",
+                            error
+                        ),
+                    }
+                }
+            }
+            result
+        }
+        _ => {
+            let src_arg = pdf_args.value_of("PDF.src").unwrap();
+            let src = pdf_src(src_arg)?;
+            let streaming = pdf_args.is_present("PDFOptions.streaming");
+            let pdf = PDF::new(Some(pdf_options(pdf_args)?), src)?;
+            let job = pdf.start_job(auth_header(args)?).await?;
+            if let Err(error) = JobRecord::add(job.pdf_id(), src_arg) {
+                eprintln!("warning: the PDF was not recorded: {:#}", error);
+            }
+            if !streaming {
+                println!("{}", job.pdf_id());
+                return Ok(());
+            }
+            eprintln!("{}", job.pdf_id());
+            let mut pages = job.stream_pages().await?;
+            let mut stdout = std::io::stdout();
+            while let Some(page) = pages.next().await {
+                stdout.write_all(page?.text.as_bytes())?;
+                stdout.flush()?;
+            }
+            Ok(())
+        }
+    }
+}
+
+// JobRecord {{{
+/**
+A PDF sent by the CLI. The record of the sent PDFs is kept in the `MATHPIX_JOBS_FILE` or in
+`$XDG_DATA_HOME/mathpixcli/pdf_jobs.json` so that they can be deleted from the server later.
+*/
+#[derive(Debug, Serialize, Deserialize)]
+struct JobRecord {
+    pdf_id: String,
+    /// URL or path of the PDF as it was given on the command line
+    src: String,
+}
+
+impl JobRecord {
+    fn path() -> anyhow::Result<PathBuf> {
+        if let Some(path) = std::env::var_os("MATHPIX_JOBS_FILE") {
+            return Ok(PathBuf::from(path));
+        }
+        let data_dir = std::env::var_os("XDG_DATA_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|home| Path::new(&home).join(".local/share")))
+            .ok_or_else(|| anyhow!("can not find the data directory (set MATHPIX_JOBS_FILE)"))?;
+        Ok(data_dir.join("mathpixcli").join("pdf_jobs.json"))
+    }
+
+    fn read_all() -> anyhow::Result<Vec<JobRecord>> {
+        let path = JobRecord::path()?;
+        match std::fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)
+                .with_context(|| format!("invalid record of the sent PDFs {:?}", path)),
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(error) => Err(error.into()),
+        }
+    }
+
+    fn write_all(records: &[JobRecord]) -> anyhow::Result<()> {
+        let path = JobRecord::path()?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(records)?)?;
+        Ok(())
+    }
+
+    fn add(pdf_id: &str, src: &str) -> anyhow::Result<()> {
+        let mut records = JobRecord::read_all()?;
+        records.push(JobRecord {
+            pdf_id: pdf_id.to_string(),
+            src: src.to_string(),
+        });
+        JobRecord::write_all(&records)
+    }
+} // }}}
+
+/// The PDF source is an URL when it starts with `http://` or `https://` and a path otherwise
+fn pdf_src(src: &str) -> anyhow::Result<PDFSrc> {
+    if src.starts_with("http://") || src.starts_with("https://") {
+        Ok(PDFSrc::from(
+            reqwest::Url::parse(src).with_context(|| format!("invalid URL {:?}", src))?,
+        ))
+    } else {
+        Ok(PDFSrc::try_from(PathBuf::from(src))?)
+    }
+}
+
+/// Collect the `PDFOptions` from the arguments of the `pdf` subcommand
+fn pdf_options(pdf_args: &ArgMatches) -> anyhow::Result<PDFOptions> {
+    let mut options = PDFOptions::default();
+    if let Some(formats) = pdf_args.values_of("PDFOptions.conversion_formats") {
+        options.add_conversion_formats_from_strings(formats)?;
+    }
+    if let Some(mut delimiters) = pdf_args.values_of("PDFOptions.math_inline_delimiters") {
+        options.math_inline_delimiters(delimiters.next().unwrap(), delimiters.next().unwrap())?;
+    }
+    if let Some(mut delimiters) = pdf_args.values_of("PDFOptions.math_display_delimiters") {
+        options.math_display_delimiters(delimiters.next().unwrap(), delimiters.next().unwrap())?;
+    }
+    if pdf_args.is_present("PDFOptions.rm_spaces") {
+        options.rm_spaces(false);
+    }
+    if pdf_args.is_present("PDFOptions.rm_fonts") {
+        options.rm_fonts(true);
+    }
+    if let Some(alphabets) = pdf_args.values_of("PDFOptions.alphabets_allowed") {
+        options.alphabets_allowed(&alphabets.collect::<Vec<_>>())?;
+    }
+    if let Some(pages) = pdf_args.value_of("PDFOptions.page_ranges") {
+        options.page_ranges_from_string(pages)?;
+    }
+    if pdf_args.is_present("PDFOptions.enable_tables_fallback") {
+        options.enable_tables_fallback(true);
+    }
+    if pdf_args.is_present("PDFOptions.numbers_default_to_math") {
+        options.numbers_default_to_math(true);
+    }
+    if pdf_args.is_present("PDFOptions.auto_number_sections") {
+        options.auto_number_sections(true);
+    }
+    if pdf_args.is_present("PDFOptions.streaming") {
+        options.streaming(true);
+    }
+    Ok(options)
+}
+
+/**
+Download the results in `formats`. A single format is written to `output` or to stdout. Multiple
+formats are written as `ID.FORMAT` files into the `output` directory or the current directory.
+*/
+async fn fetch_results(
+    job: &PdfJob,
+    formats: &[PDFOutputFormat],
+    output: Option<&Path>,
+) -> anyhow::Result<()> {
+    match (formats, output) {
+        ([format], Some(path)) if !path.is_dir() => job.download_to_file(*format, path).await?,
+        ([format], None) => std::io::stdout().write_all(&job.download(*format).await?)?,
+        (formats, output) => {
+            let dir = output.unwrap_or_else(|| Path::new("."));
+            for format in formats {
+                let path = dir.join(format!("{}.{}", job.pdf_id(), format.extension()));
+                job.download_to_file(*format, &path).await?;
+                eprintln!("{}", path.display());
+            }
+        }
+    }
+    Ok(())
+}
+// }}}
+
+async fn convert(args: &ArgMatches, convert_args: &ArgMatches) -> anyhow::Result<()> {
+    let src = convert_args.value_of("Converter.src").unwrap();
+    let source = Some(Path::new(src)).filter(|_| src != "-");
+    let mmd = match source {
+        Some(path) => {
+            std::fs::read_to_string(path).with_context(|| format!("can not read {:?}", src))?
+        }
+        None => {
+            let mut mmd = String::new();
+            std::io::Read::read_to_string(&mut std::io::stdin(), &mut mmd)?;
+            mmd
+        }
+    };
+    let mut options = ConverterOptions::default();
+    options
+        .add_formats_from_strings(convert_args.values_of("ConverterOptions.formats").unwrap())?;
+    let formats = options.formats.enabled();
+    let poll = poll_options(convert_args, "Converter.timeout")?;
+    let output = convert_args.value_of("Converter.output").map(PathBuf::from);
+
+    // NOTE: Checked before the conversion starts, the stem of a source always exists
+    if let Some(source) = source {
+        for (_, path) in conversion_paths(source, output.clone(), &formats, "") {
+            if same_file(&path, source) {
+                return Err(anyhow!(
+                    "the conversion would overwrite the source {:?}, choose another output with \
+                     --output",
+                    src
+                ));
+            }
+        }
+    }
+
+    let job = Converter::new(Some(options), mmd)?
+        .start_job(auth_header(args)?)
+        .await?;
+    eprintln!("{}", job.conversion_id());
+    job.wait(&poll, |status| {
+        eprint!(
+            "\r{}/{} formats converted",
+            status.num_completed(),
+            formats.len()
+        )
+    })
+    .await?;
+    eprintln!();
+
+    let paths = conversion_paths(
+        source.unwrap_or_else(|| Path::new("")),
+        output,
+        &formats,
+        job.conversion_id(),
+    );
+    for (format, path) in paths {
+        job.download_to_file(format, &path).await?;
+        println!("{}", path.display());
+    }
+    Ok(())
+}
+
+/**
+Files that the `formats` are downloaded to. The results are named after the `source` so that
+`notes.mmd` becomes `notes.docx`, `fallback` is the name when the source has no file name (e.g. it
+is read from stdin).
+*/
+fn conversion_paths(
+    source: &Path,
+    output: Option<PathBuf>,
+    formats: &[ConversionFormat],
+    fallback: &str,
+) -> Vec<(ConversionFormat, PathBuf)> {
+    let stem = source.file_stem().map_or_else(
+        || fallback.to_string(),
+        |stem| stem.to_string_lossy().into_owned(),
+    );
+    let dir = source.parent().unwrap_or_else(|| Path::new("."));
+    match (formats, output) {
+        ([format], Some(path)) if !path.is_dir() => vec![(*format, path)],
+        (formats, output) => {
+            let dir = output.unwrap_or_else(|| dir.to_path_buf());
+            formats
+                .iter()
+                .map(|format| (*format, dir.join(format!("{}.{}", stem, format))))
+                .collect()
+        }
+    }
+}
+
+/// Both paths are the same existing file
+fn same_file(a: &Path, b: &Path) -> bool {
+    matches!((a.canonicalize(), b.canonicalize()), (Ok(a), Ok(b)) if a == b)
 }
@@ -1,6 +1,26 @@
+use super::super::shared_objects::request::CallBackError;
+use reqwest;
+use serde_json;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum BatchError {
-    // TODO:  <21-07-21, kunzaatko> //
+    #[error("SerializationError: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("RequestError: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("CallBackError: {0}")]
+    CallBack(#[from] CallBackError),
+    #[error("NoUrls: the batch has to contain at least one URL")]
+    NoUrls,
+    #[error("ProcessingError: {0}")]
+    Processing(String),
+    #[error("Timeout: the batch was not processed in {0:?}")]
+    Timeout(std::time::Duration),
+}
+
+impl From<std::convert::Infallible> for BatchError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
 }
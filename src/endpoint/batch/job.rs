@@ -0,0 +1,175 @@
+use super::super::super::{header::AuthHeader, MATHPIX_APIURL};
+pub use super::super::poll::PollOptions;
+use super::super::poll::{wait_for, JobError, JobState, JobStatus};
+use super::error::BatchError;
+use super::response::BatchStatus;
+use reqwest::{header::HeaderMap, Url};
+use std::time::Duration;
+
+// BatchJob {{{
+/**
+Handle to a batch that is being processed by the server. It is obtained by sending a
+[super::BatchBody] with [super::BatchBody::start_job] or from the `batch_id` of a batch that was
+sent earlier with [BatchJob::new].
+*/
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    batch_id: String,
+    header: AuthHeader,
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl BatchJob {
+    pub fn new<S: Into<String>, H: Into<AuthHeader>>(batch_id: S, header: H) -> Self {
+        BatchJob {
+            batch_id: batch_id.into(),
+            header: header.into(),
+            base_url: Url::parse(MATHPIX_APIURL).unwrap(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// ID of the batch on the server
+    pub fn batch_id(&self) -> &str {
+        &self.batch_id
+    }
+
+    /// Request the results of the batch that have been processed so far
+    pub async fn status(&self) -> Result<BatchStatus, BatchError> {
+        //{{{
+        let url = self
+            .base_url
+            .join(&format!("batch/{}", self.batch_id))
+            .unwrap();
+        let headers: HeaderMap = self.header.clone().into();
+        Ok(self
+            .client
+            .get(url)
+            .headers(headers)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    } //}}}
+
+    /**
+    Poll the results of the batch until all of the images are processed. `on_progress` is called
+    with every status that is received.
+
+    Fails with `BatchError::Timeout` if the batch is not processed before the timeout of the
+    `poll` options.
+    */
+    pub async fn wait<F>(
+        &self,
+        poll: &PollOptions,
+        on_progress: F,
+    ) -> Result<BatchStatus, BatchError>
+    where
+        F: FnMut(&BatchStatus),
+    {
+        wait_for(poll, || self.status(), on_progress).await
+    }
+} // }}}
+
+impl JobStatus for BatchStatus {
+    fn state(&self) -> JobState {
+        // NOTE: The errors of the images are reported in their results, only a status without the
+        // batch fails
+        if let Some(error) = &self.error {
+            JobState::Failed(error.clone())
+        } else if self.is_complete() {
+            JobState::Completed
+        } else {
+            JobState::Pending
+        }
+    }
+}
+
+impl JobError for BatchError {
+    fn failed(message: String) -> Self {
+        BatchError::Processing(message)
+    }
+
+    fn timeout(elapsed: Duration) -> Self {
+        BatchError::Timeout(elapsed)
+    }
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod batch_job_tests {
+    use super::super::super::poll::fast_poll;
+    use super::super::super::test_server::{Reply, TestServer};
+    use super::{BatchError, BatchJob, PollOptions};
+    use crate::header::AuthHeader;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn wait_for_results() {
+        //{{{
+        let polls = Arc::new(AtomicU32::new(0));
+        let server_polls = polls.clone();
+        let server = TestServer::spawn(move |request| {
+            assert_eq!(request.path, "/batch/17");
+            let results = match server_polls.fetch_add(1, Ordering::SeqCst) {
+                0 => json!({}),
+                1 => json!({"a": {"text": "\\( x \\)"}}),
+                _ => json!({"a": {"text": "\\( x \\)"}, "b": {"error": "Image too large"}}),
+            };
+            Reply::json(json!({"keys": ["a", "b"], "results": results}))
+        })
+        .await;
+
+        let job = BatchJob::new("17", AuthHeader::new("id", "key")).with_base_url(server.url);
+        let mut progress = Vec::new();
+        let status = job
+            .wait(&fast_poll(), |status| progress.push(status.results.len()))
+            .await
+            .unwrap();
+        assert_eq!(progress, vec![0, 1, 2]);
+        assert_eq!(status.results["a"].text.as_deref(), Some("\\( x \\)"));
+        assert_eq!(
+            status.results["b"].error.as_deref(),
+            Some("Image too large")
+        );
+    } //}}}
+
+    #[tokio::test]
+    async fn wait_error() {
+        //{{{
+        let server = TestServer::spawn(|_| Reply::json(json!({"error": "Batch not found"}))).await;
+        let job = BatchJob::new("17", AuthHeader::new("id", "key")).with_base_url(server.url);
+        assert!(matches!(
+            job.wait(&fast_poll(), |_| {}).await,
+            Err(BatchError::Processing(message)) if message == "Batch not found"
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn wait_timeout() {
+        //{{{
+        let server =
+            TestServer::spawn(|_| Reply::json(json!({"keys": ["a"], "results": {}}))).await;
+        let job = BatchJob::new("17", AuthHeader::new("id", "key")).with_base_url(server.url);
+        let poll = PollOptions {
+            timeout: Some(Duration::from_millis(30)),
+            ..fast_poll()
+        };
+        assert!(matches!(
+            job.wait(&poll, |_| {}).await,
+            Err(BatchError::Timeout(_))
+        ));
+    } //}}}
+}
+// }}}
@@ -1,13 +1,158 @@
 mod error;
+mod job;
 mod options;
 mod response;
 
-use error::BatchError;
-pub use options::BatchOptions;
-use response::BatchResponse;
-use serde::Serialize;
+use super::shared_objects::request::{expand_callback, TemplateVars};
+use super::{super::MATHPIX_APIURL, MathpixEndpoint};
+pub use error::BatchError;
+pub use job::{BatchJob, PollOptions};
+pub use options::{BatchOptions, LaTeXFormats, LaTeXOptions};
+use reqwest::Url;
+pub use response::{BatchResponse, BatchStatus, LaTeXResponse};
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::convert::TryInto;
 
-#[derive(Debug, Serialize)]
+// BatchBody {{{
+#[derive(Serialize, Debug)]
+/// This structs contains the possible items that the _batch_ endpoint accepts
 pub struct BatchBody {
-    // TODO:  <21-07-21, kunzaatko> //
+    /// > Key-value for each image in the batch where the value may be a public URL or a data URL
+    #[serde(serialize_with = "serialize_urls")]
+    pub urls: BTreeMap<String, Url>,
+    /// Configuration options for the _batch_ endpoint
+    #[serde(flatten)]
+    pub options: BatchOptions,
 }
+
+impl BatchBody {
+    /// Add the image at `url` to the batch. Its result is under the `key`.
+    pub fn add_url<S: Into<String>>(&mut self, key: S, url: Url) -> &mut Self {
+        self.urls.insert(key.into(), url);
+        self
+    }
+
+    /**
+    Send the batch to the server and return a [BatchJob] handle for polling the results of the
+    images.
+    */
+    pub async fn start_job<H: Into<super::AuthHeader>>(
+        &self,
+        header: H,
+    ) -> Result<BatchJob, BatchError> {
+        let header = header.into();
+        let response = self.send_request(header.clone()).await?;
+        match response.batch_id {
+            Some(batch_id) => Ok(BatchJob::new(batch_id, header)),
+            None => {
+                Err(BatchError::Processing(response.error.unwrap_or_else(
+                    || "no batch_id in the response".to_string(),
+                )))
+            }
+        }
+    }
+}
+
+fn serialize_urls<S: Serializer>(
+    urls: &BTreeMap<String, Url>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_map(urls.iter().map(|(key, url)| (key, url.as_str())))
+}
+
+impl MathpixEndpoint for BatchBody {
+    //{{{
+    type Src = BTreeMap<String, Url>;
+    type Error = BatchError;
+    type Options = BatchOptions;
+    type Response = BatchResponse;
+
+    fn new<S, E>(options: Option<Self::Options>, src: S) -> Result<Self, Self::Error>
+    where
+        S: TryInto<Self::Src, Error = E>,
+        Self::Error: From<E>,
+        Self: Sized,
+    {
+        let urls = src.try_into()?;
+        if urls.is_empty() {
+            return Err(BatchError::NoUrls);
+        }
+        Ok(Self {
+            urls,
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    fn url(&self) -> reqwest::Url {
+        let mut url_str = MATHPIX_APIURL.to_string();
+        url_str.push_str("batch");
+        reqwest::Url::parse(&url_str).unwrap()
+    }
+
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error> {
+        let callback = self.options.latex_options.callback.as_ref();
+        let body = expand_callback::<_, Self::Error>(self, callback, &TemplateVars::now())?;
+        Ok(reqwest::Client::new().post(self.url()).json(&body))
+    }
+
+    fn options(&mut self) -> &mut Self::Options {
+        &mut self.options
+    }
+
+    fn src(&mut self) -> Option<&mut Self::Src> {
+        Some(&mut self.urls)
+    }
+} //}}}
+  // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod batch_endpoint_tests {
+    use super::{BatchBody, BatchError, BatchOptions, LaTeXFormats, MathpixEndpoint};
+    use crate::header::AuthHeader;
+    use reqwest::Url;
+    use serde_json::json;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn serialize_batch() {
+        //{{{
+        let mut urls = BTreeMap::new();
+        urls.insert(
+            "inverted".to_string(),
+            Url::parse("https://mathpix.com/examples/inverted.jpg").unwrap(),
+        );
+        let mut options = BatchOptions::default();
+        options.formats(vec![LaTeXFormats::LaTeXSimplified]);
+        let mut batch = BatchBody::new(Some(options), urls).unwrap();
+        batch.add_url(
+            "algebra",
+            Url::parse("https://mathpix.com/examples/algebra.jpg").unwrap(),
+        );
+
+        let request = batch.to_request(AuthHeader::new("id", "key")).unwrap();
+        assert_eq!(request.url().as_str(), "https://api.mathpix.com/v3/batch");
+        let body: serde_json::Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(
+            body["urls"],
+            json!({
+                "algebra": "https://mathpix.com/examples/algebra.jpg",
+                "inverted": "https://mathpix.com/examples/inverted.jpg",
+            })
+        );
+        assert_eq!(body["formats"], json!(["latex_simplified"]));
+        assert_eq!(body["callback"], json!(null));
+    } //}}}
+
+    #[test]
+    fn empty_batch() {
+        //{{{
+        assert!(matches!(
+            BatchBody::new(None, BTreeMap::new()),
+            Err(BatchError::NoUrls)
+        ));
+    } //}}}
+}
+// }}}
@@ -1,3 +1,48 @@
+pub use super::super::latex::{LaTeXFormats, LaTeXOptions};
+use serde::Serialize;
+
+// BatchOptions {{{
+/// > The request body may contain all the /v3/latex parameters except src
+#[derive(Debug, Serialize, PartialEq, Default)]
 pub struct BatchOptions {
-    // TODO:  <21-07-21, kunzaatko> //
+    /// > String postprocessing formats (see [Formatting](https://docs.mathpix.com/?shell#formatting-2) section)
+    pub formats: Option<Vec<LaTeXFormats>>,
+    /// Options of the _latex_ endpoint that are applied to every image of the batch
+    #[serde(flatten)]
+    pub latex_options: LaTeXOptions,
 }
+
+impl BatchOptions {
+    field_builder![formats, Vec<LaTeXFormats>];
+
+    /// Options of the _latex_ endpoint that are applied to every image of the batch
+    pub fn latex_options(&mut self) -> &mut LaTeXOptions {
+        &mut self.latex_options
+    }
+}
+// }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod batch_options_tests {
+    use super::super::super::latex::Ocr;
+    use super::{BatchOptions, LaTeXFormats};
+    use serde_json::json;
+
+    #[test]
+    fn serialize_batch_options() {
+        //{{{
+        let mut options = BatchOptions::default();
+        options.formats(vec![LaTeXFormats::LaTeXSimplified]);
+        options
+            .latex_options()
+            .ocr(vec![Ocr::Math])
+            .skip_recrop(true);
+        let serialized = serde_json::to_value(&options).unwrap();
+        assert_eq!(serialized["formats"], json!(["latex_simplified"]));
+        assert_eq!(serialized["ocr"], json!(["math"]));
+        assert_eq!(serialized["skip_recrop"], json!(true));
+        assert!(serialized.get("latex_options").is_none());
+    } //}}}
+}
+// }}}
@@ -1,3 +1,113 @@
+pub use super::super::latex::LaTeXResponse;
+pub use super::super::shared_objects::response::ErrorInfo;
+use serde::{Deserialize, Deserializer};
+use std::collections::BTreeMap;
+
+// pub struct BatchResponse {{{
+/// Response of the server to a batch request. The results have to be requested with the returned
+/// `batch_id`.
+#[derive(Debug, Deserialize)]
 pub struct BatchResponse {
-    // TODO:  <21-07-21, kunzaatko> //
+    /// ID of the batch to get the results with
+    #[serde(default, deserialize_with = "string_or_number")]
+    pub batch_id: Option<String>,
+    /// US locale error message
+    pub error: Option<String>,
+    /// Error info object
+    pub error_info: Option<ErrorInfo>,
+} // }}}
+
+// pub struct BatchStatus {{{
+/**
+> The response to GET `v3/batch/{batch_id}` contains the `keys` of the batch and the `results`
+> for the keys that have been processed so far.
+*/
+#[derive(Debug, Deserialize)]
+pub struct BatchStatus {
+    /// Keys of all of the images in the batch
+    #[serde(default)]
+    pub keys: Vec<String>,
+    /// Results of the images that have been processed by their keys
+    #[serde(default)]
+    pub results: BTreeMap<String, LaTeXResponse>,
+    /// US locale error message, e.g. when the batch does not exist
+    pub error: Option<String>,
+    /// Error info object
+    pub error_info: Option<ErrorInfo>,
 }
+
+impl BatchStatus {
+    /// Whether all of the images of the batch have been processed. A status without keys is not
+    /// complete, every batch has at least one image.
+    pub fn is_complete(&self) -> bool {
+        !self.keys.is_empty() && self.keys.iter().all(|key| self.results.contains_key(key))
+    }
+
+    /// Keys of the images that have not been processed yet
+    pub fn pending_keys(&self) -> impl Iterator<Item = &str> {
+        self.keys
+            .iter()
+            .filter(move |key| !self.results.contains_key(*key))
+            .map(String::as_str)
+    }
+} // }}}
+
+/// The `batch_id` is a string in the API documentation but it is a number in some responses
+fn string_or_number<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<String>, D::Error> {
+    Ok(
+        match Option::<serde_json::Value>::deserialize(deserializer)? {
+            Some(serde_json::Value::String(id)) => Some(id),
+            Some(serde_json::Value::Number(id)) => Some(id.to_string()),
+            _ => None,
+        },
+    )
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod batch_response_tests {
+    use super::{BatchResponse, BatchStatus};
+    use serde_json::json;
+
+    #[test]
+    fn deserialize_batch_response() {
+        //{{{
+        let response: BatchResponse = serde_json::from_value(json!({"batch_id": "17"})).unwrap();
+        assert_eq!(response.batch_id.as_deref(), Some("17"));
+        let response: BatchResponse = serde_json::from_value(json!({"batch_id": 17})).unwrap();
+        assert_eq!(response.batch_id.as_deref(), Some("17"));
+        let response: BatchResponse =
+            serde_json::from_value(json!({"error": "Invalid credentials"})).unwrap();
+        assert!(response.batch_id.is_none());
+    } //}}}
+
+    #[test]
+    fn deserialize_batch_status() {
+        //{{{
+        let status: BatchStatus = serde_json::from_value(json!({
+            "keys": ["algebra", "inverted"],
+            "results": {
+                "algebra": {
+                    "latex_simplified": "12 + 5 x - 8 = 12 x - 10",
+                    "latex_confidence": 0.99640350138238
+                }
+            }
+        }))
+        .unwrap();
+        assert!(!status.is_complete());
+        assert_eq!(status.pending_keys().collect::<Vec<_>>(), vec!["inverted"]);
+        assert_eq!(
+            status.results["algebra"].latex_simplified.as_deref(),
+            Some("12 + 5 x - 8 = 12 x - 10")
+        );
+
+        let status: BatchStatus = serde_json::from_value(json!({})).unwrap();
+        assert!(!status.is_complete());
+        let status: BatchStatus =
+            serde_json::from_value(json!({"error": "Batch not found"})).unwrap();
+        assert_eq!(status.error.as_deref(), Some("Batch not found"));
+    } //}}}
+}
+// }}}
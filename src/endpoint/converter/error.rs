@@ -0,0 +1,33 @@
+use reqwest;
+use serde_json;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConverterError {
+    #[error("SerializationError: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("RequestError: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("OptionsError: {0}")]
+    Options(#[from] ConverterOptionsError),
+    #[error("NoFormats: there has to be at least one format to convert to")]
+    NoFormats,
+    #[error("ProcessingError: {0}")]
+    Processing(String),
+    #[error("Timeout: the conversion was not processed in {0:?}")]
+    Timeout(std::time::Duration),
+}
+
+impl From<std::convert::Infallible> for ConverterError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum ConverterOptionsError {
+    #[error("UnknownConversionFormat: {0} is not a format of the converter endpoint (docx, tex.zip, html, md)")]
+    UnknownFormat(String),
+}
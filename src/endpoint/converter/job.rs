@@ -0,0 +1,253 @@
+use super::super::super::{header::AuthHeader, MATHPIX_APIURL};
+pub use super::super::poll::PollOptions;
+use super::super::poll::{wait_for, JobError, JobState, JobStatus};
+use super::error::ConverterError;
+use super::options::ConversionFormat;
+use super::response::{ConversionStatus, ConverterStatusResponse};
+use reqwest::{header::HeaderMap, Url};
+use std::path::Path;
+use std::time::Duration;
+
+// ConverterJob {{{
+/**
+Handle to a conversion that is being processed by the server. It is obtained by sending a
+[super::Converter] with [super::Converter::start_job] or from the `conversion_id` of a conversion
+that was requested earlier with [ConverterJob::new].
+
+```no_run
+# async fn run() -> Result<(), mathpixapi::endpoint::converter::ConverterError> {
+use mathpixapi::endpoint::converter::{ConversionFormat, ConverterJob, PollOptions};
+use mathpixapi::header::AuthHeader;
+
+let job = ConverterJob::new("2021_09_07_1a2b3c4d5e6f", AuthHeader::new("ID", "KEY"));
+job.wait(&PollOptions::default(), |_| {}).await?;
+job.download_to_file(ConversionFormat::Docx, "notes.docx").await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Debug, Clone)]
+pub struct ConverterJob {
+    conversion_id: String,
+    header: AuthHeader,
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl ConverterJob {
+    pub fn new<S: Into<String>, H: Into<AuthHeader>>(conversion_id: S, header: H) -> Self {
+        ConverterJob {
+            conversion_id: conversion_id.into(),
+            header: header.into(),
+            base_url: Url::parse(MATHPIX_APIURL).unwrap(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Tracking ID of the conversion on the server
+    pub fn conversion_id(&self) -> &str {
+        &self.conversion_id
+    }
+
+    fn endpoint_url(&self, path: &str) -> Url {
+        self.base_url.join(path).unwrap()
+    }
+
+    /// Request the current status of the conversion
+    pub async fn status(&self) -> Result<ConverterStatusResponse, ConverterError> {
+        //{{{
+        let url = self.endpoint_url(&format!("converter/{}", self.conversion_id));
+        let headers: HeaderMap = self.header.clone().into();
+        Ok(self
+            .client
+            .get(url)
+            .headers(headers)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    } //}}}
+
+    /**
+    Poll the status of the conversion until all of the formats are converted. `on_progress` is
+    called with every status that is received.
+
+    Fails with `ConverterError::Processing` if the conversion to any of the formats fails and with
+    `ConverterError::Timeout` if the conversion does not end before the timeout of the `poll`
+    options.
+    */
+    pub async fn wait<F>(
+        &self,
+        poll: &PollOptions,
+        on_progress: F,
+    ) -> Result<ConverterStatusResponse, ConverterError>
+    where
+        F: FnMut(&ConverterStatusResponse),
+    {
+        wait_for(poll, || self.status(), on_progress).await
+    }
+
+    /// Download the result of the conversion in the given format
+    pub async fn download(&self, format: ConversionFormat) -> Result<Vec<u8>, ConverterError> {
+        //{{{
+        let url = self.endpoint_url(&format!("converter/{}.{}", self.conversion_id, format));
+        let headers: HeaderMap = self.header.clone().into();
+        Ok(self
+            .client
+            .get(url)
+            .headers(headers)
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec())
+    } //}}}
+
+    /// Download the result of the conversion in the given format and write it to `path`
+    pub async fn download_to_file<P: AsRef<Path>>(
+        &self,
+        format: ConversionFormat,
+        path: P,
+    ) -> Result<(), ConverterError> {
+        let bytes = self.download(format).await?;
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+} // }}}
+
+impl JobStatus for ConverterStatusResponse {
+    fn state(&self) -> JobState {
+        // NOTE: The results of the other formats are useless when one of the requested ones fails
+        let failed = self
+            .conversion_status
+            .iter()
+            .find(|(_, format)| format.status == ConversionStatus::Error);
+        if let Some((name, format)) = failed {
+            return JobState::Failed(format!(
+                "{}: {}",
+                name,
+                format
+                    .error
+                    .as_deref()
+                    .unwrap_or("unknown conversion error")
+            ));
+        }
+        match self.status {
+            ConversionStatus::Completed => JobState::Completed,
+            ConversionStatus::Error => JobState::Failed(
+                self.error
+                    .clone()
+                    .unwrap_or_else(|| "unknown conversion error".to_string()),
+            ),
+            _ => JobState::Pending,
+        }
+    }
+}
+
+impl JobError for ConverterError {
+    fn failed(message: String) -> Self {
+        ConverterError::Processing(message)
+    }
+
+    fn timeout(elapsed: Duration) -> Self {
+        ConverterError::Timeout(elapsed)
+    }
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod converter_job_tests {
+    use super::super::super::poll::fast_poll;
+    use super::super::super::test_server::{Reply, TestServer};
+    use super::{ConversionFormat, ConverterError, ConverterJob, PollOptions};
+    use crate::header::AuthHeader;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn wait_and_download() {
+        //{{{
+        let polls = Arc::new(AtomicU32::new(0));
+        let server = TestServer::spawn(move |request| match request.path.as_str() {
+            "/converter/abc" => {
+                let status = match polls.fetch_add(1, Ordering::SeqCst) {
+                    0 => "processing",
+                    _ => "completed",
+                };
+                Reply::json(json!({
+                    "status": status,
+                    "conversion_status": {
+                        "docx": {"status": status},
+                        "md": {"status": "completed"}
+                    }
+                }))
+            }
+            "/converter/abc.docx" => Reply::bytes(vec![0x50, 0x4b, 0x03, 0x04]),
+            _ => Reply::status(404),
+        })
+        .await;
+        let job = ConverterJob::new("abc", AuthHeader::new("id", "key")).with_base_url(server.url);
+
+        let mut progress = Vec::new();
+        job.wait(&fast_poll(), |status| progress.push(status.num_completed()))
+            .await
+            .unwrap();
+        assert_eq!(progress, vec![1, 2]);
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.docx");
+        job.download_to_file(ConversionFormat::Docx, &path)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), vec![0x50, 0x4b, 0x03, 0x04]);
+        assert!(matches!(
+            job.download(ConversionFormat::Html).await,
+            Err(ConverterError::Request(_))
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn wait_errors() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/converter/failing" => Reply::json(json!({
+                "status": "processing",
+                "conversion_status": {
+                    "docx": {"status": "processing"},
+                    "tex.zip": {"status": "error", "error": "invalid markdown"}
+                }
+            })),
+            _ => Reply::json(json!({"status": "processing"})),
+        })
+        .await;
+
+        let job = ConverterJob::new("failing", AuthHeader::new("id", "key"))
+            .with_base_url(server.url.clone());
+        assert!(matches!(
+            job.wait(&fast_poll(), |_| {}).await,
+            Err(ConverterError::Processing(message)) if message == "tex.zip: invalid markdown"
+        ));
+
+        let job =
+            ConverterJob::new("stuck", AuthHeader::new("id", "key")).with_base_url(server.url);
+        let poll = PollOptions {
+            timeout: Some(Duration::from_millis(30)),
+            ..fast_poll()
+        };
+        assert!(matches!(
+            job.wait(&poll, |_| {}).await,
+            Err(ConverterError::Timeout(_))
+        ));
+    } //}}}
+}
+// }}}
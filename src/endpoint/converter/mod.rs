@@ -0,0 +1,157 @@
+mod error;
+mod job;
+mod options;
+mod response;
+
+use super::{super::MATHPIX_APIURL, MathpixEndpoint};
+pub use error::{ConverterError, ConverterOptionsError};
+pub use job::{ConverterJob, PollOptions};
+pub use options::{ConversionFormat, ConversionFormats, ConverterOptions};
+pub use response::{ConversionStatus, ConverterResponse, ConverterStatusResponse, FormatStatus};
+use serde::Serialize;
+use std::convert::TryInto;
+
+// Converter {{{
+#[derive(Serialize, Debug)]
+/// This structs contains the possible items that the _converter_ endpoint accepts
+pub struct Converter {
+    /// > Mathpix Markdown to convert
+    #[serde(rename = "mmd")]
+    pub src: String,
+    /// Configuration options for the _converter_ endpoint
+    #[serde(flatten)]
+    pub options: ConverterOptions,
+}
+
+impl Converter {
+    /**
+    Send the Mathpix Markdown to the server and return a [ConverterJob] handle for following the
+    conversion and downloading the results.
+
+    ```no_run
+    # async fn run() -> Result<(), mathpixapi::endpoint::converter::ConverterError> {
+    use mathpixapi::endpoint::converter::{ConversionFormat, Converter, ConverterOptions, PollOptions};
+    use mathpixapi::endpoint::MathpixEndpoint;
+    use mathpixapi::header::AuthHeader;
+
+    let mut options = ConverterOptions::default();
+    options.add_format(ConversionFormat::Docx);
+    let mmd = std::fs::read_to_string("notes.mmd")?;
+    let job = Converter::new(Some(options), mmd)?
+        .start_job(AuthHeader::new("ID", "KEY"))
+        .await?;
+    job.wait(&PollOptions::default(), |_| {}).await?;
+    job.download_to_file(ConversionFormat::Docx, "notes.docx").await?;
+    # Ok(())
+    # }
+    ```
+    */
+    pub async fn start_job<H: Into<super::AuthHeader>>(
+        &self,
+        header: H,
+    ) -> Result<ConverterJob, ConverterError> {
+        let header = header.into();
+        let response = self.send_request(header.clone()).await?;
+        match response.conversion_id {
+            Some(conversion_id) => Ok(ConverterJob::new(conversion_id, header)),
+            None => {
+                Err(ConverterError::Processing(response.error.unwrap_or_else(
+                    || "no conversion_id in the response".to_string(),
+                )))
+            }
+        }
+    }
+}
+
+impl MathpixEndpoint for Converter {
+    //{{{
+    type Src = String;
+    type Error = ConverterError;
+    type Options = ConverterOptions;
+    type Response = ConverterResponse;
+
+    fn new<S, E>(options: Option<Self::Options>, src: S) -> Result<Self, Self::Error>
+    where
+        S: TryInto<String, Error = E>,
+        Self::Error: From<E>,
+        Self: Sized,
+    {
+        Ok(Self {
+            src: src.try_into()?,
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    fn url(&self) -> reqwest::Url {
+        let mut url_str = MATHPIX_APIURL.to_string();
+        url_str.push_str("converter");
+        reqwest::Url::parse(&url_str).unwrap()
+    }
+
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error> {
+        // NOTE: The formats can be added after the construction, so they are only checked here
+        if self.options.formats.enabled().is_empty() {
+            return Err(ConverterError::NoFormats);
+        }
+        Ok(reqwest::Client::new().post(self.url()).json(self))
+    }
+
+    fn options(&mut self) -> &mut Self::Options {
+        &mut self.options
+    }
+
+    fn src(&mut self) -> Option<&mut Self::Src> {
+        Some(&mut self.src)
+    }
+} //}}}
+  // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod converter_endpoint_tests {
+    use super::{
+        ConversionFormat, Converter, ConverterError, ConverterOptions, ConverterOptionsError,
+        MathpixEndpoint,
+    };
+    use crate::header::AuthHeader;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn serialize_converter() {
+        //{{{
+        let mut options = ConverterOptions::default();
+        options
+            .add_format(ConversionFormat::Docx)
+            .add_formats_from_strings(["tex.zip"])
+            .unwrap();
+        let converter = Converter::new(Some(options), "# Notes\n\\( x^2 \\)").unwrap();
+        let request = converter.to_request(AuthHeader::new("id", "key")).unwrap();
+        assert_eq!(
+            request.url().as_str(),
+            "https://api.mathpix.com/v3/converter"
+        );
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body["mmd"], json!("# Notes\n\\( x^2 \\)"));
+        assert_eq!(body["formats"]["docx"], json!(true));
+        assert_eq!(body["formats"]["tex.zip"], json!(true));
+        assert_eq!(body["formats"]["html"], json!(null));
+    } //}}}
+
+    #[test]
+    fn converter_errors() {
+        //{{{
+        let converter = Converter::new(None, "# Notes").unwrap();
+        assert!(matches!(
+            converter.to_request_builder(),
+            Err(ConverterError::NoFormats)
+        ));
+        let mut options = ConverterOptions::default();
+        assert!(matches!(
+            options.add_formats_from_strings(["pptx"]),
+            Err(ConverterError::Options(ConverterOptionsError::UnknownFormat(format))) if format == "pptx"
+        ));
+        assert_eq!(options, ConverterOptions::default());
+    } //}}}
+}
+// }}}
@@ -0,0 +1,53 @@
+pub use super::super::pdf::{ConversionFormat, ConversionFormats};
+use super::error::{ConverterError, ConverterOptionsError};
+use serde::Serialize;
+
+// ConverterOptions {{{
+/// Configuration options for the _converter_ endpoint
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct ConverterOptions {
+    /// > Formats to convert the Mathpix Markdown to
+    pub formats: ConversionFormats,
+}
+
+impl ConverterOptions {
+    pub fn add_format(&mut self, format: ConversionFormat) -> &mut Self {
+        self.formats.set(format, true);
+        self
+    }
+
+    /// Add formats to convert to
+    /// * possible inputs are "docx", "tex.zip", "html" and "md"
+    ///
+    /// # Examples
+    /// ```
+    /// use mathpixapi::endpoint::converter::{ConversionFormat, ConverterOptions};
+    /// let mut options = ConverterOptions::default();
+    /// options.add_formats_from_strings(&["docx", "tex.zip"]).unwrap();
+    /// assert_eq!(
+    ///     options.formats.enabled(),
+    ///     vec![ConversionFormat::Docx, ConversionFormat::TexZip]
+    /// );
+    /// ```
+    pub fn add_formats_from_strings<S, I: IntoIterator<Item = S>>(
+        &mut self,
+        formats: I,
+    ) -> Result<&mut Self, ConverterError>
+    where
+        S: AsRef<str>,
+    {
+        //{{{
+        let formats = formats
+            .into_iter()
+            .map(|format| {
+                ConversionFormat::from_name(format.as_ref())
+                    .map_err(|_| ConverterOptionsError::UnknownFormat(format.as_ref().to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        for format in formats {
+            self.add_format(format);
+        }
+        Ok(self)
+    } //}}}
+}
+// }}}
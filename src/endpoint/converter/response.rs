@@ -0,0 +1,100 @@
+pub use super::super::shared_objects::response::ErrorInfo;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+// pub struct ConverterResponse {{{
+/// Response of the server to a conversion request. The conversion is processed asynchronously and
+/// the results have to be requested with the returned `conversion_id`.
+#[derive(Debug, Deserialize)]
+pub struct ConverterResponse {
+    /// Tracking ID to get status and results of the conversion
+    pub conversion_id: Option<String>,
+    /// US locale error message
+    pub error: Option<String>,
+    /// Error info object
+    pub error_info: Option<ErrorInfo>,
+} // }}}
+
+// pub struct ConverterStatusResponse {{{
+/**
+> The response to GET `v3/converter/{conversion_id}` contains the `status` of the whole conversion
+> and the `conversion_status` of every requested format.
+*/
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct ConverterStatusResponse {
+    /// Status of the whole conversion
+    pub status: ConversionStatus,
+    /// Status of the conversion to the formats by their names (e.g. `docx` or `tex.zip`)
+    #[serde(default)]
+    pub conversion_status: BTreeMap<String, FormatStatus>,
+    /// US locale error message
+    pub error: Option<String>,
+}
+
+impl ConverterStatusResponse {
+    /// Number of the formats that have been converted
+    pub fn num_completed(&self) -> usize {
+        self.conversion_status
+            .values()
+            .filter(|format| format.status == ConversionStatus::Completed)
+            .count()
+    }
+} // }}}
+
+// pub struct FormatStatus {{{
+/// Status of the conversion to a single format
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct FormatStatus {
+    /// Status of the conversion to the format
+    pub status: ConversionStatus,
+    /// US locale error message
+    pub error: Option<String>,
+} // }}}
+
+// pub enum ConversionStatus {{{
+/// > The status of the conversion is one of `processing`, `completed` or `error`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConversionStatus {
+    /// The conversion is in progress
+    Processing,
+    /// The conversion is done and the results are available
+    Completed,
+    /// The conversion failed
+    Error,
+    /// Status that this version of the library does not know about
+    #[serde(other)]
+    Unknown,
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod converter_response_tests {
+    use super::{ConversionStatus, ConverterStatusResponse};
+    use serde_json::json;
+
+    #[test]
+    fn deserialize_status() {
+        //{{{
+        let response = json!({
+            "status": "processing",
+            "conversion_status": {
+                "docx": {"status": "completed"},
+                "tex.zip": {"status": "processing"},
+                "html": {"status": "error", "error": "invalid markdown"}
+            }
+        });
+        let deserialized: ConverterStatusResponse = serde_json::from_value(response).unwrap();
+        assert_eq!(deserialized.status, ConversionStatus::Processing);
+        assert_eq!(deserialized.num_completed(), 1);
+        let html = &deserialized.conversion_status["html"];
+        assert_eq!(html.status, ConversionStatus::Error);
+        assert_eq!(html.error.as_deref(), Some("invalid markdown"));
+
+        let unknown: ConverterStatusResponse =
+            serde_json::from_value(json!({"status": "queued"})).unwrap();
+        assert_eq!(unknown.status, ConversionStatus::Unknown);
+        assert!(unknown.conversion_status.is_empty());
+    } //}}}
+}
+// }}}
@@ -0,0 +1,364 @@
+use super::latex::{LaTeX, LaTeXError, LaTeXFormats, LaTeXOptions, LaTeXResponse};
+use super::shared_objects::request::{Base64Image, Base64ImageError, ImageSrc};
+use super::text::{Text, TextError, TextOptions, TextResponse};
+use super::MathpixEndpoint;
+use crate::header::AuthHeader;
+use crate::MATHPIX_APIURL;
+use futures::channel::oneshot;
+use futures::stream::{self, BoxStream, StreamExt};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::convert::TryFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use thiserror::Error;
+
+/// Number of requests that are in flight at the same time by default
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Results of the images along with their paths in the order in which they were processed
+pub type FanOutResults<R, E> = BoxStream<'static, (PathBuf, Result<R, E>)>;
+
+#[derive(Debug, Error)]
+pub enum FanOutError {
+    #[error("PatternError: {0}")]
+    Pattern(#[from] glob::PatternError),
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+}
+
+/**
+Expand the `inputs` into the paths of the images. An input can be
+- a directory: its images (files of a type supported by [Base64Image]) in alphabetical order,
+- a glob pattern (containing `*`, `?` or `[`): the matched files in alphabetical order,
+- any other path: taken as it is, so that a missing file is reported in its result.
+
+Every path is listed only once, in the order of its first occurrence.
+*/
+pub fn image_paths<I, P>(inputs: I) -> Result<Vec<PathBuf>, FanOutError>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<Path>,
+{
+    //{{{
+    let mut paths = Vec::new();
+    for input in inputs {
+        let input = input.as_ref();
+        if input.is_dir() {
+            let mut entries = std::fs::read_dir(input)?
+                .map(|entry| entry.map(|entry| entry.path()))
+                .collect::<Result<Vec<_>, _>>()?;
+            entries.retain(|path| path.is_file() && Base64Image::try_from(path.clone()).is_ok());
+            entries.sort();
+            paths.extend(entries);
+        } else if is_pattern(input) {
+            let pattern = input.to_string_lossy();
+            let mut entries: Vec<PathBuf> = glob::glob(&pattern)?
+                .filter_map(Result::ok)
+                .filter(|path| path.is_file())
+                .collect();
+            entries.sort();
+            paths.extend(entries);
+        } else {
+            paths.push(input.to_path_buf());
+        }
+    }
+    let mut seen = HashSet::new();
+    paths.retain(|path| seen.insert(path.clone()));
+    Ok(paths)
+} //}}}
+
+fn is_pattern(path: &Path) -> bool {
+    path.to_string_lossy().contains(['*', '?', '['])
+}
+
+// FanOut {{{
+/**
+Send many local images to the _text_ or _latex_ endpoint concurrently. At most `concurrency`
+requests are in flight at a time and the images are read and base64 encoded on the `rayon` thread
+pool. Every image has its own result, so a failing image does not stop the rest.
+
+The results are yielded as the images are processed together with the path of the image. They can
+be collected into a map keyed by the path.
+
+```no_run
+# async fn run() -> Result<(), mathpixapi::endpoint::fan_out::FanOutError> {
+use futures::StreamExt;
+use mathpixapi::endpoint::fan_out::{image_paths, FanOut};
+use mathpixapi::endpoint::text::TextOptions;
+use mathpixapi::header::AuthHeader;
+use std::collections::BTreeMap;
+
+let paths = image_paths(["scans", "page-*.png", "equation.jpg"])?;
+let results: BTreeMap<_, _> = FanOut::new(AuthHeader::new("ID", "KEY"))
+    .concurrency(8)
+    .text(paths, TextOptions::default())
+    .collect()
+    .await;
+for (path, result) in results {
+    match result {
+        Ok(response) => println!("{:?}: {:?}", path, response.text),
+        Err(error) => eprintln!("{:?}: {}", path, error),
+    }
+}
+# Ok(())
+# }
+```
+*/
+#[derive(Debug, Clone)]
+pub struct FanOut {
+    header: AuthHeader,
+    concurrency: usize,
+    base_url: Option<Url>,
+}
+
+impl FanOut {
+    pub fn new<H: Into<AuthHeader>>(header: H) -> Self {
+        FanOut {
+            header: header.into(),
+            concurrency: DEFAULT_CONCURRENCY,
+            base_url: None,
+        }
+    }
+
+    /// Maximal number of requests that are in flight at the same time (at least 1)
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Send every image of `paths` to the _text_ endpoint with the same `options`
+    pub fn text(
+        &self,
+        paths: Vec<PathBuf>,
+        options: TextOptions,
+    ) -> FanOutResults<TextResponse, TextError> {
+        self.send(paths, move |src| Text::new(Some(options.clone()), src))
+    }
+
+    /// Send every image of `paths` to the _latex_ endpoint with the same `formats` and `options`
+    pub fn latex(
+        &self,
+        paths: Vec<PathBuf>,
+        formats: Vec<LaTeXFormats>,
+        options: LaTeXOptions,
+    ) -> FanOutResults<LaTeXResponse, LaTeXError> {
+        self.send(paths, move |src| {
+            let mut latex = LaTeX::new(Some(options.clone()), src)?;
+            latex.formats(formats.clone());
+            Ok(latex)
+        })
+    }
+
+    /**
+    Send every image of `paths` to the endpoint that is created for it by `endpoint`. The results
+    are in the order in which the images were processed.
+    */
+    pub fn send<E, F>(
+        &self,
+        paths: Vec<PathBuf>,
+        endpoint: F,
+    ) -> FanOutResults<E::Response, E::Error>
+    where
+        E: MathpixEndpoint + Send + 'static,
+        E::Response: DeserializeOwned + Send + 'static,
+        E::Error: From<Base64ImageError> + From<reqwest::Error> + Send + 'static,
+        F: Fn(ImageSrc) -> Result<E, E::Error> + Send + Sync + 'static,
+    {
+        //{{{
+        let client = reqwest::Client::new();
+        let header = self.header.clone();
+        let base_url = self.base_url.clone();
+        let endpoint = Arc::new(endpoint);
+        stream::iter(paths)
+            .map(move |path| {
+                let client = client.clone();
+                let header = header.clone();
+                let base_url = base_url.clone();
+                let endpoint = endpoint.clone();
+                async move {
+                    let result = async {
+                        let image = encode_image(path.clone()).await?;
+                        let mut request = endpoint(ImageSrc::Image(image))?.to_request(header)?;
+                        if let Some(base_url) = &base_url {
+                            *request.url_mut() = rebase(request.url(), base_url);
+                        }
+                        Ok(client
+                            .execute(request)
+                            .await?
+                            .error_for_status()?
+                            .json()
+                            .await?)
+                    }
+                    .await;
+                    (path, result)
+                }
+            })
+            .buffer_unordered(self.concurrency)
+            .boxed()
+    } //}}}
+} // }}}
+
+/// Read and encode the image on the `rayon` thread pool so that the encoding does not block the
+/// async runtime and the images are encoded in parallel
+async fn encode_image(path: PathBuf) -> Result<Base64Image, Base64ImageError> {
+    let (sender, receiver) = oneshot::channel();
+    rayon::spawn(move || {
+        let _ = sender.send(Base64Image::load(path));
+    });
+    receiver.await.expect("the encoding task panicked")
+}
+
+/// Move the endpoint `url` from the Mathpix API to `base_url`
+fn rebase(url: &Url, base_url: &Url) -> Url {
+    let endpoint = url
+        .as_str()
+        .strip_prefix(MATHPIX_APIURL)
+        .unwrap_or_else(|| url.path());
+    base_url.join(endpoint).unwrap()
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod fan_out_tests {
+    use super::super::latex::{LaTeXFormats, LaTeXOptions};
+    use super::super::test_server::{Reply, TestServer};
+    use super::super::text::{TextError, TextOptions};
+    use super::{image_paths, FanOut};
+    use crate::header::AuthHeader;
+    use futures::StreamExt;
+    use serde_json::{json, Value};
+    use std::collections::BTreeMap;
+    use std::path::{Path, PathBuf};
+
+    const JPG: &str = "./test/assets/test_encode_base64.jpg";
+
+    fn images(dir: &Path) {
+        for name in &["a.jpg", "b.JPG", "c.jpeg"] {
+            std::fs::copy(JPG, dir.join(name)).unwrap();
+        }
+        std::fs::write(dir.join("notes.txt"), "not an image").unwrap();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        std::fs::copy(JPG, dir.join("sub/d.jpg")).unwrap();
+    }
+
+    #[test]
+    fn expand_paths() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        images(dir.path());
+        let paths = image_paths(&[
+            dir.path().to_path_buf(),
+            dir.path().join("*/*.jpg"),
+            dir.path().join("a.jpg"),
+            dir.path().join("missing.png"),
+        ])
+        .unwrap();
+        let names: Vec<PathBuf> = paths
+            .iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                PathBuf::from("a.jpg"),
+                PathBuf::from("b.JPG"),
+                PathBuf::from("c.jpeg"),
+                PathBuf::from("sub/d.jpg"),
+                PathBuf::from("missing.png"),
+            ]
+        );
+        assert!(image_paths(["[unclosed"]).is_err());
+    } //}}}
+
+    #[tokio::test]
+    async fn fan_out_text() {
+        //{{{
+        let server = TestServer::spawn(|request| {
+            assert_eq!(request.path, "/text");
+            assert_eq!(request.header("app_key"), Some("key"));
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            assert!(body["src"]
+                .as_str()
+                .unwrap()
+                .starts_with("data:image/jpeg;base64,/9j/"));
+            Reply::json(json!({"request_id": "abc", "text": "x^2"}))
+        })
+        .await;
+
+        let dir = tempfile::tempdir().unwrap();
+        images(dir.path());
+        let mut paths = image_paths([dir.path()]).unwrap();
+        paths.push(dir.path().join("missing.png"));
+
+        let results: BTreeMap<PathBuf, Result<_, _>> = FanOut::new(AuthHeader::new("id", "key"))
+            .concurrency(2)
+            .with_base_url(server.url.clone())
+            .text(paths, TextOptions::default())
+            .collect()
+            .await;
+        assert_eq!(results.len(), 4);
+        assert_eq!(server.requests().len(), 3);
+        for name in &["a.jpg", "b.JPG", "c.jpeg"] {
+            let response = results[&dir.path().join(name)].as_ref().unwrap();
+            assert_eq!(response.text.as_deref(), Some("x^2"));
+        }
+        assert!(matches!(
+            results[&dir.path().join("missing.png")],
+            Err(TextError::Src(_))
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn fan_out_latex() {
+        //{{{
+        let server = TestServer::spawn(|request| {
+            assert_eq!(request.path, "/latex");
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            assert_eq!(body["formats"], json!(["latex_simplified"]));
+            assert_eq!(body["skip_recrop"], json!(true));
+            Reply::json(json!({"latex_simplified": "x^{2}"}))
+        })
+        .await;
+
+        let mut options = LaTeXOptions::default();
+        options.skip_recrop(true);
+        let results: Vec<_> = FanOut::new(AuthHeader::new("id", "key"))
+            .with_base_url(server.url.clone())
+            .latex(
+                vec![PathBuf::from(JPG)],
+                vec![LaTeXFormats::LaTeXSimplified],
+                options,
+            )
+            .collect()
+            .await;
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            results[0].1.as_ref().unwrap().latex_simplified.as_deref(),
+            Some("x^{2}")
+        );
+    } //}}}
+
+    #[tokio::test]
+    async fn fan_out_error_status() {
+        //{{{
+        let server = TestServer::spawn(|_| Reply::status(401)).await;
+        let results: Vec<_> = FanOut::new(AuthHeader::new("id", "key"))
+            .with_base_url(server.url.clone())
+            .text(vec![PathBuf::from(JPG)], TextOptions::default())
+            .collect()
+            .await;
+        assert!(matches!(
+            &results[0].1,
+            Err(TextError::Request(error)) if error.status().map(|status| status.as_u16()) == Some(401)
+        ));
+    } //}}}
+}
+// }}}
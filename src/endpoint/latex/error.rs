@@ -1,6 +1,30 @@
+pub use super::super::shared_objects::request::{Base64ImageError, CallBackError};
+use reqwest;
+use serde_json;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum LaTeXError {
-    // TODO:  <21-07-21, kunzaatko> //
+    #[error("SerializationError: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("SrcError: {0}")]
+    Src(#[from] Base64ImageError),
+    #[error("RequestError: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("CallBackError: {0}")]
+    CallBack(#[from] CallBackError),
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum LaTeXOptionsError {
+    #[error("UnknownLaTeXFormat: {0} is not a format of the latex endpoint (text, text_display, latex_normal, latex_styled, latex_simplified, latex_list, mathml, asciimath, wolfram)")]
+    UnknownFormat(String),
+    #[error("UnknownOcr: {0} is not one of math, text")]
+    UnknownOcr(String),
+}
+
+impl From<std::convert::Infallible> for LaTeXError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
 }
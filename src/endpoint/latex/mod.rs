@@ -2,11 +2,17 @@ mod error;
 mod options;
 mod response;
 
-pub use super::shared_objects::request::{CallBack, ImageSrc, MetaData};
-use error::LaTeXError;
-pub use options::{LaTeXFormats, LaTeXOptions};
-use response::LaTeXResponse;
+use super::shared_objects::request::{expand_callback, json_body, BODY_CAPACITY};
+pub use super::shared_objects::request::{
+    CallBack, CallBackError, ImageSrc, MetaData, TemplateVars,
+};
+use super::{super::MATHPIX_APIURL, MathpixEndpoint};
+pub use error::{LaTeXError, LaTeXOptionsError};
+pub use options::{FormatOptions, LaTeXFormats, LaTeXOptions, Ocr, Region, Transforms};
+use reqwest;
+pub use response::{Candidates, LaTeXResponse};
 use serde::Serialize;
+use std::convert::TryInto;
 
 // LaTeX {{{
 #[derive(Serialize, Debug)]
@@ -20,3 +26,103 @@ pub struct LaTeX {
     #[serde(flatten)]
     pub options: LaTeXOptions,
 } //}}}
+
+impl LaTeX {
+    /// Set the formats that should be returned. By default only `text` is returned.
+    pub fn formats(&mut self, val: Vec<LaTeXFormats>) -> &mut Self {
+        self.formats = val;
+        self
+    }
+}
+
+impl MathpixEndpoint for LaTeX {
+    //{{{
+    type Src = ImageSrc;
+    type Error = LaTeXError;
+    type Options = LaTeXOptions;
+    type Response = LaTeXResponse;
+
+    fn new<S, E>(options: Option<Self::Options>, src: S) -> Result<Self, Self::Error>
+    where
+        S: TryInto<ImageSrc, Error = E>,
+        Self::Error: From<E>,
+        Self: Sized,
+    {
+        Ok(Self {
+            src: src.try_into()?,
+            formats: vec![LaTeXFormats::Text],
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    fn url(&self) -> reqwest::Url {
+        let mut url_str = MATHPIX_APIURL.to_string();
+        url_str.push_str("latex");
+        reqwest::Url::parse(&url_str).unwrap()
+    }
+
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error> {
+        // NOTE: The image is encoded first so that a missing file is a `Src` error
+        let src_len = self.src.encode()?.len();
+        let request = reqwest::Client::new().post(self.url());
+        let capacity = src_len + BODY_CAPACITY;
+        // NOTE: The body is only converted to a JSON value (a copy of the encoded image) to
+        // expand the callback
+        if self.options.callback.is_none() {
+            return Ok(json_body(request, self, capacity)?);
+        }
+        let mut vars = TemplateVars::now();
+        vars.image = self.src.file_name();
+        let body = expand_callback::<_, Self::Error>(self, self.options.callback.as_ref(), &vars)?;
+        Ok(json_body(request, &body, capacity)?)
+    }
+
+    fn options(&mut self) -> &mut Self::Options {
+        &mut self.options
+    }
+
+    fn src(&mut self) -> Option<&mut Self::Src> {
+        Some(&mut self.src)
+    }
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+mod latex_endpoint_tests {
+    use super::{CallBack, ImageSrc, LaTeX, LaTeXOptions, MathpixEndpoint};
+    use crate::header::AuthHeader;
+    use regex::Regex;
+    use reqwest::Url;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn expand_callback_headers() {
+        //{{{
+        let mut callback = CallBack::new("https://example.com/callback").unwrap();
+        callback
+            .header_line("X-Image: $image")
+            .unwrap()
+            .header_line("X-Sent: $date")
+            .unwrap()
+            .header_line("X-Tag: $tag")
+            .unwrap()
+            .reply(json!({"id": 1}));
+        let mut options = LaTeXOptions::default();
+        options.callback(callback);
+        let src = ImageSrc::Url(Url::parse("https://mathpix.com/examples/limit.jpg").unwrap());
+        let latex = LaTeX::new(Some(options), src).unwrap();
+
+        let request = latex.to_request(AuthHeader::new("id", "key")).unwrap();
+        assert_eq!(request.url().as_str(), "https://api.mathpix.com/v3/latex");
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        let headers = &body["callback"]["headers"];
+        assert_eq!(headers["X-Image"], json!("limit.jpg"));
+        assert_eq!(headers["X-Tag"], json!("$tag"));
+        let date = Regex::new(r"^\d{4}-\d{2}-\d{2}T\d{2}:\d{2}:\d{2}Z$").unwrap();
+        assert!(date.is_match(headers["X-Sent"].as_str().unwrap()));
+        assert_eq!(body["callback"]["reply"], json!({"id": 1}));
+        assert_eq!(body["formats"], json!(["text"]));
+    } //}}}
+}
+// }}}
@@ -1,8 +1,10 @@
 use super::super::shared_objects::request::{CallBack, ConfidenceThreshold, ImageSrc, MetaData};
+use super::error::LaTeXOptionsError;
 use serde::Serialize;
+use std::str::FromStr;
 
 // LaTeXOptions {{{
-#[derive(Debug, Serialize, PartialEq, Default)]
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
 pub struct LaTeXOptions {
     /// > Process only math `["math"]` or both math and text `["math", "text"]`
     pub ocr: Option<Vec<Ocr>>,
@@ -48,7 +50,7 @@ impl LaTeXOptions {
 // }}}
 
 // LaTeXFormats {{{
-#[derive(Debug, Serialize, Clone)]
+#[derive(Debug, Serialize, Clone, PartialEq)]
 #[serde(rename_all = "snake_case")]
 pub enum LaTeXFormats {
     /// > Text mode output, with math inside delimiters, eg. test \(x^2\), inline math by default
@@ -76,6 +78,25 @@ pub enum LaTeXFormats {
     /// > A string compatible with the Wolfram Alpha engine
     Wolfram,
 }
+
+impl FromStr for LaTeXFormats {
+    type Err = LaTeXOptionsError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "text" => Ok(LaTeXFormats::Text),
+            "text_display" => Ok(LaTeXFormats::TextDisplay),
+            "latex_normal" => Ok(LaTeXFormats::LaTeXNormal),
+            "latex_styled" => Ok(LaTeXFormats::LaTeXStyled),
+            "latex_simplified" => Ok(LaTeXFormats::LaTeXSimplified),
+            "latex_list" => Ok(LaTeXFormats::LaTeXList),
+            "mathml" => Ok(LaTeXFormats::MathML),
+            "asciimath" => Ok(LaTeXFormats::AsciiMath),
+            "wolfram" => Ok(LaTeXFormats::Wolfram),
+            other => Err(LaTeXOptionsError::UnknownFormat(other.to_string())),
+        }
+    }
+}
 // }}}
 
 // Ocr {{{
@@ -88,6 +109,18 @@ pub enum Ocr {
     /// > Process text from the image
     Text,
 }
+
+impl FromStr for Ocr {
+    type Err = LaTeXOptionsError;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "math" => Ok(Ocr::Math),
+            "text" => Ok(Ocr::Text),
+            other => Err(LaTeXOptionsError::UnknownOcr(other.to_string())),
+        }
+    }
+}
 // }}}
 
 // FormatOptions {{{
@@ -298,11 +331,8 @@ mod latex_options_tests {
             displaymath_delims: None,
         };
 
-        let callback = CallBack {
-            post: Some("https://duckduckgo.com/".to_string()),
-            headers: None,
-            reply: Some("Here is your reply".to_string()),
-        };
+        let mut callback = CallBack::new("https://duckduckgo.com/").unwrap();
+        callback.reply(json!("Here is your reply"));
 
         let region = Region {
             top_left_x: Some(0),
@@ -339,5 +369,20 @@ mod latex_options_tests {
         };
         assert_eq!(latex_options, expected);
     } //}}}
+
+    #[test]
+    fn parse_formats_and_ocr() {
+        //{{{
+        assert_eq!("latex_styled".parse(), Ok(LaTeXFormats::LaTeXStyled));
+        assert_eq!(
+            "latex".parse::<LaTeXFormats>(),
+            Err(LaTeXOptionsError::UnknownFormat("latex".to_string()))
+        );
+        assert_eq!("math".parse(), Ok(Ocr::Math));
+        assert_eq!(
+            "chem".parse::<Ocr>(),
+            Err(LaTeXOptionsError::UnknownOcr("chem".to_string()))
+        );
+    } //}}}
 }
 // }}}
@@ -9,6 +9,15 @@ Object that are shared in multiple endpoints. Now consists of `ImageSrc` (and `B
 */
 mod shared_objects;
 
+/**
+Polling of the jobs that are processed asynchronously by the server (PDFs, batches and
+conversions). Now consists of `PollOptions` and the polling loop that is shared by the jobs.
+*/
+mod poll;
+
+#[cfg(test)]
+mod test_server;
+
 macro_rules! field_builder {
     ($field_name: ident, $field_type: ty) => {
         pub fn $field_name(&mut self, val: $field_type) -> &mut Self {
@@ -30,6 +39,26 @@ Module for constructing the _batch_ endpoint request, associated response struct
 */
 pub mod batch; //}}}
 
+// pub mod converter; {{{
+/**
+Module for constructing the _converter_ endpoint request, associated response structure and error handling for the _converter_ endpoint.
+
+> Mathpix supports conversions from Mathpix Markdown to other formats: DOCX, LaTeX zip, HTML and
+> Markdown. The conversion is processed asynchronously, its status and results are requested
+> with the returned `conversion_id`.
+*/
+pub mod converter; //}}}
+
+// pub mod fan_out; {{{
+/**
+Module for sending many local images to the _text_ or _latex_ endpoint concurrently.
+
+The _batch_ endpoint only accepts URLs of the images. [fan_out::FanOut] sends local image files
+(given as paths, glob patterns or directories) as separate requests with a bounded number of
+requests in flight and returns a result for each of the images.
+*/
+pub mod fan_out; //}}}
+
 // pub mod latex; {{{
 /**
 Module for constructing the _latex_ endpoint request, associated response structure and error handling for the _latex_ endpoint.
@@ -160,11 +189,21 @@ where
     > [^certificate]: There is a free license for the API certificate available with limited request
     > numbers. For further information see the [mathpix accounts website](https://accounts.mathpix.com/ocr-api).
     */
-    async fn send_request<H, Fut, F>(&self, header: H) -> Result<Self::Response, Self::Error>
+    async fn send_request<H>(&self, header: H) -> Result<Self::Response, Self::Error>
     where
+        Self: Sync,
         H: Into<self::AuthHeader> + std::marker::Send,
-        reqwest::Response: Into<Self::Response>,
-        reqwest::Error: Into<Self::Error>;
+        Self::Response: serde::de::DeserializeOwned,
+        Self::Error: From<reqwest::Error>,
+    {
+        let request = self.to_request(header)?;
+        Ok(reqwest::Client::new()
+            .execute(request)
+            .await?
+            .json::<Self::Response>()
+            .await?)
+    }
+
     /**
     Create a `reqwest::Request` from `self` with the `header`
 
@@ -181,15 +220,17 @@ where
         <Self as MathpixEndpoint>::Error: From<reqwest::Error>,
     {
         let headers: reqwest::header::HeaderMap = header.into().into();
-        Ok(self.to_request_builder().headers(headers).build()?)
+        Ok(self.to_request_builder()?.headers(headers).build()?)
     }
 
     /**
     Create a `reqwest::RequestBuilder` from `self`
 
-    This could be usefull if you do not want to add the header right away.
+    This could be usefull if you do not want to add the header right away. It fails when the body
+    of the request can not be constructed (e.g. a local file that should be uploaded can not be
+    read).
     */
-    fn to_request_builder(&self) -> reqwest::RequestBuilder;
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error>;
 
     /**
     Return the URL that is associated with the request
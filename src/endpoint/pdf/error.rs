@@ -1,6 +1,91 @@
+use super::page_ranges::PageRangesError;
+use reqwest;
+use serde_json;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum PDFError {
-    // TODO:  <21-07-21, kunzaatko> //
+    #[error("SerializationError: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("SrcError: {0}")]
+    Src(#[from] PDFPathError),
+    #[error("RequestError: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("OptionsError: {0}")]
+    Options(#[from] PDFOptionsError),
+    #[error("ProcessingError: {0}")]
+    Processing(String),
+    #[error("Timeout: the PDF was not processed in {0:?}")]
+    Timeout(std::time::Duration),
+    #[error("DocumentError: {0}")]
+    Document(#[from] lopdf::Error),
+    #[error("SplitError: {0}")]
+    Split(String),
+    #[error("TexZipError: {0}")]
+    TexZip(#[from] TexZipError),
 }
+
+impl From<std::convert::Infallible> for PDFError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum TexZipError {
+    #[error("ZipError: {0}")]
+    Zip(#[from] zip::result::ZipError),
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("UnsafePath: the entry {0:?} would be extracted outside of the target directory")]
+    UnsafePath(String),
+    #[error("FileExists: {0:?} already exists (extract with `force` to overwrite it)")]
+    FileExists(std::path::PathBuf),
+    #[error("NoMainTex: the archive does not contain a .tex file")]
+    NoMainTex,
+}
+
+#[derive(Debug, Error)]
+pub enum PDFPathError {
+    #[error("InvalidExtension: {0}")]
+    InvalidExtension(String),
+    #[error("UnsupportedFileType: {0}")]
+    UnsupportedFileType(String),
+}
+
+#[derive(Error, Debug)]
+pub enum PDFOptionsError {
+    #[error("BadOption: {0}")]
+    BadOption(#[from] BadPDFOptionError),
+    #[error("UnreasonableOptions: {0}")]
+    UnreasonableOptions(#[from] UnreasonableOptionsError),
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum UnreasonableOptionsError {
+    #[error("NoAlphabetsAllowed: There should be atleast one alphabet allowed.")]
+    NoAlphabetsAllowed,
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BadPDFOptionError {
+    #[error(
+        "BadConversionFormat: {0} is not available as a conversion format. Possible options are {:?}.",
+        CONVERSION_FORMATS
+    )]
+    ConversionFormat(String),
+    #[error("BadAlphabetAllowed: {0} is not available as an allowed alphabet. Possible options are {:?}.", ALPHABETS_ALLOWED)]
+    AlphabetAllowed(String),
+    #[error("BadDelimiters: ({0:?}, {1:?}) are not valid delimiters. Both of the delimiters must be non-empty.")]
+    Delimiters(String, String),
+    #[error("BadPageRanges: {0}")]
+    PageRanges(#[from] PageRangesError),
+}
+
+const CONVERSION_FORMATS: &[&str] = &["docx", "tex.zip", "html", "md"];
+const ALPHABETS_ALLOWED: &[&str] = &[
+    "en", "{no/!}en", "hi", "{no/!}hi", "zh", "{no/!}zh", "ja", "{no/!}ja", "ko", "{no/!}ko", "ru",
+    "{no/!}ru", "th", "{no/!}th",
+];
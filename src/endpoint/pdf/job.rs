@@ -0,0 +1,532 @@
+use super::super::super::{header::AuthHeader, MATHPIX_APIURL};
+pub use super::super::poll::PollOptions;
+use super::super::poll::{wait_for, JobError, JobState, JobStatus};
+use super::error::PDFError;
+use super::lines::PDFLines;
+use super::options::ConversionFormat;
+use super::response::{PDFPageChunk, PDFProcessingStatus, PDFStatusResponse};
+use super::tex_zip::{extract_tex_zip, ExtractOptions, TexProject};
+use futures::stream::{self, BoxStream, Stream, StreamExt};
+use reqwest::{header::HeaderMap, Url};
+use std::collections::VecDeque;
+use std::fmt;
+use std::path::Path;
+use std::time::Duration;
+
+// PDFOutputFormat {{{
+/// Formats in which the results of the PDF processing can be downloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PDFOutputFormat {
+    /// Mathpix Markdown, always available
+    Mmd,
+    /// Markdown, available when requested in the `conversion_formats`
+    Md,
+    /// DOCX, available when requested in the `conversion_formats`
+    Docx,
+    /// LaTeX zip, available when requested in the `conversion_formats`
+    TexZip,
+    /// HTML, available when requested in the `conversion_formats`
+    Html,
+    /// Line by line data, always available
+    LinesJson,
+}
+
+impl PDFOutputFormat {
+    /// Extension of the format as used in the result URL and the file name
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PDFOutputFormat::Mmd => "mmd",
+            PDFOutputFormat::Md => "md",
+            PDFOutputFormat::Docx => "docx",
+            PDFOutputFormat::TexZip => "tex.zip",
+            PDFOutputFormat::Html => "html",
+            PDFOutputFormat::LinesJson => "lines.json",
+        }
+    }
+
+    pub fn from_extension(extension: &str) -> Option<Self> {
+        match extension {
+            "mmd" => Some(PDFOutputFormat::Mmd),
+            "md" => Some(PDFOutputFormat::Md),
+            "docx" => Some(PDFOutputFormat::Docx),
+            "tex.zip" => Some(PDFOutputFormat::TexZip),
+            "html" => Some(PDFOutputFormat::Html),
+            "lines.json" => Some(PDFOutputFormat::LinesJson),
+            _ => None,
+        }
+    }
+}
+
+impl From<ConversionFormat> for PDFOutputFormat {
+    fn from(format: ConversionFormat) -> Self {
+        match format {
+            ConversionFormat::Docx => PDFOutputFormat::Docx,
+            ConversionFormat::TexZip => PDFOutputFormat::TexZip,
+            ConversionFormat::Html => PDFOutputFormat::Html,
+            ConversionFormat::Md => PDFOutputFormat::Md,
+        }
+    }
+}
+
+impl fmt::Display for PDFOutputFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.extension())
+    }
+} // }}}
+
+// PdfJob {{{
+/**
+Handle to a PDF that is being processed by the server. It is obtained by sending a [super::PDF]
+with [super::PDF::start_job] or from the `pdf_id` of a PDF that was sent earlier with
+[PdfJob::new].
+
+```no_run
+# async fn run() -> Result<(), mathpixapi::endpoint::pdf::PDFError> {
+use mathpixapi::endpoint::pdf::{PDFOutputFormat, PdfJob, PollOptions};
+use mathpixapi::header::AuthHeader;
+
+let job = PdfJob::new("2021_09_07_5fe0cd0ed0d3e4a3f6b4g", AuthHeader::new("ID", "KEY"));
+job.wait(&PollOptions::default(), |status| {
+    eprintln!("{:?} pages done", status.num_pages_completed)
+})
+.await?;
+job.download_to_file(PDFOutputFormat::Mmd, "result.mmd").await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Debug, Clone)]
+pub struct PdfJob {
+    pdf_id: String,
+    header: AuthHeader,
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl PdfJob {
+    pub fn new<S: Into<String>, H: Into<AuthHeader>>(pdf_id: S, header: H) -> Self {
+        PdfJob {
+            pdf_id: pdf_id.into(),
+            header: header.into(),
+            base_url: Url::parse(MATHPIX_APIURL).unwrap(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// Tracking ID of the PDF on the server
+    pub fn pdf_id(&self) -> &str {
+        &self.pdf_id
+    }
+
+    pub(crate) fn endpoint_url(&self, path: &str) -> Url {
+        self.base_url.join(path).unwrap()
+    }
+
+    pub(crate) fn headers(&self) -> HeaderMap {
+        self.header.clone().into()
+    }
+
+    pub(crate) fn client(&self) -> &reqwest::Client {
+        &self.client
+    }
+
+    /// Request the current processing status of the PDF
+    pub async fn status(&self) -> Result<PDFStatusResponse, PDFError> {
+        //{{{
+        let url = self.endpoint_url(&format!("pdf/{}", self.pdf_id));
+        Ok(self
+            .client
+            .get(url)
+            .headers(self.headers())
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    } //}}}
+
+    /// Delete the PDF and its results from the server
+    pub async fn delete(&self) -> Result<(), PDFError> {
+        let url = self.endpoint_url(&format!("pdf/{}", self.pdf_id));
+        self.client
+            .delete(url)
+            .headers(self.headers())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+
+    /**
+    Poll the status of the PDF until the processing is completed. `on_progress` is called with
+    every status that is received, which can be used to report the number of processed pages.
+
+    Fails with `PDFError::Processing` if the server reports an error and with `PDFError::Timeout`
+    if the processing does not end before the timeout of the `poll` options.
+    */
+    pub async fn wait<F>(
+        &self,
+        poll: &PollOptions,
+        on_progress: F,
+    ) -> Result<PDFStatusResponse, PDFError>
+    where
+        F: FnMut(&PDFStatusResponse),
+    {
+        wait_for(poll, || self.status(), on_progress).await
+    }
+
+    /**
+    Stream the Mathpix Markdown of the pages as they are processed. The PDF has to be sent with the
+    `streaming` option (see [super::PDFOptions::streaming]), otherwise the server does not emit
+    the pages. The stream ends when the server closes the connection after the last page.
+
+    ```no_run
+    # async fn run(job: mathpixapi::endpoint::pdf::PdfJob) -> Result<(), mathpixapi::endpoint::pdf::PDFError> {
+    use futures::StreamExt;
+
+    let mut pages = job.stream_pages().await?;
+    while let Some(page) = pages.next().await {
+        print!("{}", page?.text);
+    }
+    # Ok(())
+    # }
+    ```
+    */
+    pub async fn stream_pages(
+        &self,
+    ) -> Result<BoxStream<'static, Result<PDFPageChunk, PDFError>>, PDFError> {
+        //{{{
+        let url = self.endpoint_url(&format!("pdf/{}/stream", self.pdf_id));
+        let response = self
+            .client
+            .get(url)
+            .headers(self.headers())
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(page_chunks(response.bytes_stream()).boxed())
+    } //}}}
+
+    /// Download the result of the processing in the given format
+    pub async fn download(&self, format: PDFOutputFormat) -> Result<Vec<u8>, PDFError> {
+        //{{{
+        let url = self.endpoint_url(&format!("pdf/{}.{}", self.pdf_id, format.extension()));
+        Ok(self
+            .client
+            .get(url)
+            .headers(self.headers())
+            .send()
+            .await?
+            .error_for_status()?
+            .bytes()
+            .await?
+            .to_vec())
+    } //}}}
+
+    /**
+    Download the `tex.zip` result and extract it into `dir` as a LaTeX project, see
+    [super::extract_tex_zip]. The PDF has to be sent with the `tex.zip` conversion format.
+    */
+    pub async fn extract_tex_zip<P: AsRef<Path>>(
+        &self,
+        dir: P,
+        options: &ExtractOptions,
+    ) -> Result<TexProject, PDFError> {
+        let bytes = self.download(PDFOutputFormat::TexZip).await?;
+        Ok(extract_tex_zip(&bytes, dir, options)?)
+    }
+
+    /// Download the line by line data of the PDF (`lines.json`) and parse it
+    pub async fn lines(&self) -> Result<PDFLines, PDFError> {
+        let bytes = self.download(PDFOutputFormat::LinesJson).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    /// Download the result of the processing in the given format and write it to `path`
+    pub async fn download_to_file<P: AsRef<Path>>(
+        &self,
+        format: PDFOutputFormat,
+        path: P,
+    ) -> Result<(), PDFError> {
+        let bytes = self.download(format).await?;
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+} // }}}
+
+impl JobStatus for PDFStatusResponse {
+    fn state(&self) -> JobState {
+        match self.status {
+            PDFProcessingStatus::Completed => JobState::Completed,
+            PDFProcessingStatus::Error => JobState::Failed(
+                self.error
+                    .clone()
+                    .unwrap_or_else(|| "unknown processing error".to_string()),
+            ),
+            _ => JobState::Pending,
+        }
+    }
+}
+
+impl JobError for PDFError {
+    fn failed(message: String) -> Self {
+        PDFError::Processing(message)
+    }
+
+    fn timeout(elapsed: Duration) -> Self {
+        PDFError::Timeout(elapsed)
+    }
+}
+
+/**
+Split the body of the streaming response into the emitted pages. Every page is a JSON object on a
+separate line, optionally prefixed by `data:` as in server-sent events. The lines can be split
+arbitrarily between the chunks of the body.
+*/
+fn page_chunks<S, B>(bytes: S) -> impl Stream<Item = Result<PDFPageChunk, PDFError>>
+where
+    S: Stream<Item = reqwest::Result<B>> + Send + 'static,
+    B: AsRef<[u8]>,
+{
+    //{{{
+    let state = (bytes.boxed(), Vec::new(), VecDeque::new(), false);
+    stream::unfold(
+        state,
+        |(mut bytes, mut buffer, mut pages, mut done)| async move {
+            loop {
+                if let Some(page) = pages.pop_front() {
+                    return Some((page, (bytes, buffer, pages, done)));
+                }
+                if done {
+                    return None;
+                }
+                match bytes.next().await {
+                    Some(Ok(chunk)) => {
+                        buffer.extend_from_slice(chunk.as_ref());
+                        while let Some(end) = buffer.iter().position(|&byte| byte == b'\n') {
+                            let line: Vec<u8> = buffer.drain(..=end).collect();
+                            pages.extend(parse_page_line(&line));
+                        }
+                    }
+                    Some(Err(error)) => {
+                        pages.push_back(Err(error.into()));
+                        done = true;
+                    }
+                    None => {
+                        pages.extend(parse_page_line(&buffer));
+                        done = true;
+                    }
+                }
+            }
+        },
+    )
+} //}}}
+
+/// Parse a line of the streaming response. Empty lines and other server-sent event fields are skipped.
+fn parse_page_line(line: &[u8]) -> Option<Result<PDFPageChunk, PDFError>> {
+    let line = String::from_utf8_lossy(line);
+    let line = line.trim();
+    let data = line.strip_prefix("data:").unwrap_or(line).trim_start();
+    if !data.starts_with('{') {
+        return None;
+    }
+    Some(serde_json::from_str(data).map_err(PDFError::from))
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod pdf_job_tests {
+    use super::super::super::poll::fast_poll;
+    use super::super::super::test_server::{Reply, TestServer};
+    use super::{PDFError, PDFOutputFormat, PdfJob, PollOptions};
+    use crate::header::AuthHeader;
+    use futures::StreamExt;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn wait_reports_progress() {
+        //{{{
+        let polls = Arc::new(AtomicU32::new(0));
+        let server_polls = polls.clone();
+        let server = TestServer::spawn(move |request| {
+            assert_eq!(request.path, "/pdf/abc");
+            assert_eq!(request.header("app_id"), Some("id"));
+            let completed = server_polls.fetch_add(1, Ordering::SeqCst) + 1;
+            let status = if completed < 3 { "split" } else { "completed" };
+            Reply::json(json!({
+                "status": status,
+                "num_pages": 3,
+                "num_pages_completed": completed,
+                "percent_done": completed as f32 / 3. * 100.,
+            }))
+        })
+        .await;
+
+        let job = PdfJob::new("abc", AuthHeader::new("id", "key")).with_base_url(server.url);
+        let mut progress = Vec::new();
+        let status = job
+            .wait(&fast_poll(), |status| {
+                progress.push(status.num_pages_completed.unwrap())
+            })
+            .await
+            .unwrap();
+        assert_eq!(status.num_pages, Some(3));
+        assert_eq!(progress, vec![1, 2, 3]);
+    } //}}}
+
+    #[tokio::test]
+    async fn wait_errors() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/pdf/failing" => Reply::json(json!({"status": "error", "error": "bad pdf"})),
+            _ => Reply::json(json!({"status": "loaded"})),
+        })
+        .await;
+
+        let job =
+            PdfJob::new("failing", AuthHeader::new("id", "key")).with_base_url(server.url.clone());
+        assert!(matches!(
+            job.wait(&fast_poll(), |_| {}).await,
+            Err(PDFError::Processing(message)) if message == "bad pdf"
+        ));
+
+        let job = PdfJob::new("stuck", AuthHeader::new("id", "key")).with_base_url(server.url);
+        let poll = PollOptions {
+            timeout: Some(Duration::from_millis(50)),
+            ..fast_poll()
+        };
+        assert!(matches!(
+            job.wait(&poll, |_| {}).await,
+            Err(PDFError::Timeout(_))
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn download_results() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/pdf/abc.mmd" => Reply::bytes("# Title"),
+            "/pdf/abc.tex.zip" => Reply::bytes(vec![0x50, 0x4b, 0x03, 0x04]),
+            _ => Reply::status(404),
+        })
+        .await;
+        let job = PdfJob::new("abc", AuthHeader::new("id", "key")).with_base_url(server.url);
+
+        assert_eq!(
+            job.download(PDFOutputFormat::Mmd).await.unwrap(),
+            b"# Title"
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("result.tex.zip");
+        job.download_to_file(PDFOutputFormat::TexZip, &path)
+            .await
+            .unwrap();
+        assert_eq!(std::fs::read(path).unwrap(), vec![0x50, 0x4b, 0x03, 0x04]);
+
+        assert!(matches!(
+            job.download(PDFOutputFormat::Docx).await,
+            Err(PDFError::Request(_))
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn delete_pdf() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/pdf/abc" if request.method == "DELETE" => Reply::json(json!({})),
+            _ => Reply::status(404),
+        })
+        .await;
+        let job =
+            PdfJob::new("abc", AuthHeader::new("id", "key")).with_base_url(server.url.clone());
+        job.delete().await.unwrap();
+        let job = PdfJob::new("missing", AuthHeader::new("id", "key")).with_base_url(server.url);
+        assert!(matches!(job.delete().await, Err(PDFError::Request(_))));
+    } //}}}
+
+    #[tokio::test]
+    async fn download_lines() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/pdf/abc.lines.json" => Reply::json(json!({"pages": [{
+                "page": 1,
+                "page_width": 100,
+                "page_height": 100,
+                "lines": [{"type": "text", "cnt": [[0, 0], [10, 10]], "text": "Hi", "confidence": 0.5}]
+            }]})),
+            _ => Reply::status(404),
+        })
+        .await;
+        let job = PdfJob::new("abc", AuthHeader::new("id", "key")).with_base_url(server.url);
+        let lines = job.lines().await.unwrap();
+        assert_eq!(lines.pages[0].lines[0].text.as_deref(), Some("Hi"));
+    } //}}}
+
+    #[tokio::test]
+    async fn stream_pages() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/pdf/abc/stream" => Reply::Chunked {
+                content_type: "text/event-stream",
+                chunks: vec![
+                    b"data: {\"page_idx\": 1, \"text\": \"# Title\\n\", ".to_vec(),
+                    b"\"pdf_selected_len\": 2}\n\ndata: {\"page_idx\": 2,".to_vec(),
+                    b" \"text\": \"Text\", \"pdf_selected_len\": 2}".to_vec(),
+                ],
+                delay: Duration::from_millis(10),
+            },
+            _ => Reply::status(404),
+        })
+        .await;
+        let job = PdfJob::new("abc", AuthHeader::new("id", "key")).with_base_url(server.url);
+
+        let pages: Vec<_> = job
+            .stream_pages()
+            .await
+            .unwrap()
+            .map(|page| page.unwrap())
+            .collect()
+            .await;
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].page_idx, 1);
+        assert_eq!(pages[0].text, "# Title\n");
+        assert_eq!(pages[1].text, "Text");
+        assert_eq!(pages[1].pdf_selected_len, Some(2));
+    } //}}}
+
+    #[tokio::test]
+    async fn stream_pages_errors() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.path.as_str() {
+            "/pdf/bad/stream" => Reply::Chunked {
+                content_type: "application/x-ndjson",
+                chunks: vec![b"{\"page_idx\": 1, \"text\": \"ok\"}\n{\"page_idx\":".to_vec()],
+                delay: Duration::from_millis(0),
+            },
+            _ => Reply::status(404),
+        })
+        .await;
+        let job =
+            PdfJob::new("bad", AuthHeader::new("id", "key")).with_base_url(server.url.clone());
+        let pages: Vec<_> = job.stream_pages().await.unwrap().collect().await;
+        assert!(matches!(&pages[0], Ok(page) if page.text == "ok"));
+        assert!(matches!(pages[1], Err(PDFError::Serialization(_))));
+
+        let job = PdfJob::new("missing", AuthHeader::new("id", "key")).with_base_url(server.url);
+        assert!(matches!(
+            job.stream_pages().await,
+            Err(PDFError::Request(_))
+        ));
+    } //}}}
+}
+// }}}
@@ -0,0 +1,281 @@
+use super::super::shared_objects::response::LineData;
+use serde::Deserialize;
+use std::ops::Deref;
+
+// pub struct PDFLines {{{
+/**
+Line by line data of a processed PDF as returned in the `lines.json` result. It consists of the
+pages of the PDF with the lines that were recognized on each of them.
+
+```
+use mathpixapi::endpoint::pdf::PDFLines;
+# let json = r#"{"pages": [{"page": 1, "page_width": 100, "page_height": 200, "lines": [
+#     {"type": "text", "cnt": [[10, 20], [90, 20], [90, 40], [10, 40]], "text": "Hi", "confidence": 0.4}
+# ]}]}"#;
+let lines: PDFLines = serde_json::from_str(json).unwrap();
+for (page, line) in lines.low_confidence(0.5) {
+    // Rectangle to highlight in a PDF page of 612x792 points
+    let contour = page.to_pdf_points(line, 612., 792.);
+    println!("page {}: {:?} {:?}", page.page, line.text, contour);
+}
+```
+*/
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct PDFLines {
+    /// List of [PDFPageLines] objects
+    pub pages: Vec<PDFPageLines>,
+}
+
+impl PDFLines {
+    /// Iterate over the pages
+    pub fn pages(&self) -> impl Iterator<Item = &PDFPageLines> {
+        self.pages.iter()
+    }
+
+    /// The page with the number `page` (numbered from 1)
+    pub fn page(&self, page: u32) -> Option<&PDFPageLines> {
+        self.pages.iter().find(|page_lines| page_lines.page == page)
+    }
+
+    /// Iterate over all of the lines along with the page they are on
+    pub fn lines(&self) -> impl Iterator<Item = (&PDFPageLines, &PDFLineData)> {
+        self.pages
+            .iter()
+            .flat_map(|page| page.lines.iter().map(move |line| (page, line)))
+    }
+
+    /// Iterate over the lines of type `line_type` (e.g. `math` or `table`)
+    pub fn lines_of_type<'a>(
+        &'a self,
+        line_type: &'a str,
+    ) -> impl Iterator<Item = (&'a PDFPageLines, &'a PDFLineData)> {
+        self.lines()
+            .filter(move |(_, line)| line.r#type == line_type)
+    }
+
+    /// Iterate over the lines with a `confidence` below `threshold`
+    pub fn low_confidence(
+        &self,
+        threshold: f32,
+    ) -> impl Iterator<Item = (&PDFPageLines, &PDFLineData)> {
+        self.lines().filter(
+            move |(_, line)| matches!(line.confidence, Some(confidence) if confidence < threshold),
+        )
+    }
+} // }}}
+
+// pub struct PDFPageLines {{{
+/// Lines of a single page of the PDF. The coordinates of the lines are pixel coordinates of the
+/// page rendered as an image of `page_width`×`page_height` pixels with the origin at the top left.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct PDFPageLines {
+    /// ID of the image of the page
+    pub image_id: Option<String>,
+    /// Number of the page (numbered from 1)
+    pub page: u32,
+    /// Width of the rendered page in pixels
+    pub page_width: u32,
+    /// Height of the rendered page in pixels
+    pub page_height: u32,
+    /// List of [PDFLineData] objects
+    pub lines: Vec<PDFLineData>,
+}
+
+impl PDFPageLines {
+    /// Iterate over the lines of type `line_type` (e.g. `math` or `table`)
+    pub fn lines_of_type<'a>(
+        &'a self,
+        line_type: &'a str,
+    ) -> impl Iterator<Item = &'a PDFLineData> {
+        self.lines
+            .iter()
+            .filter(move |line| line.r#type == line_type)
+    }
+
+    /// Contour of `line` relative to the size of the page, (0, 0) is the top left corner and (1, 1)
+    /// the bottom right corner
+    pub fn relative_contour(&self, line: &PDFLineData) -> Vec<(f32, f32)> {
+        let (width, height) = (self.page_width as f32, self.page_height as f32);
+        line.cnt
+            .iter()
+            .map(|&(x, y)| (x as f32 / width, y as f32 / height))
+            .collect()
+    }
+
+    /**
+    Contour of `line` in the coordinates of the PDF page of `width`×`height` points. The origin is
+    at the bottom left corner as in the PDF user space, so the contour can be used for annotating
+    the original PDF.
+    */
+    pub fn to_pdf_points(&self, line: &PDFLineData, width: f32, height: f32) -> Vec<(f32, f32)> {
+        self.relative_contour(line)
+            .into_iter()
+            .map(|(x, y)| (x * width, (1. - y) * height))
+            .collect()
+    }
+} // }}}
+
+// pub struct PDFLineData {{{
+/// A line of a PDF page. It extends the [LineData] of the _text_ endpoint with the fields that are
+/// specific to PDFs and dereferences to it.
+#[derive(Debug, Deserialize, PartialEq)]
+pub struct PDFLineData {
+    /// Fields shared with the _text_ endpoint line data (`type`, `cnt`, `text`, `confidence`, ...)
+    #[serde(flatten)]
+    pub line_data: LineData,
+    /// ID of the line
+    pub id: Option<String>,
+    /// ID of the line that contains this line (e.g. a table containing the cell)
+    pub parent_id: Option<String>,
+    /// IDs of the lines that this line contains
+    pub children_ids: Option<Vec<String>>,
+    /// Bounding box of the line, pixel coordinates
+    pub region: Option<LineRegion>,
+    /// Index of the line on the page
+    pub line: Option<u32>,
+    /// Index of the column that the line is in
+    pub column: Option<u32>,
+    /// Estimated font size of the line
+    pub font_size: Option<f32>,
+    /// Recognized `text_display` format
+    pub text_display: Option<String>,
+    /// Specifies if printed content was detected in the line
+    pub is_printed: Option<bool>,
+    /// Specifies if handwritten content was detected in the line
+    pub is_handwritten: Option<bool>,
+    /// Whether the line is part of the converted output
+    pub conversion_output: Option<bool>,
+}
+
+impl Deref for PDFLineData {
+    type Target = LineData;
+
+    fn deref(&self) -> &Self::Target {
+        &self.line_data
+    }
+}
+
+/// Bounding box of a line in pixel coordinates
+#[derive(Debug, Deserialize, PartialEq, Clone, Copy)]
+pub struct LineRegion {
+    pub top_left_x: i32,
+    pub top_left_y: i32,
+    pub width: i32,
+    pub height: i32,
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod pdf_lines_tests {
+    use super::{LineRegion, PDFLines};
+    use serde_json::json;
+
+    fn lines() -> PDFLines {
+        serde_json::from_value(json!({
+            "pages": [
+                {
+                    "image_id": "2021_10_01_abc-1",
+                    "page": 1,
+                    "page_width": 1000,
+                    "page_height": 2000,
+                    "lines": [
+                        {
+                            "id": "a1",
+                            "type": "text",
+                            "cnt": [[100, 200], [900, 200], [900, 300], [100, 300]],
+                            "region": {"top_left_x": 100, "top_left_y": 200, "width": 800, "height": 100},
+                            "line": 0,
+                            "column": 0,
+                            "font_size": 12,
+                            "text": "Theorem 1",
+                            "confidence": 0.99,
+                            "confidence_rate": 0.99,
+                            "is_printed": true,
+                            "is_handwritten": false,
+                            "conversion_output": true
+                        },
+                        {
+                            "id": "a2",
+                            "type": "math",
+                            "cnt": [[0, 0], [500, 0], [500, 1000], [0, 1000]],
+                            "text": "\\[ x^2 \\]",
+                            "confidence": 0.3
+                        }
+                    ]
+                },
+                {
+                    "page": 2,
+                    "page_width": 1000,
+                    "page_height": 2000,
+                    "lines": [
+                        {
+                            "type": "math",
+                            "cnt": [[10, 10], [20, 20]],
+                            "text": "\\( y \\)",
+                            "confidence": 0.95
+                        }
+                    ]
+                }
+            ]
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn deserialize_lines() {
+        //{{{
+        let lines = lines();
+        assert_eq!(lines.pages.len(), 2);
+        let line = &lines.pages[0].lines[0];
+        assert_eq!(line.r#type, "text");
+        assert_eq!(line.text.as_deref(), Some("Theorem 1"));
+        assert!(line.included);
+        assert_eq!(line.font_size, Some(12.));
+        assert_eq!(
+            line.region,
+            Some(LineRegion {
+                top_left_x: 100,
+                top_left_y: 200,
+                width: 800,
+                height: 100
+            })
+        );
+        assert_eq!(lines.page(2).unwrap().lines.len(), 1);
+        assert!(lines.page(3).is_none());
+    } //}}}
+
+    #[test]
+    fn filter_lines() {
+        //{{{
+        let lines = lines();
+        let math: Vec<u32> = lines
+            .lines_of_type("math")
+            .map(|(page, _)| page.page)
+            .collect();
+        assert_eq!(math, vec![1, 2]);
+        assert_eq!(lines.pages[0].lines_of_type("text").count(), 1);
+
+        let low: Vec<&str> = lines
+            .low_confidence(0.9)
+            .filter_map(|(_, line)| line.id.as_deref())
+            .collect();
+        assert_eq!(low, vec!["a2"]);
+    } //}}}
+
+    #[test]
+    fn page_coordinates() {
+        //{{{
+        let lines = lines();
+        let page = &lines.pages[0];
+        let line = &page.lines[1];
+        assert_eq!(
+            page.relative_contour(line),
+            vec![(0., 0.), (0.5, 0.), (0.5, 0.5), (0., 0.5)]
+        );
+        assert_eq!(
+            page.to_pdf_points(line, 600., 800.),
+            vec![(0., 800.), (300., 800.), (300., 400.), (0., 400.)]
+        );
+    } //}}}
+}
+// }}}
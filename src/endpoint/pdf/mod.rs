@@ -1,23 +1,43 @@
 mod error;
+mod job;
+mod lines;
 mod options;
+mod page_ranges;
 mod response;
+mod results;
+mod split;
+mod tex_zip;
 
 pub use super::shared_objects::request::{AlphabetsAllowed, MetaData};
-use error::PDFError;
-pub use options::PDFOptions;
-use reqwest::Url;
-use response::PDFResponse;
-use serde::{
-    ser::{SerializeStruct, Serializer},
-    Serialize,
+use super::{super::MATHPIX_APIURL, MathpixEndpoint};
+pub use error::{
+    BadPDFOptionError, PDFError, PDFOptionsError, PDFPathError, TexZipError,
+    UnreasonableOptionsError,
 };
-use std::{convert::TryFrom, path::PathBuf};
+pub use job::{PDFOutputFormat, PdfJob, PollOptions};
+pub use lines::{LineRegion, PDFLineData, PDFLines, PDFPageLines};
+pub use options::{ConversionFormat, ConversionFormats, PDFOptions};
+pub use page_ranges::{PageRange, PageRanges, PageRangesError};
+use reqwest::{
+    header::{HeaderMap, CONTENT_TYPE},
+    multipart::{Form, Part},
+    Url,
+};
+pub use response::{PDFPageChunk, PDFProcessingStatus, PDFResponse, PDFStatusResponse};
+pub use results::{PDFResult, PDFResults, PDFResultsQuery};
+use serde::{Serialize, Serializer};
+pub use split::{PDFChunk, SplitOptions, SplitPDF, SplitPdfJob};
+use std::{
+    convert::{TryFrom, TryInto},
+    path::PathBuf,
+};
+pub use tex_zip::{extract_tex_zip, ExtractOptions, TexProject};
 
 const PDF_EXTENSIONS: &[&str] = &["pdf"];
 
 // PDF {{{
 #[derive(Debug)]
-/// This structs contains the possible items that the _text_ endpoint accepts
+/// This structs contains the possible items that the _pdf_ endpoint accepts
 pub struct PDF {
     /// > Source of PDF
     pub src: PDFSrc,
@@ -25,18 +45,136 @@ pub struct PDF {
     pub options: PDFOptions,
 }
 
+/// Body of the request when the PDF is sent as an URL
+#[derive(Serialize)]
+struct PDFUrlBody<'a> {
+    url: String,
+    #[serde(flatten)]
+    options: &'a PDFOptions,
+}
+
+/**
+When the source is a `PDFSrc::Url`, the PDF is serialized as the `JSON` body of the request. When
+the source is a `PDFSrc::Path`, only the options are serialized, since those are what is sent in
+the `options_json` part of the `multipart/form-data` request along with the file.
+*/
 impl Serialize for PDF {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
-        let mut state = serializer.serialize_struct("PostPDF", 2)?;
-        if let PDFSrc::Url(url) = &self.src {
-            state.serialize_field("url", &url.to_string())?;
+        match &self.src {
+            PDFSrc::Url(url) => PDFUrlBody {
+                url: url.to_string(),
+                options: &self.options,
+            }
+            .serialize(serializer),
+            PDFSrc::Path(_) => self.options.serialize(serializer),
+        }
+    }
+}
+
+impl PDF {
+    /**
+    Send the PDF to the server and return a [PdfJob] handle for following the processing and
+    downloading the results.
+    */
+    pub async fn start_job<H: Into<super::AuthHeader>>(
+        &self,
+        header: H,
+    ) -> Result<PdfJob, PDFError> {
+        let header = header.into();
+        let response = self.send_request(header.clone()).await?;
+        match response.pdf_id {
+            Some(pdf_id) => Ok(PdfJob::new(pdf_id, header)),
+            None => Err(PDFError::Processing(
+                response
+                    .error
+                    .unwrap_or_else(|| "no pdf_id in the response".to_string()),
+            )),
+        }
+    }
+
+    /// Create the `multipart/form-data` body for uploading a local PDF file
+    fn multipart_form(&self, path: &PDFPath) -> Result<Form, PDFError> {
+        let file_name = path
+            .pdf_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file.pdf".to_string());
+        multipart_form(&self.options, file_name, std::fs::read(&path.pdf_path)?)
+    }
+}
+
+/// Create the `multipart/form-data` body for uploading the PDF `bytes` with `options`
+fn multipart_form(
+    options: &PDFOptions,
+    file_name: String,
+    bytes: Vec<u8>,
+) -> Result<Form, PDFError> {
+    let file = Part::bytes(bytes)
+        .file_name(file_name)
+        .mime_str("application/pdf")?;
+    Ok(Form::new()
+        .part("file", file)
+        .text("options_json", serde_json::to_string(options)?))
+}
+
+impl MathpixEndpoint for PDF {
+    //{{{
+    type Src = PDFSrc;
+    type Error = PDFError;
+    type Options = PDFOptions;
+    type Response = PDFResponse;
+
+    fn new<S, E>(options: Option<Self::Options>, src: S) -> Result<Self, Self::Error>
+    where
+        S: TryInto<PDFSrc, Error = E>,
+        Self::Error: From<E>,
+        Self: Sized,
+    {
+        Ok(Self {
+            src: src.try_into()?,
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    fn url(&self) -> reqwest::Url {
+        let mut url_str = MATHPIX_APIURL.to_string();
+        url_str.push_str("pdf");
+        reqwest::Url::parse(&url_str).unwrap()
+    }
+
+    fn to_request<H: Into<super::AuthHeader>>(
+        &self,
+        header: H,
+    ) -> Result<reqwest::Request, Self::Error> {
+        let mut headers: HeaderMap = header.into().into();
+        // NOTE: The content type of a multipart request has to contain the boundary which is set by
+        // the `RequestBuilder`. The `application/json` from the header would overwrite it.
+        if let PDFSrc::Path(_) = self.src {
+            headers.remove(CONTENT_TYPE);
         }
-        state.end()
+        Ok(self.to_request_builder()?.headers(headers).build()?)
+    }
+
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error> {
+        let builder = reqwest::Client::new().post(self.url());
+        Ok(match &self.src {
+            PDFSrc::Url(_) => builder.json(self),
+            PDFSrc::Path(path) => builder.multipart(self.multipart_form(path)?),
+        })
+    }
+
+    fn options(&mut self) -> &mut Self::Options {
+        &mut self.options
+    }
+
+    fn src(&mut self) -> Option<&mut Self::Src> {
+        Some(&mut self.src)
     }
 } //}}}
+  // }}}
 
 /// A checked pdf file path
 #[derive(Debug)]
@@ -45,23 +183,26 @@ pub struct PDFPath {
 }
 
 impl PDFPath {
-    pub fn new(path: PathBuf) -> Result<Self, String> {
+    pub fn new(path: PathBuf) -> Result<Self, PDFPathError> {
         Self::try_from(path)
     }
 }
 
 impl TryFrom<PathBuf> for PDFPath {
-    type Error = String;
+    type Error = PDFPathError;
 
     fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
-        let extension = path
-            .extension()
-            .ok_or_else(|| "Extension not found of no file passed".to_string())?;
+        let extension = path.extension().ok_or_else(|| {
+            PDFPathError::InvalidExtension(format!("File {:?} has an invalid extension.", path))
+        })?;
         match extension {
-            _ if PDF_EXTENSIONS.contains(&extension.to_str().unwrap().to_lowercase().as_str()) => {
+            _ if PDF_EXTENSIONS.contains(&extension.to_string_lossy().to_lowercase().as_str()) => {
                 Ok(PDFPath { pdf_path: path })
             }
-            _ => Err("Unsupported filetype. Must be a PDF file.".to_string()),
+            _ => Err(PDFPathError::UnsupportedFileType(format!(
+                "File {:?} has an unsupported filetype. Must be a PDF file.",
+                path
+            ))),
         }
     }
 }
@@ -74,12 +215,34 @@ pub enum PDFSrc {
     Path(PDFPath),
 }
 
+impl From<Url> for PDFSrc {
+    fn from(url: Url) -> Self {
+        PDFSrc::Url(url)
+    }
+}
+
+impl From<PDFPath> for PDFSrc {
+    fn from(path: PDFPath) -> Self {
+        PDFSrc::Path(path)
+    }
+}
+
+impl TryFrom<PathBuf> for PDFSrc {
+    type Error = PDFPathError;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        Ok(PDFSrc::Path(path.try_into()?))
+    }
+}
+
 // TESTS {{{
 #[cfg(test)]
 mod pdf_endpoint_tests {
-    use super::{PDFOptions, PDFSrc, PDF};
+    use super::super::super::header::AuthHeader;
+    use super::{MathpixEndpoint, PDFError, PDFOptions, PDFPathError, PDFSrc, PDF};
     use reqwest::Url;
     use serde_json::json;
+    use std::path::PathBuf;
 
     #[test]
     fn serialize_pdf() {
@@ -89,10 +252,60 @@ mod pdf_endpoint_tests {
             options: PDFOptions::default(),
         };
         let serilized = serde_json::to_value(pdf_body).unwrap();
-        let expected = json!({
-            "url" : "https://www.duckduckgo.com/"
-        });
+        let mut expected = serde_json::to_value(PDFOptions::default()).unwrap();
+        expected["url"] = json!("https://www.duckduckgo.com/");
         assert_eq!(serilized, expected);
     } // }}}
+
+    #[test]
+    fn new_pdf_from_path() {
+        // {{{
+        let pdf = PDF::new(None, PathBuf::from("./test/assets/test_pdf.pdf")).unwrap();
+        assert!(matches!(pdf.src, PDFSrc::Path(_)));
+
+        let pdf = PDF::new(None, PathBuf::from("./test/assets/test_encode_base64.jpg"));
+        assert!(matches!(
+            pdf,
+            Err(PDFError::Src(PDFPathError::UnsupportedFileType(_)))
+        ));
+    } // }}}
+
+    #[test]
+    fn url_request_is_json() {
+        // {{{
+        let mut options = PDFOptions::default();
+        options.rm_fonts(true);
+        let pdf = PDF::new(
+            Some(options.clone()),
+            Url::parse("https://www.duckduckgo.com/").unwrap(),
+        )
+        .unwrap();
+        let request = pdf.to_request(AuthHeader::new("id", "key")).unwrap();
+        assert_eq!(request.url().as_str(), "https://api.mathpix.com/v3/pdf");
+        assert_eq!(request.headers()["content-type"], "application/json");
+        assert_eq!(request.headers()["app_id"], "id");
+        let body: serde_json::Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        let mut expected = serde_json::to_value(options).unwrap();
+        expected["url"] = json!("https://www.duckduckgo.com/");
+        assert_eq!(body, expected);
+        assert_eq!(body["rm_fonts"], json!(true));
+    } // }}}
+
+    #[test]
+    fn path_request_is_multipart() {
+        // {{{
+        let pdf = PDF::new(None, PathBuf::from("./test/assets/test_pdf.pdf")).unwrap();
+        let request = pdf.to_request(AuthHeader::new("id", "key")).unwrap();
+        let content_type = request.headers()["content-type"].to_str().unwrap();
+        assert!(content_type.starts_with("multipart/form-data; boundary="));
+        assert_eq!(request.headers()["app_key"], "key");
+
+        let missing = PDF::new(None, PathBuf::from("./test/assets/missing.pdf")).unwrap();
+        assert!(matches!(
+            missing.to_request(AuthHeader::new("id", "key")),
+            Err(PDFError::Io(_))
+        ));
+    } // }}}
 }
 // }}}
@@ -1,6 +1,386 @@
+pub use super::super::shared_objects::request::{AlphabetsAllowed, MetaData};
+use super::error::{BadPDFOptionError, PDFOptionsError, UnreasonableOptionsError};
+use super::page_ranges::PageRanges;
 use serde::Serialize;
+use std::fmt;
 
-#[derive(Debug, Default, Serialize)]
+// PDFOptions {{{
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
 pub struct PDFOptions {
-    // TODO:  <21-07-21, kunzaatko> //
+    /// > Key value object
+    pub metadata: Option<MetaData>,
+    /// > Specify `{"docx": true}` or `{"tex.zip": true}` to enable conversion to those output formats
+    pub conversion_formats: Option<ConversionFormats>,
+    /// > Specifies begin inline math and end inline math delimiters
+    pub math_inline_delimiters: Option<(String, String)>,
+    /// > Specifies begin display math and end display math delimiters
+    pub math_display_delimiters: Option<(String, String)>,
+    /// > Determines whether extra white space is removed from equations in `latex_styled` and `text` formats. Default is `true`.
+    pub rm_spaces: Option<bool>,
+    /// > Determines whether font commands such as `\mathbf` and `\mathrm` are removed from equations in `latex_styled` and `text` formats. Default is `false`.
+    pub rm_fonts: Option<bool>,
+    /// > See [AlphabetsAllowed](https://docs.mathpix.com/?shell#alphabetsallowed-object) section, use this to specify which alphabets you don't want in the output
+    pub alphabets_allowed: Option<AlphabetsAllowed>,
+    /// > Specifies a page range as a comma-separated string. Examples include `2,4-6` which selects pages `[2,4,5,6]` and `2 - -2` which selects all pages starting with the second page and ending with the next-to-last page
+    pub page_ranges: Option<PageRanges>,
+    /// > Enables advanced table processing algorithm that supports very large and complex tables. Default is `false`.
+    pub enable_tables_fallback: Option<bool>,
+    /// > Specifies whether numbers are always math, e.g., `Answer: \( 17 \)` instead of `Answer: 17`. Default is `false`.
+    pub numbers_default_to_math: Option<bool>,
+    /// > Specifies whether sections and subsections should be numbered automatically. Default is `false`.
+    pub auto_number_sections: Option<bool>,
+    /// > Enables streaming of the results of the pages as they are processed, see [super::PdfJob::stream_pages]. Default is `false`.
+    pub streaming: Option<bool>,
+} // }}}
+
+impl PDFOptions {
+    //{{{
+    pub fn metadata(&mut self, val: MetaData) -> &mut Self {
+        self.metadata = Some(val);
+        self
+    }
+
+    pub fn add_conversion_format(&mut self, format: ConversionFormat) -> &mut Self {
+        //{{{
+        self.conversion_formats
+            .get_or_insert_with(ConversionFormats::default)
+            .set(format, true);
+        self
+    } //}}}
+
+    /// Add conversion formats to the options of the request
+    /// * possible inputs are "docx", "tex.zip", "html" and "md"
+    ///
+    /// # Examples
+    /// ```
+    /// use mathpixapi::endpoint::pdf::{ConversionFormats, PDFOptions};
+    /// let mut options = PDFOptions::default();
+    /// options.add_conversion_formats_from_strings(&["docx", "tex.zip"]).unwrap();
+    /// let expected = ConversionFormats {
+    ///     docx: Some(true),
+    ///     tex_zip: Some(true),
+    ///     html: None,
+    ///     md: None,
+    /// };
+    /// assert_eq!(options.conversion_formats, Some(expected));
+    /// ```
+    pub fn add_conversion_formats_from_strings<S, I: IntoIterator<Item = S>>(
+        &mut self,
+        formats: I,
+    ) -> Result<&mut Self, PDFOptionsError>
+    where
+        S: AsRef<str>,
+    {
+        //{{{
+        let formats = formats
+            .into_iter()
+            .map(|format| ConversionFormat::from_name(format.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        for format in formats {
+            self.add_conversion_format(format);
+        }
+        Ok(self)
+    } //}}}
+
+    pub fn math_inline_delimiters<S: Into<String>>(
+        &mut self,
+        begin: S,
+        end: S,
+    ) -> Result<&mut Self, PDFOptionsError> {
+        self.math_inline_delimiters = Some(delimiters(begin.into(), end.into())?);
+        Ok(self)
+    }
+
+    pub fn math_display_delimiters<S: Into<String>>(
+        &mut self,
+        begin: S,
+        end: S,
+    ) -> Result<&mut Self, PDFOptionsError> {
+        self.math_display_delimiters = Some(delimiters(begin.into(), end.into())?);
+        Ok(self)
+    }
+
+    pub fn rm_spaces(&mut self, val: bool) -> &mut Self {
+        self.rm_spaces = Some(val);
+        self
+    }
+
+    pub fn rm_fonts(&mut self, val: bool) -> &mut Self {
+        self.rm_fonts = Some(val);
+        self
+    }
+
+    /// Set which alphabets are allowed in the output. An alphabet prefixed with `no` or `!` is
+    /// disallowed.
+    pub fn alphabets_allowed<S: AsRef<str>>(
+        &mut self,
+        alphabets: &[S],
+    ) -> Result<&mut Self, PDFOptionsError> {
+        //{{{
+        let mut alphabets_allowed = self.alphabets_allowed.clone().unwrap_or_default();
+        for alphabet in alphabets {
+            let alphabet = alphabet.as_ref();
+            let result = match alphabet
+                .strip_prefix("no")
+                .or_else(|| alphabet.strip_prefix('!'))
+            {
+                Some(disallowed) => alphabets_allowed.disallow(vec![disallowed.to_string()]),
+                None => alphabets_allowed.allow(vec![alphabet.to_string()]),
+            };
+            result.map_err(|_| BadPDFOptionError::AlphabetAllowed(alphabet.to_string()))?;
+        }
+        if alphabets_allowed.all_false() {
+            return Err(UnreasonableOptionsError::NoAlphabetsAllowed.into());
+        }
+        self.alphabets_allowed = Some(alphabets_allowed);
+        Ok(self)
+    } //}}}
+
+    pub fn page_ranges(&mut self, val: PageRanges) -> &mut Self {
+        self.page_ranges = Some(val);
+        self
+    }
+
+    /// Set the pages to process from an expression such as `"1-3,7,10-"` (see [PageRanges])
+    pub fn page_ranges_from_string<S: AsRef<str>>(
+        &mut self,
+        val: S,
+    ) -> Result<&mut Self, PDFOptionsError> {
+        let page_ranges = val
+            .as_ref()
+            .parse::<PageRanges>()
+            .map_err(BadPDFOptionError::from)?;
+        Ok(self.page_ranges(page_ranges))
+    }
+
+    pub fn enable_tables_fallback(&mut self, val: bool) -> &mut Self {
+        self.enable_tables_fallback = Some(val);
+        self
+    }
+
+    pub fn numbers_default_to_math(&mut self, val: bool) -> &mut Self {
+        self.numbers_default_to_math = Some(val);
+        self
+    }
+
+    pub fn auto_number_sections(&mut self, val: bool) -> &mut Self {
+        self.auto_number_sections = Some(val);
+        self
+    }
+
+    pub fn streaming(&mut self, val: bool) -> &mut Self {
+        self.streaming = Some(val);
+        self
+    }
+} //}}}
+
+fn delimiters(begin: String, end: String) -> Result<(String, String), PDFOptionsError> {
+    if begin.is_empty() || end.is_empty() {
+        return Err(BadPDFOptionError::Delimiters(begin, end).into());
+    }
+    Ok((begin, end))
+}
+
+// ConversionFormats {{{
+/// Formats that the PDF should be converted to in addition to the `mmd` output
+#[derive(Debug, Default, Serialize, PartialEq, Clone)]
+pub struct ConversionFormats {
+    /// > DOCX file (compatible with MS Office, Google Docs, Libre Office)
+    pub docx: Option<bool>,
+    /// > LaTeX zip file (includes images)
+    #[serde(rename = "tex.zip")]
+    pub tex_zip: Option<bool>,
+    /// > HTML file rendered from the `mmd`
+    pub html: Option<bool>,
+    /// > Markdown file
+    pub md: Option<bool>,
+}
+
+impl ConversionFormats {
+    pub fn set(&mut self, format: ConversionFormat, val: bool) -> &mut Self {
+        match format {
+            ConversionFormat::Docx => self.docx = Some(val),
+            ConversionFormat::TexZip => self.tex_zip = Some(val),
+            ConversionFormat::Html => self.html = Some(val),
+            ConversionFormat::Md => self.md = Some(val),
+        }
+        self
+    }
+
+    /// The formats that are requested
+    pub fn enabled(&self) -> Vec<ConversionFormat> {
+        [
+            (ConversionFormat::Docx, self.docx),
+            (ConversionFormat::TexZip, self.tex_zip),
+            (ConversionFormat::Html, self.html),
+            (ConversionFormat::Md, self.md),
+        ]
+        .iter()
+        .filter(|(_, val)| *val == Some(true))
+        .map(|(format, _)| *format)
+        .collect()
+    }
+}
+
+/// A single format from [ConversionFormats]
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub enum ConversionFormat {
+    Docx,
+    TexZip,
+    Html,
+    Md,
+}
+
+impl ConversionFormat {
+    pub fn from_name(name: &str) -> Result<Self, BadPDFOptionError> {
+        match name {
+            "docx" => Ok(ConversionFormat::Docx),
+            "tex.zip" => Ok(ConversionFormat::TexZip),
+            "html" => Ok(ConversionFormat::Html),
+            "md" => Ok(ConversionFormat::Md),
+            other => Err(BadPDFOptionError::ConversionFormat(other.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for ConversionFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ConversionFormat::Docx => "docx",
+            ConversionFormat::TexZip => "tex.zip",
+            ConversionFormat::Html => "html",
+            ConversionFormat::Md => "md",
+        })
+    }
+}
+// }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod pdf_options_tests {
+    use super::super::error::{BadPDFOptionError, PDFOptionsError, UnreasonableOptionsError};
+    use super::super::page_ranges::PageRangesError;
+    use super::{AlphabetsAllowed, ConversionFormat, ConversionFormats, PDFOptions};
+    use serde_json::{json, Value::Null};
+
+    #[test]
+    fn serialize_pdf_options() {
+        //{{{
+        let mut options = PDFOptions::default();
+        options
+            .add_conversion_format(ConversionFormat::TexZip)
+            .rm_spaces(true)
+            .auto_number_sections(false)
+            .math_inline_delimiters("$", "$")
+            .unwrap()
+            .page_ranges_from_string("2,4-6")
+            .unwrap();
+        let serialized = serde_json::to_value(&options).unwrap();
+        let expected = json!({
+            "metadata": Null,
+            "conversion_formats": {
+                "docx": Null,
+                "tex.zip": true,
+                "html": Null,
+                "md": Null,
+            },
+            "math_inline_delimiters": ["$", "$"],
+            "math_display_delimiters": Null,
+            "rm_spaces": true,
+            "rm_fonts": Null,
+            "alphabets_allowed": Null,
+            "page_ranges": "2,4-6",
+            "enable_tables_fallback": Null,
+            "numbers_default_to_math": Null,
+            "auto_number_sections": false,
+            "streaming": Null,
+        });
+        assert_eq!(serialized, expected);
+    } //}}}
+
+    #[test]
+    fn conversion_formats_from_strings() {
+        //{{{
+        let mut options = PDFOptions::default();
+        options
+            .add_conversion_formats_from_strings(["docx", "html", "md"])
+            .unwrap();
+        assert_eq!(
+            options.conversion_formats,
+            Some(ConversionFormats {
+                docx: Some(true),
+                tex_zip: None,
+                html: Some(true),
+                md: Some(true),
+            })
+        );
+
+        let mut options = PDFOptions::default();
+        assert!(matches!(
+            options.add_conversion_formats_from_strings(["docx", "pptx"]),
+            Err(PDFOptionsError::BadOption(
+                BadPDFOptionError::ConversionFormat(_)
+            ))
+        ));
+        // NOTE: Nothing is added when one of the formats is wrong
+        assert_eq!(options.conversion_formats, None);
+    } //}}}
+
+    #[test]
+    fn delimiters() {
+        //{{{
+        let mut options = PDFOptions::default();
+        options.math_display_delimiters("\\[", "\\]").unwrap();
+        assert_eq!(
+            options.math_display_delimiters,
+            Some(("\\[".to_string(), "\\]".to_string()))
+        );
+        assert!(options.math_inline_delimiters("", "$").is_err());
+        assert_eq!(options.math_inline_delimiters, None);
+    } //}}}
+
+    #[test]
+    fn alphabets_allowed() {
+        //{{{
+        let mut options = PDFOptions::default();
+        options.alphabets_allowed(&["en", "noru", "!zh"]).unwrap();
+        assert_eq!(
+            options.alphabets_allowed,
+            Some(AlphabetsAllowed {
+                en: Some(true),
+                ru: Some(false),
+                zh: Some(false),
+                ..Default::default()
+            })
+        );
+        assert!(matches!(
+            options.alphabets_allowed(&["xx"]),
+            Err(PDFOptionsError::BadOption(
+                BadPDFOptionError::AlphabetAllowed(_)
+            ))
+        ));
+        assert!(matches!(
+            options.alphabets_allowed(&["noen", "nohi", "noja", "noko", "noth"]),
+            Err(PDFOptionsError::UnreasonableOptions(
+                UnreasonableOptionsError::NoAlphabetsAllowed
+            ))
+        ));
+    } //}}}
+
+    #[test]
+    fn page_ranges() {
+        //{{{
+        let mut options = PDFOptions::default();
+        options.page_ranges_from_string("10-, -2").unwrap();
+        assert_eq!(options.page_ranges, Some("10-,-2".parse().unwrap()));
+        assert!(matches!(
+            options.page_ranges_from_string("3-1"),
+            Err(PDFOptionsError::BadOption(BadPDFOptionError::PageRanges(
+                PageRangesError::ReversedRange { start: 3, end: 1 }
+            )))
+        ));
+        assert!(options.page_ranges_from_string("one").is_err());
+        assert_eq!(options.page_ranges, Some("10-,-2".parse().unwrap()));
+    } //}}}
 }
+// }}}
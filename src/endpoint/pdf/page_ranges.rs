@@ -0,0 +1,364 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+use thiserror::Error;
+
+// PageRange {{{
+/// A single item of [PageRanges]. Pages are numbered from 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PageRange {
+    /// A single page (e.g. `7`)
+    Page(u32),
+    /// All of the pages from the first to the second one including both (e.g. `1-3`)
+    Range(u32, u32),
+    /// All of the pages from the given one until the end of the document (e.g. `10-`)
+    From(u32),
+    /// The given number of pages at the end of the document (e.g. `-2`)
+    Last(u32),
+}
+
+impl PageRange {
+    /// Pages of the range in a document with `num_pages` pages
+    pub fn resolve(&self, num_pages: u32) -> Option<RangeInclusive<u32>> {
+        let (start, end) = match *self {
+            PageRange::Page(page) => (page, page),
+            PageRange::Range(start, end) => (start, end.min(num_pages)),
+            PageRange::From(start) => (start, num_pages),
+            PageRange::Last(count) => (num_pages.saturating_sub(count) + 1, num_pages),
+        };
+        if start > end || start > num_pages {
+            None
+        } else {
+            Some(start..=end)
+        }
+    }
+
+    /// Pages spanned by the range when it does not depend on the length of the document
+    fn bounds(&self) -> Option<(u32, u32)> {
+        match *self {
+            PageRange::Page(page) => Some((page, page)),
+            PageRange::Range(start, end) => Some((start, end)),
+            _ => None,
+        }
+    }
+
+    fn from_bounds(start: u32, end: u32) -> Self {
+        if start == end {
+            PageRange::Page(start)
+        } else {
+            PageRange::Range(start, end)
+        }
+    }
+}
+
+impl fmt::Display for PageRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PageRange::Page(page) => write!(f, "{}", page),
+            PageRange::Range(start, end) => write!(f, "{}-{}", start, end),
+            PageRange::From(start) => write!(f, "{}-", start),
+            PageRange::Last(count) => write!(f, "-{}", count),
+        }
+    }
+}
+
+impl FromStr for PageRange {
+    type Err = PageRangesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        //{{{
+        let item = s.trim();
+        let page = |number: &str| -> Result<u32, PageRangesError> {
+            let number = number.trim();
+            let page: u32 = number
+                .parse()
+                .map_err(|_| PageRangesError::InvalidPage(number.to_string()))?;
+            if page == 0 {
+                return Err(PageRangesError::ZeroPage(item.to_string()));
+            }
+            Ok(page)
+        };
+        if item.is_empty() {
+            return Err(PageRangesError::EmptyItem);
+        }
+        match item.split_once('-') {
+            None => Ok(PageRange::Page(page(item)?)),
+            Some((start, "")) => Ok(PageRange::From(page(start)?)),
+            Some(("", count)) => Ok(PageRange::Last(page(count)?)),
+            Some((start, end)) => {
+                let (start, end) = (page(start)?, page(end)?);
+                if start > end {
+                    return Err(PageRangesError::ReversedRange { start, end });
+                }
+                Ok(PageRange::from_bounds(start, end))
+            }
+        }
+    } //}}}
+}
+// }}}
+
+// PageRanges {{{
+/**
+Pages of a PDF that should be processed. It is parsed from a comma separated list of pages
+(`7`), ranges (`1-3`), open ranges until the end of the document (`10-`) and counts of pages at
+the end of the document (`-2` are the last two pages). The ranges are normalized when parsed, so
+that overlapping and adjacent ranges are merged.
+
+```
+use mathpixapi::endpoint::pdf::PageRanges;
+let pages: PageRanges = "7, 1-3, 2-4, 10-, 12, -2".parse().unwrap();
+assert_eq!(pages.to_string(), "1-4,7,10-,-2");
+assert_eq!(pages.resolve(20), vec![1..=4, 7..=7, 10..=20]);
+```
+*/
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PageRanges {
+    ranges: Vec<PageRange>,
+}
+
+impl PageRanges {
+    pub fn new<I: IntoIterator<Item = PageRange>>(ranges: I) -> Result<Self, PageRangesError> {
+        //{{{
+        let ranges: Vec<PageRange> = ranges.into_iter().collect();
+        if ranges.is_empty() {
+            return Err(PageRangesError::Empty);
+        }
+        for range in &ranges {
+            match *range {
+                PageRange::Page(0) | PageRange::Range(0, _) | PageRange::From(0) => {
+                    return Err(PageRangesError::ZeroPage(range.to_string()))
+                }
+                PageRange::Last(0) => return Err(PageRangesError::InvalidPage("0".to_string())),
+                PageRange::Range(start, end) if start > end => {
+                    return Err(PageRangesError::ReversedRange { start, end })
+                }
+                _ => {}
+            }
+        }
+        Ok(PageRanges {
+            ranges: Self::normalize(ranges),
+        })
+    } //}}}
+
+    /// Merge the overlapping and adjacent ranges and sort them
+    fn normalize(ranges: Vec<PageRange>) -> Vec<PageRange> {
+        //{{{
+        let from = ranges
+            .iter()
+            .filter_map(|range| match range {
+                PageRange::From(start) => Some(*start),
+                _ => None,
+            })
+            .min();
+        let last = ranges
+            .iter()
+            .filter_map(|range| match range {
+                PageRange::Last(count) => Some(*count),
+                _ => None,
+            })
+            .max();
+
+        let mut bounds: Vec<(u32, u32)> = ranges.iter().filter_map(PageRange::bounds).collect();
+        bounds.sort_unstable();
+        let mut merged: Vec<(u32, u32)> = Vec::with_capacity(bounds.len());
+        for (start, end) in bounds {
+            match merged.last_mut() {
+                Some((_, last_end)) if start <= last_end.saturating_add(1) => {
+                    *last_end = (*last_end).max(end)
+                }
+                _ => merged.push((start, end)),
+            }
+        }
+
+        let mut normalized = Vec::with_capacity(merged.len() + 2);
+        let mut from = from;
+        for (start, end) in merged {
+            match from {
+                // NOTE: the range reaches into the open range so it is absorbed by it
+                Some(from_start) if end.saturating_add(1) >= from_start => {
+                    from = Some(from_start.min(start))
+                }
+                _ => normalized.push(PageRange::from_bounds(start, end)),
+            }
+        }
+        normalized.extend(from.map(PageRange::From));
+        normalized.extend(last.map(PageRange::Last));
+        normalized
+    } //}}}
+
+    pub fn ranges(&self) -> &[PageRange] {
+        &self.ranges
+    }
+
+    /**
+    Pages selected in a document with `num_pages` pages as sorted and disjoint ranges. Pages
+    beyond the end of the document are left out.
+    */
+    pub fn resolve(&self, num_pages: u32) -> Vec<RangeInclusive<u32>> {
+        //{{{
+        let mut bounds: Vec<(u32, u32)> = self
+            .ranges
+            .iter()
+            .filter_map(|range| range.resolve(num_pages))
+            .map(|range| (*range.start(), *range.end()))
+            .collect();
+        bounds.sort_unstable();
+        let mut resolved: Vec<RangeInclusive<u32>> = Vec::with_capacity(bounds.len());
+        for (start, end) in bounds {
+            match resolved.last_mut() {
+                Some(last) if start <= last.end() + 1 => {
+                    *last = *last.start()..=end.max(*last.end())
+                }
+                _ => resolved.push(start..=end),
+            }
+        }
+        resolved
+    } //}}}
+
+    /**
+    The ranges in the syntax of the `page_ranges` option of the _pdf_ endpoint. Negative numbers
+    count the pages from the end of the document, so `10-` is `10 - -1` and `-2` is `-2 - -1`.
+    */
+    pub fn to_api_string(&self) -> String {
+        self.ranges
+            .iter()
+            .map(|range| match range {
+                PageRange::Page(page) => page.to_string(),
+                PageRange::Range(start, end) => format!("{}-{}", start, end),
+                PageRange::From(start) => format!("{} - -1", start),
+                PageRange::Last(count) => format!("-{} - -1", count),
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+}
+
+impl FromStr for PageRanges {
+    type Err = PageRangesError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.trim().is_empty() {
+            return Err(PageRangesError::Empty);
+        }
+        PageRanges::new(
+            s.split(',')
+                .map(str::parse)
+                .collect::<Result<Vec<PageRange>, _>>()?,
+        )
+    }
+}
+
+impl fmt::Display for PageRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ranges: Vec<String> = self.ranges.iter().map(PageRange::to_string).collect();
+        f.write_str(&ranges.join(","))
+    }
+}
+
+impl Serialize for PageRanges {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_api_string())
+    }
+}
+// }}}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PageRangesError {
+    #[error("EmptyPageRanges: at least one page or page range is required (e.g. \"1-3,7\")")]
+    Empty,
+    #[error("EmptyPageRange: there is an empty item between two commas")]
+    EmptyItem,
+    #[error("InvalidPage: {0:?} is not a page number. Pages are positive whole numbers.")]
+    InvalidPage(String),
+    #[error("ZeroPage: {0:?} contains page 0, but pages are numbered from 1")]
+    ZeroPage(String),
+    #[error("ReversedRange: the range {start}-{end} ends before it starts (did you mean {end}-{start}?)")]
+    ReversedRange { start: u32, end: u32 },
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod page_ranges_tests {
+    use super::{PageRange, PageRanges, PageRangesError};
+    use serde_json::json;
+
+    #[test]
+    fn parse_page_ranges() {
+        //{{{
+        let pages: PageRanges = "1-3,7,10-".parse().unwrap();
+        assert_eq!(
+            pages.ranges(),
+            &[
+                PageRange::Range(1, 3),
+                PageRange::Page(7),
+                PageRange::From(10)
+            ]
+        );
+        let pages: PageRanges = " -2 ".parse().unwrap();
+        assert_eq!(pages.ranges(), &[PageRange::Last(2)]);
+        let pages: PageRanges = "4-4".parse().unwrap();
+        assert_eq!(pages.ranges(), &[PageRange::Page(4)]);
+    } //}}}
+
+    #[test]
+    fn parse_errors() {
+        //{{{
+        assert_eq!("".parse::<PageRanges>(), Err(PageRangesError::Empty));
+        assert_eq!(
+            "1,,2".parse::<PageRanges>(),
+            Err(PageRangesError::EmptyItem)
+        );
+        assert_eq!(
+            "1,a-3".parse::<PageRanges>(),
+            Err(PageRangesError::InvalidPage("a".to_string()))
+        );
+        assert_eq!(
+            "0-3".parse::<PageRanges>(),
+            Err(PageRangesError::ZeroPage("0-3".to_string()))
+        );
+        assert_eq!(
+            "5-2".parse::<PageRanges>(),
+            Err(PageRangesError::ReversedRange { start: 5, end: 2 })
+        );
+        assert!("1-2-3".parse::<PageRanges>().is_err());
+        assert!("--2".parse::<PageRanges>().is_err());
+        assert!(PageRanges::new(vec![]).is_err());
+    } //}}}
+
+    #[test]
+    fn normalize_page_ranges() {
+        //{{{
+        let pages: PageRanges = "9,3-5,1,2,6,12-,14-20,11,-2,-3".parse().unwrap();
+        assert_eq!(pages.to_string(), "1-6,9,11-,-3");
+
+        let pages: PageRanges = "20-,5-,1-2".parse().unwrap();
+        assert_eq!(pages.to_string(), "1-2,5-");
+    } //}}}
+
+    #[test]
+    fn resolve_page_ranges() {
+        //{{{
+        let pages: PageRanges = "1-3,7,10-".parse().unwrap();
+        assert_eq!(pages.resolve(12), vec![1..=3, 7..=7, 10..=12]);
+        assert_eq!(pages.resolve(5), vec![1..=3]);
+
+        let pages: PageRanges = "1,-2".parse().unwrap();
+        assert_eq!(pages.resolve(10), vec![1..=1, 9..=10]);
+        assert_eq!(pages.resolve(2), vec![1..=2]);
+    } //}}}
+
+    #[test]
+    fn serialize_page_ranges() {
+        //{{{
+        let pages: PageRanges = "1-3,7,10-,-2".parse().unwrap();
+        assert_eq!(
+            serde_json::to_value(&pages).unwrap(),
+            json!("1-3,7,10 - -1,-2 - -1")
+        );
+    } //}}}
+}
+// }}}
@@ -1,3 +1,107 @@
+pub use super::super::shared_objects::response::ErrorInfo;
+use serde::Deserialize;
+
+// pub struct PDFResponse {{{
+/// Response of the server to a PDF upload. The PDF is processed asynchronously and the results
+/// have to be requested with the returned `pdf_id`.
+#[derive(Debug, Deserialize)]
 pub struct PDFResponse {
-    // TODO:  <21-07-21, kunzaatko> //
+    /// Tracking ID to get status and result of the processing
+    pub pdf_id: Option<String>,
+    /// US locale error message
+    pub error: Option<String>,
+    /// Error info object
+    pub error_info: Option<ErrorInfo>,
+} // }}}
+
+// pub struct PDFStatusResponse {{{
+/// Response of the server to a request for the status of PDF processing
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PDFStatusResponse {
+    /// Processing status of the PDF
+    pub status: PDFProcessingStatus,
+    /// Total number of pages in the PDF
+    pub num_pages: Option<u32>,
+    /// Number of pages that have been processed
+    pub num_pages_completed: Option<u32>,
+    /// Percentage of the pages that have been processed
+    pub percent_done: Option<f32>,
+    /// US locale error message
+    pub error: Option<String>,
+} // }}}
+
+// pub enum PDFProcessingStatus {{{
+/// > The status of the processing is one of `received`, `loaded`, `split`, `completed` or `error`
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PDFProcessingStatus {
+    /// The PDF was received by the server
+    Received,
+    /// The PDF was loaded by the server
+    Loaded,
+    /// The PDF was split into pages which are being processed
+    Split,
+    /// All of the pages were processed and the results are available
+    Completed,
+    /// The processing of the PDF failed
+    Error,
+    /// Status that this version of the library does not know about
+    #[serde(other)]
+    Unknown,
 }
+
+impl PDFProcessingStatus {
+    /// Whether the processing of the PDF has ended (successfully or not)
+    pub fn is_finished(&self) -> bool {
+        matches!(
+            self,
+            PDFProcessingStatus::Completed | PDFProcessingStatus::Error
+        )
+    }
+} // }}}
+
+// pub struct PDFPageChunk {{{
+/// Result of a single page as it is emitted by the server when the PDF is processed with the
+/// `streaming` option
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PDFPageChunk {
+    /// Index of the page in the PDF (numbered from 1)
+    pub page_idx: u32,
+    /// Mathpix Markdown of the page
+    pub text: String,
+    /// Number of pages of the PDF that are being processed
+    pub pdf_selected_len: Option<u32>,
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod pdf_response_tests {
+    use super::{PDFProcessingStatus, PDFStatusResponse};
+    use serde_json::json;
+
+    #[test]
+    fn deserialize_status() {
+        //{{{
+        let response = json!({
+            "status": "split",
+            "num_pages": 9,
+            "percent_done": 11.11111111111111,
+            "num_pages_completed": 1
+        });
+        let deserialized: PDFStatusResponse = serde_json::from_value(response).unwrap();
+        let expected = PDFStatusResponse {
+            status: PDFProcessingStatus::Split,
+            num_pages: Some(9),
+            num_pages_completed: Some(1),
+            percent_done: Some(11.111111),
+            error: None,
+        };
+        assert_eq!(deserialized, expected);
+        assert!(!deserialized.status.is_finished());
+
+        let unknown: PDFStatusResponse =
+            serde_json::from_value(json!({"status": "queued"})).unwrap();
+        assert_eq!(unknown.status, PDFProcessingStatus::Unknown);
+    } //}}}
+}
+// }}}
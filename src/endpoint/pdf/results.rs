@@ -0,0 +1,162 @@
+use super::super::super::{header::AuthHeader, MATHPIX_APIURL};
+use super::error::PDFError;
+use super::response::PDFProcessingStatus;
+use reqwest::{header::HeaderMap, Url};
+use serde::{Deserialize, Serialize};
+
+// PDFResultsQuery {{{
+/**
+Request for the list of the PDFs processed with the API keys of the account (`GET v3/pdf-results`).
+The results are paginated, the first page is `1`.
+
+```no_run
+# async fn run() -> Result<(), mathpixapi::endpoint::pdf::PDFError> {
+use mathpixapi::endpoint::pdf::PDFResultsQuery;
+use mathpixapi::header::AuthHeader;
+
+let results = PDFResultsQuery::default()
+    .per_page(20)
+    .from_date("2021-09-01")
+    .send(AuthHeader::new("ID", "KEY"))
+    .await?;
+for pdf in results.pdfs {
+    println!("{} {:?} {:?}", pdf.id, pdf.status, pdf.input_file);
+}
+# Ok(())
+# }
+```
+*/
+#[derive(Debug, Default, Serialize, Clone, PartialEq)]
+pub struct PDFResultsQuery {
+    /// > Page of the results, starting at 1
+    pub page: Option<u32>,
+    /// > Number of results per page
+    pub per_page: Option<u32>,
+    /// > Starting (included) ISO datetime of the results
+    pub from_date: Option<String>,
+    /// > Ending (excluded) ISO datetime of the results
+    pub to_date: Option<String>,
+    #[serde(skip)]
+    base_url: Option<Url>,
+}
+
+impl PDFResultsQuery {
+    field_builder!(page, u32);
+    field_builder!(per_page, u32);
+
+    pub fn from_date<S: Into<String>>(&mut self, val: S) -> &mut Self {
+        self.from_date = Some(val.into());
+        self
+    }
+
+    pub fn to_date<S: Into<String>>(&mut self, val: S) -> &mut Self {
+        self.to_date = Some(val.into());
+        self
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(&mut self, base_url: Url) -> &mut Self {
+        self.base_url = Some(base_url);
+        self
+    }
+
+    /// Request the page of the results
+    pub async fn send<H: Into<AuthHeader>>(&self, header: H) -> Result<PDFResults, PDFError> {
+        //{{{
+        let url = self
+            .base_url
+            .clone()
+            .unwrap_or_else(|| Url::parse(MATHPIX_APIURL).unwrap())
+            .join("pdf-results")
+            .unwrap();
+        let headers: HeaderMap = header.into().into();
+        Ok(reqwest::Client::new()
+            .get(url)
+            .headers(headers)
+            .query(self)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?)
+    } //}}}
+} // }}}
+
+// PDFResults {{{
+/// A page of the PDFs processed with the API keys of the account
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PDFResults {
+    /// List of [PDFResult] objects
+    pub pdfs: Vec<PDFResult>,
+}
+
+/// A PDF processed with the API keys of the account
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct PDFResult {
+    /// Tracking ID of the PDF
+    pub id: String,
+    /// Name of the uploaded file or the URL of the PDF
+    pub input_file: Option<String>,
+    /// Processing status of the PDF
+    pub status: PDFProcessingStatus,
+    /// ISO datetime when the PDF was received
+    pub created_at: Option<String>,
+    /// ISO datetime when the processing was last updated
+    pub modified_at: Option<String>,
+    /// Total number of pages in the PDF
+    pub num_pages: Option<u32>,
+    /// Number of pages that have been processed
+    pub num_pages_completed: Option<u32>,
+    /// Percentage of the pages that have been processed
+    pub percent_done: Option<f32>,
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod pdf_results_tests {
+    use super::super::super::test_server::{Reply, TestServer};
+    use super::{PDFProcessingStatus, PDFResultsQuery};
+    use crate::header::AuthHeader;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn list_results() {
+        //{{{
+        let server = TestServer::spawn(|request| {
+            assert_eq!(request.method, "GET");
+            assert_eq!(request.header("app_key"), Some("key"));
+            Reply::json(json!({"pdfs": [
+                {
+                    "id": "2021_09_07_abc",
+                    "input_file": "textbook.pdf",
+                    "status": "completed",
+                    "created_at": "2021-09-07T10:00:00.000Z",
+                    "modified_at": "2021-09-07T10:01:00.000Z",
+                    "num_pages": 3,
+                    "num_pages_completed": 3,
+                    "percent_done": 100
+                },
+                {"id": "2021_09_08_def", "status": "split"}
+            ]}))
+        })
+        .await;
+
+        let results = PDFResultsQuery::default()
+            .page(2)
+            .per_page(10)
+            .from_date("2021-09-01")
+            .with_base_url(server.url.clone())
+            .send(AuthHeader::new("id", "key"))
+            .await
+            .unwrap();
+        assert_eq!(
+            server.requests()[0].path,
+            "/pdf-results?page=2&per_page=10&from_date=2021-09-01"
+        );
+        assert_eq!(results.pdfs.len(), 2);
+        assert_eq!(results.pdfs[0].input_file.as_deref(), Some("textbook.pdf"));
+        assert_eq!(results.pdfs[0].percent_done, Some(100.));
+        assert_eq!(results.pdfs[1].status, PDFProcessingStatus::Split);
+    } //}}}
+}
+// }}}
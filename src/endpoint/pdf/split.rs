@@ -0,0 +1,600 @@
+use super::super::super::{header::AuthHeader, MATHPIX_APIURL};
+use super::super::poll::wait_for;
+use super::error::PDFError;
+use super::job::{PDFOutputFormat, PdfJob, PollOptions};
+use super::lines::PDFLines;
+use super::options::PDFOptions;
+use super::response::{PDFProcessingStatus, PDFResponse, PDFStatusResponse};
+use super::{multipart_form, PDFPath};
+use futures::future::try_join_all;
+use lopdf::Document;
+use reqwest::{header::HeaderMap, header::CONTENT_TYPE, Url};
+use std::collections::HashSet;
+use std::path::Path;
+
+// SplitOptions {{{
+/**
+Limits for the chunks that a local PDF is split into. A chunk respects both of the limits when both
+are set. With no limits the PDF is sent in a single chunk.
+*/
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SplitOptions {
+    /// Maximal number of pages of a chunk
+    pub max_pages: Option<u32>,
+    /// Maximal size of a chunk in bytes
+    pub max_bytes: Option<u64>,
+}
+
+impl SplitOptions {
+    field_builder!(max_pages, u32);
+    field_builder!(max_bytes, u64);
+} // }}}
+
+// PDFChunk {{{
+/// A part of a PDF that is sent as a separate PDF
+#[derive(Debug, Clone)]
+pub struct PDFChunk {
+    /// Numbers of the pages of the original PDF that the chunk consists of (numbered from 1)
+    pub pages: Vec<u32>,
+    /// The chunk as a PDF document
+    pub bytes: Vec<u8>,
+}
+
+impl PDFPath {
+    /**
+    Split the PDF into chunks within the limits of `split`. Only the pages selected by the
+    `page_ranges` of `options` are included in the chunks.
+
+    Fails with `PDFError::Split` if a single page does not fit in `max_bytes`.
+    */
+    pub fn split(
+        &self,
+        split: &SplitOptions,
+        options: &PDFOptions,
+    ) -> Result<Vec<PDFChunk>, PDFError> {
+        //{{{
+        let document = Document::load(&self.pdf_path)?;
+        let num_pages = document.get_pages().len() as u32;
+        let pages: Vec<u32> = match &options.page_ranges {
+            Some(page_ranges) => page_ranges
+                .resolve(num_pages)
+                .into_iter()
+                .flatten()
+                .collect(),
+            None => (1..=num_pages).collect(),
+        };
+        if pages.is_empty() {
+            return Err(PDFError::Split(format!(
+                "no pages of {:?} are selected",
+                self.pdf_path
+            )));
+        }
+        let max_pages = split.max_pages.unwrap_or(u32::MAX).max(1) as usize;
+
+        let mut chunks = Vec::new();
+        for group in pages.chunks(max_pages) {
+            match split.max_bytes {
+                Some(max_bytes) => chunks.extend(split_by_size(&document, group, max_bytes)?),
+                None => chunks.push(PDFChunk {
+                    pages: group.to_vec(),
+                    bytes: extract_pages(&document, group)?,
+                }),
+            }
+        }
+        Ok(chunks)
+    } //}}}
+}
+
+/**
+Split `pages` into the longest runs of pages that fit in `max_bytes`. Every extraction of pages
+copies the whole document, so the length of a chunk is found by doubling it while it fits and then
+by a binary search, which needs a logarithmic number of extractions per chunk.
+*/
+fn split_by_size(
+    document: &Document,
+    pages: &[u32],
+    max_bytes: u64,
+) -> Result<Vec<PDFChunk>, PDFError> {
+    //{{{
+    let fits = |pages: &[u32]| -> Result<Option<Vec<u8>>, PDFError> {
+        let bytes = extract_pages(document, pages)?;
+        Ok(Some(bytes).filter(|bytes| bytes.len() as u64 <= max_bytes))
+    };
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    while start < pages.len() {
+        let remaining = &pages[start..];
+        // NOTE: `fitting` pages fit in the chunk and `failing` pages (if any) do not
+        let mut bytes = fits(&remaining[..1])?.ok_or_else(|| {
+            PDFError::Split(format!(
+                "page {} does not fit in {} bytes",
+                remaining[0], max_bytes
+            ))
+        })?;
+        let mut fitting = 1;
+        let mut failing = None;
+        while failing.is_none() && fitting < remaining.len() {
+            let len = (fitting * 2).min(remaining.len());
+            match fits(&remaining[..len])? {
+                Some(chunk) => {
+                    fitting = len;
+                    bytes = chunk;
+                }
+                None => failing = Some(len),
+            }
+        }
+        if let Some(mut failing) = failing {
+            while failing - fitting > 1 {
+                let len = (fitting + failing) / 2;
+                match fits(&remaining[..len])? {
+                    Some(chunk) => {
+                        fitting = len;
+                        bytes = chunk;
+                    }
+                    None => failing = len,
+                }
+            }
+        }
+        chunks.push(PDFChunk {
+            pages: remaining[..fitting].to_vec(),
+            bytes,
+        });
+        start += fitting;
+    }
+    Ok(chunks)
+} //}}}
+
+/// A copy of `document` with only the `pages`
+fn extract_pages(document: &Document, pages: &[u32]) -> Result<Vec<u8>, PDFError> {
+    let pages: HashSet<u32> = pages.iter().copied().collect();
+    let mut chunk = document.clone();
+    let deleted: Vec<u32> = chunk
+        .get_pages()
+        .into_keys()
+        .filter(|page| !pages.contains(page))
+        .collect();
+    chunk.delete_pages(&deleted);
+    chunk.prune_objects();
+    let mut bytes = Vec::new();
+    chunk.save_to(&mut bytes)?;
+    Ok(bytes)
+}
+// }}}
+
+// SplitPDF {{{
+/**
+A local PDF that is sent in chunks to stay under the upload limits of the server. The chunks are
+processed as separate PDFs and the [SplitPdfJob] merges the results back together.
+
+```no_run
+# async fn run() -> Result<(), mathpixapi::endpoint::pdf::PDFError> {
+use mathpixapi::endpoint::pdf::{PDFOptions, PDFOutputFormat, PDFPath, PollOptions, SplitOptions, SplitPDF};
+use mathpixapi::header::AuthHeader;
+
+let mut split = SplitOptions::default();
+split.max_pages(100).max_bytes(50_000_000);
+let path = PDFPath::new("textbook.pdf".into())?;
+let job = SplitPDF::new(&path, PDFOptions::default(), &split)?
+    .start_job(AuthHeader::new("ID", "KEY"))
+    .await?;
+job.wait(&PollOptions::default(), |_| {}).await?;
+job.download_to_file(PDFOutputFormat::Mmd, "textbook.mmd").await?;
+# Ok(())
+# }
+```
+*/
+#[derive(Debug)]
+pub struct SplitPDF {
+    /// Options of the PDF. The `page_ranges` are already applied when splitting.
+    options: PDFOptions,
+    file_name: String,
+    chunks: Vec<PDFChunk>,
+    base_url: Url,
+}
+
+impl SplitPDF {
+    pub fn new(
+        path: &PDFPath,
+        options: PDFOptions,
+        split: &SplitOptions,
+    ) -> Result<Self, PDFError> {
+        let chunks = path.split(split, &options)?;
+        let file_name = path
+            .pdf_path
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "file".to_string());
+        Ok(SplitPDF {
+            options: PDFOptions {
+                page_ranges: None,
+                ..options
+            },
+            file_name,
+            chunks,
+            base_url: Url::parse(MATHPIX_APIURL).unwrap(),
+        })
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// The chunks that the PDF is sent in
+    pub fn chunks(&self) -> &[PDFChunk] {
+        &self.chunks
+    }
+
+    /// Send the chunks to the server one after another and return a handle to all of them
+    pub async fn start_job<H: Into<AuthHeader>>(&self, header: H) -> Result<SplitPdfJob, PDFError> {
+        //{{{
+        let header = header.into();
+        let mut headers: HeaderMap = header.clone().into();
+        headers.remove(CONTENT_TYPE);
+        let client = reqwest::Client::new();
+        let mut parts = Vec::with_capacity(self.chunks.len());
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            let file_name = format!("{}-{}.pdf", self.file_name, index + 1);
+            let response: PDFResponse = client
+                .post(self.base_url.join("pdf").unwrap())
+                .headers(headers.clone())
+                .multipart(multipart_form(
+                    &self.options,
+                    file_name,
+                    chunk.bytes.clone(),
+                )?)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            let pdf_id = match response.pdf_id {
+                Some(pdf_id) => pdf_id,
+                None => {
+                    return Err(PDFError::Processing(
+                        response
+                            .error
+                            .unwrap_or_else(|| "no pdf_id in the response".to_string()),
+                    ))
+                }
+            };
+            parts.push(SplitPart {
+                job: PdfJob::new(pdf_id, header.clone()).with_base_url(self.base_url.clone()),
+                pages: chunk.pages.clone(),
+            });
+        }
+        Ok(SplitPdfJob { parts })
+    } //}}}
+} // }}}
+
+// SplitPdfJob {{{
+#[derive(Debug, Clone)]
+struct SplitPart {
+    job: PdfJob,
+    /// Pages of the original PDF that the job processes
+    pages: Vec<u32>,
+}
+
+/**
+Handle to a PDF that is processed in chunks. It provides the same interface as [PdfJob] and merges
+the results of the chunks as if the PDF was processed as a whole.
+*/
+#[derive(Debug, Clone)]
+pub struct SplitPdfJob {
+    parts: Vec<SplitPart>,
+}
+
+impl SplitPdfJob {
+    /// Handles of the chunks along with the pages of the original PDF that they process
+    pub fn jobs(&self) -> impl Iterator<Item = (&PdfJob, &[u32])> {
+        self.parts
+            .iter()
+            .map(|part| (&part.job, part.pages.as_slice()))
+    }
+
+    /// Tracking IDs of the chunks on the server
+    pub fn pdf_ids(&self) -> Vec<&str> {
+        self.parts.iter().map(|part| part.job.pdf_id()).collect()
+    }
+
+    /**
+    Request the processing status of all of the chunks. The status is `completed` only when all of
+    the chunks are completed and `error` when any of the chunks failed. The page counts are summed.
+    */
+    pub async fn status(&self) -> Result<PDFStatusResponse, PDFError> {
+        //{{{
+        let statuses = try_join_all(self.parts.iter().map(|part| part.job.status())).await?;
+        let status = statuses
+            .iter()
+            .map(|status| status.status)
+            .find(|&status| status == PDFProcessingStatus::Error)
+            .or_else(|| {
+                statuses
+                    .iter()
+                    .map(|status| status.status)
+                    .find(|&status| status != PDFProcessingStatus::Completed)
+            })
+            .unwrap_or(PDFProcessingStatus::Completed);
+        let num_pages: u32 = self.parts.iter().map(|part| part.pages.len() as u32).sum();
+        let num_pages_completed: u32 = statuses
+            .iter()
+            .zip(&self.parts)
+            .map(|(status, part)| match status.status {
+                PDFProcessingStatus::Completed => part.pages.len() as u32,
+                _ => status.num_pages_completed.unwrap_or(0),
+            })
+            .sum();
+        let errors: Vec<String> = statuses
+            .iter()
+            .zip(&self.parts)
+            .filter_map(|(status, part)| {
+                status
+                    .error
+                    .as_ref()
+                    .map(|error| format!("{}: {}", part.job.pdf_id(), error))
+            })
+            .collect();
+        Ok(PDFStatusResponse {
+            status,
+            num_pages: Some(num_pages),
+            num_pages_completed: Some(num_pages_completed),
+            percent_done: Some(num_pages_completed as f32 / num_pages as f32 * 100.),
+            error: if errors.is_empty() {
+                None
+            } else {
+                Some(errors.join("; "))
+            },
+        })
+    } //}}}
+
+    /// Poll the status of the chunks until all of them are processed, see [PdfJob::wait]
+    pub async fn wait<F>(
+        &self,
+        poll: &PollOptions,
+        on_progress: F,
+    ) -> Result<PDFStatusResponse, PDFError>
+    where
+        F: FnMut(&PDFStatusResponse),
+    {
+        wait_for(poll, || self.status(), on_progress).await
+    }
+
+    /**
+    Download the results of the chunks in the given format and merge them. Only the `mmd`, `md`
+    and `lines.json` results can be merged, the other formats fail with `PDFError::Split` and have
+    to be downloaded from the individual [SplitPdfJob::jobs]. The pages of `lines.json` are
+    renumbered to the pages of the original PDF, a page that is not in its chunk fails as well.
+    */
+    pub async fn download(&self, format: PDFOutputFormat) -> Result<Vec<u8>, PDFError> {
+        //{{{
+        match format {
+            PDFOutputFormat::Mmd | PDFOutputFormat::Md => {
+                let results =
+                    try_join_all(self.parts.iter().map(|part| part.job.download(format))).await?;
+                Ok(results.join(&b"\n\n"[..]))
+            }
+            PDFOutputFormat::LinesJson => {
+                let results =
+                    try_join_all(self.parts.iter().map(|part| part.job.download(format))).await?;
+                let mut pages = Vec::new();
+                for (result, part) in results.iter().zip(&self.parts) {
+                    let mut lines: serde_json::Value = serde_json::from_slice(result)?;
+                    if let Some(serde_json::Value::Array(chunk_pages)) =
+                        lines.get_mut("pages").map(serde_json::Value::take)
+                    {
+                        for mut page in chunk_pages {
+                            let number = page.get("page").and_then(serde_json::Value::as_u64);
+                            // NOTE: A chunk-local number could collide with a page of another chunk
+                            let original = number
+                                .and_then(|page| (page as usize).checked_sub(1))
+                                .and_then(|index| part.pages.get(index))
+                                .ok_or_else(|| {
+                                    PDFError::Split(format!(
+                                        "page {:?} of a chunk is not one of its {} pages",
+                                        number,
+                                        part.pages.len()
+                                    ))
+                                })?;
+                            page["page"] = (*original).into();
+                            pages.push(page);
+                        }
+                    }
+                }
+                Ok(serde_json::to_vec(&serde_json::json!({ "pages": pages }))?)
+            }
+            _ => Err(PDFError::Split(format!(
+                "results in the `{}` format can not be merged",
+                format
+            ))),
+        }
+    } //}}}
+
+    /// Download the merged result in the given format and write it to `path`
+    pub async fn download_to_file<P: AsRef<Path>>(
+        &self,
+        format: PDFOutputFormat,
+        path: P,
+    ) -> Result<(), PDFError> {
+        let bytes = self.download(format).await?;
+        tokio::fs::write(path, bytes).await?;
+        Ok(())
+    }
+
+    /// Download the merged line by line data with the page numbers of the original PDF
+    pub async fn lines(&self) -> Result<PDFLines, PDFError> {
+        let bytes = self.download(PDFOutputFormat::LinesJson).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod pdf_split_tests {
+    use super::super::super::test_server::{Reply, TestServer};
+    use super::{
+        PDFError, PDFOptions, PDFOutputFormat, PDFPath, PollOptions, SplitOptions, SplitPDF,
+    };
+    use crate::header::AuthHeader;
+    use lopdf::Document;
+    use serde_json::json;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn test_pdf() -> PDFPath {
+        PDFPath::new(PathBuf::from("./test/assets/test_pdf.pdf")).unwrap()
+    }
+
+    fn page_count(bytes: &[u8]) -> usize {
+        Document::load_mem(bytes).unwrap().get_pages().len()
+    }
+
+    #[test]
+    fn split_by_pages() {
+        //{{{
+        let mut split = SplitOptions::default();
+        split.max_pages(2);
+        let chunks = test_pdf().split(&split, &PDFOptions::default()).unwrap();
+        let pages: Vec<Vec<u32>> = chunks.iter().map(|chunk| chunk.pages.clone()).collect();
+        assert_eq!(pages, vec![vec![1, 2], vec![3]]);
+        assert_eq!(page_count(&chunks[0].bytes), 2);
+        assert_eq!(page_count(&chunks[1].bytes), 1);
+
+        let mut options = PDFOptions::default();
+        options.page_ranges_from_string("2-").unwrap();
+        let chunks = test_pdf()
+            .split(&SplitOptions::default(), &options)
+            .unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].pages, vec![2, 3]);
+        assert_eq!(page_count(&chunks[0].bytes), 2);
+    } //}}}
+
+    #[test]
+    fn split_by_size() {
+        //{{{
+        let path = test_pdf();
+        let whole = std::fs::metadata(&path.pdf_path).unwrap().len();
+        let mut split = SplitOptions::default();
+        split.max_bytes(whole * 10);
+        let chunks = path.split(&split, &PDFOptions::default()).unwrap();
+        assert_eq!(chunks.len(), 1);
+
+        let single = path
+            .split(SplitOptions::default().max_pages(1), &PDFOptions::default())
+            .unwrap();
+        let max_bytes = single.iter().map(|chunk| chunk.bytes.len()).max().unwrap() as u64;
+        let chunks = path
+            .split(
+                SplitOptions::default().max_bytes(max_bytes),
+                &PDFOptions::default(),
+            )
+            .unwrap();
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.bytes.len() as u64 <= max_bytes));
+        assert_eq!(
+            chunks
+                .iter()
+                .flat_map(|chunk| chunk.pages.clone())
+                .collect::<Vec<_>>(),
+            vec![1, 2, 3]
+        );
+
+        assert!(matches!(
+            path.split(
+                SplitOptions::default().max_bytes(10),
+                &PDFOptions::default()
+            ),
+            Err(PDFError::Split(_))
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn split_job_merges_results() {
+        //{{{
+        let uploads = Arc::new(AtomicU32::new(0));
+        let server_uploads = uploads.clone();
+        let server = TestServer::spawn(move |request| {
+            if request.method == "POST" {
+                assert!(request
+                    .header("content-type")
+                    .unwrap()
+                    .starts_with("multipart/form-data"));
+                let chunk = server_uploads.fetch_add(1, Ordering::SeqCst) + 1;
+                return Reply::json(json!({ "pdf_id": format!("chunk{}", chunk) }));
+            }
+            match request.path.as_str() {
+                "/pdf/chunk1" | "/pdf/chunk2" => Reply::json(json!({"status": "completed"})),
+                "/pdf/chunk1.mmd" => Reply::bytes("Page 1\n\nPage 2"),
+                "/pdf/chunk2.mmd" => Reply::bytes("Page 3"),
+                "/pdf/chunk1.lines.json" => Reply::json(json!({"pages": [
+                    {"page": 1, "page_width": 10, "page_height": 10, "lines": []},
+                    {"page": 2, "page_width": 10, "page_height": 10, "lines": []},
+                ]})),
+                "/pdf/chunk2.lines.json" => Reply::json(json!({"pages": [
+                    {"page": 1, "page_width": 10, "page_height": 10, "lines": [
+                        {"type": "text", "cnt": [[0, 0]], "text": "Page 3"}
+                    ]},
+                ]})),
+                _ => Reply::status(404),
+            }
+        })
+        .await;
+
+        let pdf = SplitPDF::new(
+            &test_pdf(),
+            PDFOptions::default(),
+            SplitOptions::default().max_pages(2),
+        )
+        .unwrap()
+        .with_base_url(server.url.clone());
+        let job = pdf.start_job(AuthHeader::new("id", "key")).await.unwrap();
+        assert_eq!(job.pdf_ids(), vec!["chunk1", "chunk2"]);
+
+        let status = job.wait(&PollOptions::default(), |_| {}).await.unwrap();
+        assert_eq!(status.num_pages, Some(3));
+        assert_eq!(status.num_pages_completed, Some(3));
+
+        assert_eq!(
+            job.download(PDFOutputFormat::Mmd).await.unwrap(),
+            b"Page 1\n\nPage 2\n\nPage 3"
+        );
+        let lines = job.lines().await.unwrap();
+        let pages: Vec<u32> = lines.pages().map(|page| page.page).collect();
+        assert_eq!(pages, vec![1, 2, 3]);
+        assert_eq!(
+            lines.page(3).unwrap().lines[0].text.as_deref(),
+            Some("Page 3")
+        );
+        assert!(matches!(
+            job.download(PDFOutputFormat::Docx).await,
+            Err(PDFError::Split(_))
+        ));
+    } //}}}
+
+    #[tokio::test]
+    async fn merge_unknown_pages() {
+        //{{{
+        let server = TestServer::spawn(|request| match request.method.as_str() {
+            "POST" => Reply::json(json!({ "pdf_id": "chunk" })),
+            _ => Reply::json(json!({"pages": [
+                {"page": 0, "lines": []},
+                {"page": 2, "lines": []},
+                {"page": 9, "lines": []},
+            ]})),
+        })
+        .await;
+        let pdf = SplitPDF::new(&test_pdf(), PDFOptions::default(), &SplitOptions::default())
+            .unwrap()
+            .with_base_url(server.url.clone());
+        let job = pdf.start_job(AuthHeader::new("id", "key")).await.unwrap();
+        // NOTE: The pages that are not in the chunk can not be mapped to the original pages
+        assert!(matches!(
+            job.download(PDFOutputFormat::LinesJson).await,
+            Err(PDFError::Split(message)) if message.contains("page Some(0)")
+        ));
+    } //}}}
+}
+// }}}
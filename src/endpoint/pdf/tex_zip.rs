@@ -0,0 +1,475 @@
+use super::error::TexZipError;
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use zip::ZipArchive;
+
+/// Extensions of the images in the order in which `pdflatex` looks for them
+const IMAGE_EXTENSIONS: &[&str] = &[
+    "pdf", "png", "jpg", "jpeg", "eps", "gif", "svg", "bmp", "tif", "tiff",
+];
+
+/// Mode bits of a symbolic link in the unix mode of a zip entry
+const SYMLINK_MODE: u32 = 0o120000;
+
+// ExtractOptions {{{
+/// Options for extracting the `tex.zip` result of a PDF into a directory
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ExtractOptions {
+    /// Overwrite files that already exist in the target directory
+    pub force: bool,
+    /**
+    Rename the images to `image-001.EXT`, `image-002.EXT`, ... in the order in which they are
+    referenced in the main `.tex` file and update the references in the `.tex` files accordingly
+    */
+    pub rename_images: bool,
+}
+
+impl ExtractOptions {
+    pub fn force(&mut self, val: bool) -> &mut Self {
+        self.force = val;
+        self
+    }
+
+    pub fn rename_images(&mut self, val: bool) -> &mut Self {
+        self.rename_images = val;
+        self
+    }
+} // }}}
+
+// TexProject {{{
+/// The LaTeX project extracted from a `tex.zip` result. The paths are relative to `dir`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TexProject {
+    /// Directory that the project was extracted into
+    pub dir: PathBuf,
+    /// The `.tex` file with the `\documentclass` that should be compiled
+    pub main_tex: PathBuf,
+    /// All of the `.tex` files including the `main_tex`
+    pub tex_files: Vec<PathBuf>,
+    /// Image assets of the project
+    pub images: Vec<PathBuf>,
+    /// All of the extracted files
+    pub files: Vec<PathBuf>,
+}
+
+impl TexProject {
+    /// Path of the main `.tex` file including the `dir`
+    pub fn main_tex_path(&self) -> PathBuf {
+        self.dir.join(&self.main_tex)
+    }
+} // }}}
+
+/**
+Extract the `tex.zip` archive `bytes` into `dir`. Nothing is written when an entry would end up
+outside of `dir` (`TexZipError::UnsafePath`) or when a file already exists and `force` is not set
+(`TexZipError::FileExists`).
+*/
+pub fn extract_tex_zip<P: AsRef<Path>>(
+    bytes: &[u8],
+    dir: P,
+    options: &ExtractOptions,
+) -> Result<TexProject, TexZipError> {
+    //{{{
+    let dir = dir.as_ref();
+    let mut entries = read_entries(bytes)?;
+    entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+    let tex_files: Vec<PathBuf> = entries
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|path| has_extension(path, &["tex"]))
+        .cloned()
+        .collect();
+    let main_tex = entries
+        .iter()
+        .find(|(path, content)| {
+            has_extension(path, &["tex"])
+                && String::from_utf8_lossy(content).contains("\\documentclass")
+        })
+        .map(|(path, _)| path.clone())
+        .or_else(|| tex_files.first().cloned())
+        .ok_or(TexZipError::NoMainTex)?;
+
+    if options.rename_images {
+        rename_images(&mut entries, &main_tex);
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    if !options.force {
+        if let Some((path, _)) = entries.iter().find(|(path, _)| dir.join(path).exists()) {
+            return Err(TexZipError::FileExists(dir.join(path)));
+        }
+    }
+    for (path, content) in &entries {
+        let target = dir.join(path);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(target, content)?;
+    }
+
+    let files: Vec<PathBuf> = entries.into_iter().map(|(path, _)| path).collect();
+    Ok(TexProject {
+        dir: dir.to_path_buf(),
+        main_tex,
+        tex_files,
+        images: files
+            .iter()
+            .filter(|path| has_extension(path, IMAGE_EXTENSIONS))
+            .cloned()
+            .collect(),
+        files,
+    })
+} //}}}
+
+/// Read the files of the archive and check that they stay inside of the target directory
+fn read_entries(bytes: &[u8]) -> Result<Vec<(PathBuf, Vec<u8>)>, TexZipError> {
+    let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let mut file = archive.by_index(index)?;
+        if file.is_dir() {
+            continue;
+        }
+        let is_symlink = matches!(file.unix_mode(), Some(mode) if mode & 0o170000 == SYMLINK_MODE);
+        let path = match file.enclosed_name() {
+            Some(path) if !is_symlink => path.to_path_buf(),
+            _ => return Err(TexZipError::UnsafePath(file.name().to_string())),
+        };
+        // NOTE: The size is declared by the archive, so it is not trusted for a preallocation
+        let mut content = Vec::new();
+        file.read_to_end(&mut content)?;
+        entries.push((path, content));
+    }
+    Ok(entries)
+}
+
+/**
+Rename the images in the order of their first `\includegraphics` in `main_tex` (unreferenced
+images last) and replace the `\includegraphics` arguments in the `.tex` files. The arguments are
+relative to the directory of `main_tex` and can be with or without the extension. An argument
+without the extension refers to the image with the first extension in [IMAGE_EXTENSIONS] as in
+`pdflatex`.
+*/
+fn rename_images(entries: &mut [(PathBuf, Vec<u8>)], main_tex: &Path) {
+    //{{{
+    let main_dir = main_tex.parent().unwrap_or_else(|| Path::new(""));
+    let images: Vec<PathBuf> = entries
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|path| has_extension(path, IMAGE_EXTENSIONS))
+        .cloned()
+        .collect();
+    if images.is_empty() {
+        return;
+    }
+    let references = References::new(&images, main_dir);
+
+    let main_content = entries
+        .iter()
+        .find(|(path, _)| path == main_tex)
+        .map(|(_, content)| String::from_utf8_lossy(content).into_owned())
+        .unwrap_or_default();
+    let mut ordered: Vec<&PathBuf> = Vec::with_capacity(images.len());
+    for captures in graphics_pattern().captures_iter(&main_content) {
+        if let Some((image, _)) = references.resolve(&captures[1]) {
+            if !ordered.contains(&image) {
+                ordered.push(image);
+            }
+        }
+    }
+    ordered.extend(
+        images
+            .iter()
+            .filter(|image| !ordered.contains(image))
+            .collect::<Vec<_>>(),
+    );
+
+    // NOTE: The new names must not replace the other files of the project
+    let taken: HashSet<&PathBuf> = entries
+        .iter()
+        .map(|(path, _)| path)
+        .filter(|path| !has_extension(path, IMAGE_EXTENSIONS))
+        .collect();
+    let mut renamed: HashMap<PathBuf, PathBuf> = HashMap::new();
+    let mut number = 0;
+    for image in ordered {
+        let extension = image
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let new_path = loop {
+            number += 1;
+            let new_path = image.with_file_name(format!("image-{:03}.{}", number, extension));
+            if !taken.contains(&new_path) {
+                break new_path;
+            }
+        };
+        renamed.insert(image.clone(), new_path);
+    }
+
+    for (path, content) in entries.iter_mut() {
+        if let Some(new_path) = renamed.get(path) {
+            *path = new_path.clone();
+        } else if has_extension(path, &["tex"]) {
+            let text = String::from_utf8_lossy(content);
+            let replaced = graphics_pattern().replace_all(&text, |captures: &regex::Captures| {
+                let argument = captures.get(1).unwrap();
+                match references.resolve(argument.as_str()) {
+                    Some((image, with_extension)) => {
+                        let whole = captures.get(0).unwrap();
+                        let new_reference = reference(&renamed[image], main_dir, with_extension);
+                        format!(
+                            "{}{}{}",
+                            &whole.as_str()[..argument.start() - whole.start()],
+                            new_reference,
+                            &whole.as_str()[argument.end() - whole.start()..]
+                        )
+                    }
+                    None => captures[0].to_string(),
+                }
+            });
+            *content = replaced.into_owned().into_bytes();
+        }
+    }
+} //}}}
+
+/// `\includegraphics[options]{argument}` with the argument in the first group
+fn graphics_pattern() -> &'static Regex {
+    static PATTERN: OnceLock<Regex> = OnceLock::new();
+    PATTERN.get_or_init(|| {
+        Regex::new(r"\\includegraphics\*?\s*(?:\[[^\]]*\]\s*)?\{\s*([^}]*?)\s*\}").unwrap()
+    })
+}
+
+/// The images of a project by the arguments of `\includegraphics` that refer to them
+struct References<'a> {
+    with_extension: HashMap<String, &'a PathBuf>,
+    without_extension: HashMap<String, Vec<&'a PathBuf>>,
+}
+
+impl<'a> References<'a> {
+    fn new(images: &'a [PathBuf], dir: &Path) -> Self {
+        let mut references = References {
+            with_extension: HashMap::new(),
+            without_extension: HashMap::new(),
+        };
+        for image in images {
+            references
+                .with_extension
+                .insert(reference(image, dir, true), image);
+            references
+                .without_extension
+                .entry(reference(image, dir, false))
+                .or_default()
+                .push(image);
+        }
+        references
+    }
+
+    /// The image that `argument` refers to and whether the argument includes the extension
+    fn resolve(&self, argument: &str) -> Option<(&'a PathBuf, bool)> {
+        if let Some(&image) = self.with_extension.get(argument) {
+            return Some((image, true));
+        }
+        let precedence = |image: &&PathBuf| {
+            let extension = image
+                .extension()
+                .map(|extension| extension.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            IMAGE_EXTENSIONS
+                .iter()
+                .position(|&known| known == extension)
+                .unwrap_or(usize::MAX)
+        };
+        self.without_extension
+            .get(argument)?
+            .iter()
+            .copied()
+            .min_by_key(precedence)
+            .map(|image| (image, false))
+    }
+}
+
+/// How the `path` is referenced from a `.tex` file in `dir` (always with `/` separators)
+fn reference(path: &Path, dir: &Path, with_extension: bool) -> String {
+    let relative = path.strip_prefix(dir).unwrap_or(path);
+    let relative = if with_extension {
+        relative.to_path_buf()
+    } else {
+        relative.with_extension("")
+    };
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn has_extension(path: &Path, extensions: &[&str]) -> bool {
+    path.extension()
+        .map(|extension| extensions.contains(&extension.to_string_lossy().to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod tex_zip_tests {
+    use super::{extract_tex_zip, ExtractOptions, TexZipError};
+    use std::io::{Cursor, Write};
+    use std::path::PathBuf;
+    use zip::{write::FileOptions, ZipWriter};
+
+    const MAIN_TEX: &str = "\\documentclass{article}\n\\begin{document}\n\
+        \\includegraphics{images/zz_second}\n\\includegraphics{images/aa_first.JPG}\n\
+        \\end{document}\n";
+
+    fn archive(files: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        for (name, content) in files {
+            writer.start_file(*name, FileOptions::default()).unwrap();
+            writer.write_all(content).unwrap();
+        }
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn tex_zip() -> Vec<u8> {
+        archive(&[
+            ("abc/abc.tex", MAIN_TEX.as_bytes()),
+            ("abc/chapter.tex", b"\\section{A}"),
+            ("abc/images/aa_first.JPG", b"jpg"),
+            ("abc/images/zz_second.png", b"png"),
+        ])
+    }
+
+    #[test]
+    fn extract_project() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        let project = extract_tex_zip(&tex_zip(), dir.path(), &ExtractOptions::default()).unwrap();
+        assert_eq!(project.main_tex, PathBuf::from("abc/abc.tex"));
+        assert_eq!(project.tex_files.len(), 2);
+        assert_eq!(
+            project.images,
+            vec![
+                PathBuf::from("abc/images/aa_first.JPG"),
+                PathBuf::from("abc/images/zz_second.png")
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(project.main_tex_path()).unwrap(),
+            MAIN_TEX
+        );
+
+        assert!(matches!(
+            extract_tex_zip(&tex_zip(), dir.path(), &ExtractOptions::default()),
+            Err(TexZipError::FileExists(_))
+        ));
+        assert!(extract_tex_zip(
+            &tex_zip(),
+            dir.path(),
+            ExtractOptions::default().force(true)
+        )
+        .is_ok());
+    } //}}}
+
+    #[test]
+    fn rename_images() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        let project = extract_tex_zip(
+            &tex_zip(),
+            dir.path(),
+            ExtractOptions::default().rename_images(true),
+        )
+        .unwrap();
+        assert_eq!(
+            project.images,
+            vec![
+                PathBuf::from("abc/images/image-001.png"),
+                PathBuf::from("abc/images/image-002.jpg")
+            ]
+        );
+        let main_tex = std::fs::read_to_string(project.main_tex_path()).unwrap();
+        assert!(main_tex.contains("\\includegraphics{images/image-001}"));
+        assert!(main_tex.contains("\\includegraphics{images/image-002.jpg}"));
+        assert_eq!(
+            std::fs::read(dir.path().join("abc/images/image-002.jpg")).unwrap(),
+            b"jpg"
+        );
+        assert!(!dir.path().join("abc/images/aa_first.JPG").exists());
+    } //}}}
+
+    #[test]
+    fn rename_ambiguous_images() {
+        //{{{
+        let main_tex = "\\documentclass{article}\n\\begin{document}\n\
+            \\includegraphics[width=2cm]{images/fig10}\n\\includegraphics{images/fig1}\n\
+            \\includegraphics*{images/fig1.png}\nSee images/fig1 and images/image-001.tex.\n\
+            \\end{document}\n";
+        let bytes = archive(&[
+            ("abc/abc.tex", main_tex.as_bytes()),
+            ("abc/images/fig1.png", b"fig1 png"),
+            ("abc/images/fig1.pdf", b"fig1 pdf"),
+            ("abc/images/fig10.png", b"fig10 png"),
+            ("abc/images/image-002.pdf", b"old image"),
+            ("abc/images/image-001.pdf.tex", b"not an image"),
+            ("abc/images/image-003.png.txt", b"notes"),
+        ]);
+        let dir = tempfile::tempdir().unwrap();
+        let project = extract_tex_zip(
+            &bytes,
+            dir.path(),
+            ExtractOptions::default().rename_images(true),
+        )
+        .unwrap();
+        assert_eq!(
+            project.images,
+            vec![
+                PathBuf::from("abc/images/image-001.png"),
+                PathBuf::from("abc/images/image-002.pdf"),
+                PathBuf::from("abc/images/image-003.png"),
+                PathBuf::from("abc/images/image-004.pdf"),
+            ]
+        );
+        let read = |path: &str| std::fs::read(dir.path().join("abc/images").join(path)).unwrap();
+        assert_eq!(read("image-001.png"), b"fig10 png");
+        // NOTE: `fig1` without the extension is the PDF as in pdflatex
+        assert_eq!(read("image-002.pdf"), b"fig1 pdf");
+        assert_eq!(read("image-003.png"), b"fig1 png");
+        assert_eq!(read("image-004.pdf"), b"old image");
+        assert_eq!(read("image-001.pdf.tex"), b"not an image");
+
+        let main = std::fs::read_to_string(project.main_tex_path()).unwrap();
+        assert!(main.contains("\\includegraphics[width=2cm]{images/image-001}"));
+        assert!(main.contains("\\includegraphics{images/image-002}\n"));
+        assert!(main.contains("\\includegraphics*{images/image-003.png}"));
+        // NOTE: Only the arguments of `\includegraphics` are replaced
+        assert!(main.contains("See images/fig1 and images/image-001.tex."));
+    } //}}}
+
+    #[test]
+    fn reject_unsafe_archives() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("project");
+        for name in &["../evil.tex", "/etc/evil.tex", "abc/../../evil.tex"] {
+            let bytes = archive(&[("abc/abc.tex", MAIN_TEX.as_bytes()), (name, b"evil")]);
+            assert!(matches!(
+                extract_tex_zip(&bytes, &target, &ExtractOptions::default()),
+                Err(TexZipError::UnsafePath(_))
+            ));
+        }
+        assert!(!target.exists());
+
+        let bytes = archive(&[("abc/image.png", b"png")]);
+        assert!(matches!(
+            extract_tex_zip(&bytes, &target, &ExtractOptions::default()),
+            Err(TexZipError::NoMainTex)
+        ));
+    } //}}}
+}
+// }}}
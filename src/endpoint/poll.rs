@@ -0,0 +1,167 @@
+use std::future::Future;
+use std::time::{Duration, Instant};
+
+// PollOptions {{{
+/**
+Configuration of the polling for the status of a job on the server. The first status request is
+sent right away, the interval until the next one starts at `initial_interval` and is multiplied by
+`multiplier` after every request until it reaches `max_interval`.
+*/
+#[derive(Debug, Clone, PartialEq)]
+pub struct PollOptions {
+    /// Interval between the first and the second status request
+    pub initial_interval: Duration,
+    /// Upper bound of the interval between two status requests
+    pub max_interval: Duration,
+    /**
+    Factor by which the interval grows after each status request. A factor that does not grow the
+    interval (below 1, negative or NaN) or that overflows it polls every `max_interval`.
+    */
+    pub multiplier: f32,
+    /// Give up waiting after this duration. `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+}
+
+impl Default for PollOptions {
+    fn default() -> Self {
+        PollOptions {
+            initial_interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(30),
+            multiplier: 1.5,
+            timeout: Some(Duration::from_secs(30 * 60)),
+        }
+    }
+}
+
+impl PollOptions {
+    /// The interval that follows after `interval`
+    pub(crate) fn next_interval(&self, interval: Duration) -> Duration {
+        Duration::try_from_secs_f64(interval.as_secs_f64() * f64::from(self.multiplier))
+            .ok()
+            .filter(|next| *next >= interval)
+            .map_or(self.max_interval, |next| next.min(self.max_interval))
+    }
+} // }}}
+
+/// Options that poll quickly so the tests of the jobs do not wait
+#[cfg(test)]
+pub(crate) fn fast_poll() -> PollOptions {
+    PollOptions {
+        initial_interval: Duration::from_millis(5),
+        max_interval: Duration::from_millis(20),
+        multiplier: 2.0,
+        timeout: Some(Duration::from_secs(5)),
+    }
+}
+
+/// State of a job on the server as it is reported by its status
+pub(crate) enum JobState {
+    /// The job is still being processed
+    Pending,
+    /// The job is processed and its results are available
+    Completed,
+    /// The processing failed with the message of the server
+    Failed(String),
+}
+
+/// Status response of a job that can be polled with [wait_for]
+pub(crate) trait JobStatus {
+    fn state(&self) -> JobState;
+}
+
+/// Error of a job that can be polled with [wait_for]
+pub(crate) trait JobError {
+    /// The server reports that the processing failed
+    fn failed(message: String) -> Self;
+    /// The job was not processed before the timeout
+    fn timeout(elapsed: Duration) -> Self;
+}
+
+/**
+Poll `status` until the job is processed. `on_progress` is called with every status that is
+received. It is shared by all of the jobs (PDFs, batches, conversions) so that they back off and
+time out in the same way.
+*/
+pub(crate) async fn wait_for<T, E, S, Fut, F>(
+    poll: &PollOptions,
+    mut status: S,
+    mut on_progress: F,
+) -> Result<T, E>
+where
+    T: JobStatus,
+    E: JobError,
+    S: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    F: FnMut(&T),
+{
+    //{{{
+    let start = Instant::now();
+    let mut interval = poll.initial_interval;
+    loop {
+        let status = status().await?;
+        on_progress(&status);
+        match status.state() {
+            JobState::Completed => return Ok(status),
+            JobState::Failed(message) => return Err(E::failed(message)),
+            JobState::Pending => {}
+        }
+        let mut sleep = interval;
+        if let Some(timeout) = poll.timeout {
+            let elapsed = start.elapsed();
+            if elapsed >= timeout {
+                return Err(E::timeout(elapsed));
+            }
+            sleep = sleep.min(timeout - elapsed);
+        }
+        tokio::time::sleep(sleep).await;
+        interval = poll.next_interval(interval);
+    }
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+mod poll_tests {
+    use super::{fast_poll, PollOptions};
+    use std::time::Duration;
+
+    #[test]
+    fn poll_backoff() {
+        //{{{
+        let poll = fast_poll();
+        let intervals: Vec<Duration> = (0..4)
+            .scan(poll.initial_interval, |interval, _| {
+                let current = *interval;
+                *interval = poll.next_interval(*interval);
+                Some(current)
+            })
+            .collect();
+        assert_eq!(
+            intervals,
+            [5, 10, 20, 20].map(Duration::from_millis).to_vec()
+        );
+    } //}}}
+
+    #[test]
+    fn poll_bad_multipliers() {
+        //{{{
+        let interval = Duration::from_millis(10);
+        for multiplier in [-2.0, 0.0, 0.5, f32::NAN, f32::INFINITY, f32::MAX] {
+            let poll = PollOptions {
+                multiplier,
+                ..fast_poll()
+            };
+            assert_eq!(poll.next_interval(interval), poll.max_interval);
+        }
+        let poll = PollOptions {
+            multiplier: 1.0,
+            ..fast_poll()
+        };
+        assert_eq!(poll.next_interval(interval), interval);
+        let poll = PollOptions {
+            max_interval: Duration::MAX,
+            ..fast_poll()
+        };
+        assert_eq!(poll.next_interval(Duration::MAX), Duration::MAX);
+    } //}}}
+}
+// }}}
@@ -1,17 +1,34 @@
-use base64::encode;
+use super::transcode::{
+    accepted_mime, format_from_extension, sniff_format, tiff_frames_to_png, to_png, SNIFF_LEN,
+    SUPPORTED_FORMATS,
+};
+use base64::write::EncoderStringWriter;
+use base64::{decode, encode};
+use image::ImageFormat;
 use mime::{Mime, IMAGE_JPEG, IMAGE_PNG};
 use serde::{Serialize, Serializer};
 use std::convert::TryFrom;
-use std::path::PathBuf;
+use std::fmt;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
 use thiserror::Error;
 
-const JPEG_EXTENSIONS: &[&str] = &["jpg", "jpeg", "jpe", "jif", "jfif", "jfi"];
-const PNG_EXTENSIONS: &[&str] = &["png"];
+/**
+Image file that is sent as a base64 encoded data URL. The file is read and encoded only once, on
+the first call to [Base64Image::encode] (or when it is serialized), and the encoding is cached.
 
-#[derive(Debug, PartialEq)]
+The type of the image is detected from the content of the file and from its extension when the
+file can not be read yet. JPEG and PNG images are sent as they are. GIF, BMP, WebP, TIFF and PNM
+images are transcoded to PNG, which is their MIME type.
+*/
+#[derive(Debug)]
 pub struct Base64Image {
     img_path: PathBuf,
     img_mime: Mime,
+    /// Format of the file when it has to be transcoded to PNG
+    source_format: Option<ImageFormat>,
+    encoded: OnceLock<String>,
 }
 
 #[derive(Error, Debug)]
@@ -20,48 +37,242 @@ pub enum Base64ImageError {
     InvalidExtension(String),
     #[error("UnsupportedFileType: {0}")]
     UnsupportedFileType(String),
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("InvalidDataUrl: {0}")]
+    InvalidDataUrl(String),
+    #[error("UnsupportedUrl: {0}")]
+    UnsupportedUrl(String),
+    #[error("TranscodeError: {0}")]
+    Transcode(String),
+}
+
+impl Base64Image {
+    /**
+    Image from the `data` in memory (e.g. a rendered image). The `name` is used as the path of the
+    image (e.g. for `$image` in the callback headers) but it is never read.
+    */
+    pub fn from_bytes<P: Into<PathBuf>>(name: P, mime: Mime, data: &[u8]) -> Self {
+        let encoded = OnceLock::new();
+        let _ = encoded.set(format!("data:{};base64,{}", mime, encode(data)));
+        Base64Image {
+            img_path: name.into(),
+            img_mime: mime,
+            source_format: None,
+            encoded,
+        }
+    }
+
+    /**
+    Image from the `data` in memory whose type is detected from its content. It is named
+    `image.jpg` or `image.png` by the type it is sent as. Images that are not JPEG or PNG are
+    transcoded right away.
+    */
+    pub fn from_data(data: &[u8]) -> Result<Self, Base64ImageError> {
+        //{{{
+        let format = sniff_format(data).ok_or_else(|| {
+            Base64ImageError::UnsupportedFileType(format!(
+                "The data is not an image of a supported type. {} images are supported.",
+                SUPPORTED_FORMATS
+            ))
+        })?;
+        Ok(match format {
+            ImageFormat::Jpeg => Base64Image::from_bytes("image.jpg", IMAGE_JPEG, data),
+            ImageFormat::Png => Base64Image::from_bytes("image.png", IMAGE_PNG, data),
+            _ => Base64Image::from_bytes("image.png", IMAGE_PNG, &to_png(data, format)?),
+        })
+    } //}}}
+
+    /**
+    All of the frames of a multi-frame TIFF image at `path` as separate PNG images named
+    `{stem}-{n}.png`. Any other image is returned as the only frame.
+
+    ```no_run
+    # fn run() -> Result<(), Box<dyn std::error::Error>> {
+    use mathpixapi::endpoint::text::Base64Image;
+
+    for page in Base64Image::frames("scan.tiff")? {
+        println!("{}", page.path().display());
+    }
+    # Ok(())
+    # }
+    ```
+    */
+    pub fn frames<P: Into<PathBuf>>(path: P) -> Result<Vec<Self>, Base64ImageError> {
+        //{{{
+        let image = Base64Image::try_from(path.into())?;
+        if image.source_format != Some(ImageFormat::Tiff) {
+            return Ok(vec![image]);
+        }
+        let data = std::fs::read(&image.img_path)?;
+        let stem = image
+            .img_path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(tiff_frames_to_png(&data)?
+            .into_iter()
+            .enumerate()
+            .map(|(n, png)| {
+                let name = image
+                    .img_path
+                    .with_file_name(format!("{}-{}.png", stem, n + 1));
+                Base64Image::from_bytes(name, IMAGE_PNG, &png)
+            })
+            .collect())
+    } //}}}
+
+    /// Path of the image file
+    pub fn path(&self) -> &Path {
+        &self.img_path
+    }
+
+    /// MIME type of the image
+    pub fn mime(&self) -> &Mime {
+        &self.img_mime
+    }
+
+    /**
+    Image file at `path` that is read and encoded right away, so that a missing or broken file is
+    reported here and not when the request is sent.
+    */
+    pub fn load<P: Into<PathBuf>>(path: P) -> Result<Self, Base64ImageError> {
+        let image = Base64Image::try_from(path.into())?;
+        image.encode()?;
+        Ok(image)
+    }
+
+    /**
+    The data URL of the image (`data:MIME;base64,DATA`). The file is read (and transcoded) on the
+    first call and the result is cached for the subsequent calls.
+
+    JPEG and PNG files are encoded as they are read, so only the encoding is held in memory and
+    not the content of the file as well.
+    */
+    pub fn encode(&self) -> Result<&str, Base64ImageError> {
+        //{{{
+        if let Some(encoded) = self.encoded.get() {
+            return Ok(encoded);
+        }
+        let encoded = match self.source_format {
+            Some(format) => {
+                let png = to_png(&std::fs::read(&self.img_path)?, format)?;
+                format!("data:{};base64,{}", self.img_mime, encode(png))
+            }
+            None => {
+                let mut file = std::fs::File::open(&self.img_path)?;
+                let prefix = format!("data:{};base64,", self.img_mime);
+                let len = file.metadata()?.len() as usize;
+                let mut encoded = String::with_capacity(prefix.len() + len.div_ceil(3) * 4);
+                encoded.push_str(&prefix);
+                let mut writer = EncoderStringWriter::from(encoded, base64::STANDARD);
+                std::io::copy(&mut file, &mut writer)?;
+                writer.into_inner()
+            }
+        };
+        Ok(self.encoded.get_or_init(|| encoded))
+    } //}}}
+}
+
+/// First bytes of the file at `path` that are needed to detect its format
+fn read_header(path: &Path) -> std::io::Result<Vec<u8>> {
+    let mut header = Vec::with_capacity(SNIFF_LEN);
+    std::fs::File::open(path)?
+        .take(SNIFF_LEN as u64)
+        .read_to_end(&mut header)?;
+    Ok(header)
+}
+
+/**
+Check that the `data:` URL is a base64 encoded image of a supported type, e.g.
+`data:image/png;base64,iVBORw0KGgo...`.
+*/
+pub(crate) fn validate_data_url(url: &str) -> Result<(), Base64ImageError> {
+    //{{{
+    let invalid = |message: &str| Base64ImageError::InvalidDataUrl(message.to_string());
+    let data = url
+        .get(..5)
+        .filter(|scheme| scheme.eq_ignore_ascii_case("data:"))
+        .map(|_| &url[5..])
+        .ok_or_else(|| invalid("the URL does not start with `data:`"))?;
+    let (header, payload) = data
+        .split_once(',')
+        .ok_or_else(|| invalid("there is no `,` before the data"))?;
+    let mut parameters = header.split(';');
+    let mime: Mime = parameters
+        .next()
+        .unwrap_or_default()
+        .parse()
+        .map_err(|_| invalid("the media type is missing or invalid"))?;
+    if mime != IMAGE_JPEG && mime != IMAGE_PNG {
+        return Err(Base64ImageError::UnsupportedFileType(format!(
+            "The data URL has an unsupported media type {}. jpg and png images are supported.",
+            mime
+        )));
+    }
+    if !parameters.any(|parameter| parameter.eq_ignore_ascii_case("base64")) {
+        return Err(invalid("the data is not base64 encoded"));
+    }
+    decode(payload).map_err(|error| invalid(&error.to_string()))?;
+    Ok(())
+} //}}}
+
+// NOTE: The cached encoding is not a part of the identity of the image
+impl PartialEq for Base64Image {
+    fn eq(&self, other: &Self) -> bool {
+        self.img_path == other.img_path
+            && self.img_mime == other.img_mime
+            && self.source_format == other.source_format
+    }
 }
 
 impl TryFrom<PathBuf> for Base64Image {
     //{{{
     type Error = Base64ImageError;
     fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
-        let extension = path.extension().ok_or_else(|| {
-            Self::Error::InvalidExtension(format!("File {:?} has an invalid extension.", path))
-        })?;
-        let img_mime = match extension {
-            // FIX: error handling <22-05-21, kunzaatko> //
-            _ if JPEG_EXTENSIONS.contains(&extension.to_str().unwrap().to_lowercase().as_str()) => {
-                IMAGE_JPEG
-            }
-            _ if PNG_EXTENSIONS.contains(&extension.to_str().unwrap().to_lowercase().as_str()) => {
-                IMAGE_PNG
-            }
-            _ => {
-                return Err(Self::Error::UnsupportedFileType(format!(
-                    "File {:?} has an unsupported filetype. jpg and png images are supported.",
-                    path
-                )))
+        // NOTE: The content decides when the file is readable, so that a wrong extension does not
+        // matter. The extension is only used for files that do not exist (yet).
+        let format = match read_header(&path) {
+            Ok(header) => sniff_format(&header).ok_or_else(|| {
+                Self::Error::UnsupportedFileType(format!(
+                    "File {:?} is not an image of a supported type. {} images are supported.",
+                    path, SUPPORTED_FORMATS
+                ))
+            })?,
+            Err(_) => {
+                let extension = path
+                    .extension()
+                    .and_then(|ext| ext.to_str())
+                    .ok_or_else(|| {
+                        Self::Error::InvalidExtension(format!(
+                            "File {:?} has an invalid extension.",
+                            path
+                        ))
+                    })?;
+                format_from_extension(extension).ok_or_else(|| {
+                    Self::Error::UnsupportedFileType(format!(
+                        "File {:?} has an unsupported filetype. {} images are supported.",
+                        path, SUPPORTED_FORMATS
+                    ))
+                })?
             }
         };
         Ok(Base64Image {
             img_path: path,
-            img_mime,
+            img_mime: accepted_mime(format).unwrap_or(IMAGE_PNG),
+            source_format: accepted_mime(format).map_or(Some(format), |_| None),
+            encoded: OnceLock::new(),
         })
     }
 } //}}}
 
-impl ToString for Base64Image {
-    //{{{
-    fn to_string(&self) -> String {
-        let mut string = "data:".to_string();
-        string.push_str(&self.img_mime.to_string());
-        string.push_str(";base64,");
-        // PERF: Why does this clone need to be here? <22-05-21, kunzaatko> //
-        string.push_str(&encode(&std::fs::read(self.img_path.clone()).unwrap()));
-        return string;
+/// The path and the MIME type of the image (e.g. `scan.jpg (image/jpeg)`), the image is not read.
+/// The data URL is given by [Base64Image::encode].
+impl fmt::Display for Base64Image {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({})", self.img_path.display(), self.img_mime)
     }
-} //}}}
+}
 
 impl Serialize for Base64Image {
     //{{{
@@ -69,15 +280,19 @@ impl Serialize for Base64Image {
     where
         S: Serializer,
     {
-        self.to_string().serialize(serializer)
+        self.encode()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
     }
 } //}}}
 
 // TESTS {{{
 #[cfg(test)]
 mod base64image_tests {
+    use super::super::transcode::transcode_tests::sample;
     use super::Base64ImageError;
     use super::*;
+    use image::ImageOutputFormat;
     use mime::{IMAGE_JPEG, IMAGE_PNG};
     use regex::Regex;
     use serde_json::json;
@@ -96,6 +311,8 @@ mod base64image_tests {
         let acctual = Base64Image {
             img_path: "./test/assets/test_encode_base64.jpg".into(),
             img_mime: IMAGE_JPEG,
+            source_format: None,
+            encoded: OnceLock::new(),
         };
         assert_eq!(base64image, acctual);
 
@@ -107,6 +324,8 @@ mod base64image_tests {
         let acctual = Base64Image {
             img_path: "./test/assets/test_encode_base64.png".into(),
             img_mime: IMAGE_PNG,
+            source_format: None,
+            encoded: OnceLock::new(),
         };
         assert_eq!(base64image, acctual);
 
@@ -118,16 +337,18 @@ mod base64image_tests {
         let acctual = Base64Image {
             img_path: "./test/assets/test_encode_base64.JPG".into(),
             img_mime: IMAGE_JPEG,
+            source_format: None,
+            encoded: OnceLock::new(),
         };
         assert_eq!(base64image, acctual);
 
         // UnsupportedFileType
         let base64image: Result<Base64Image, Base64ImageError> =
             PathBuf::from("./test/assets/test_encode_base64.txt".to_string()).try_into();
-        assert!(match base64image {
-            Err(Base64ImageError::UnsupportedFileType(_)) => true,
-            _ => false,
-        });
+        assert!(matches!(
+            base64image,
+            Err(Base64ImageError::UnsupportedFileType(_))
+        ));
 
         let error_re = Regex::new(r"UnsupportedFileType: .*").unwrap();
         assert!(error_re.is_match(&format!("{}", base64image.unwrap_err())));
@@ -148,9 +369,192 @@ mod base64image_tests {
         let base64image = Base64Image {
             img_path: "./test/assets/test_encode_base64.jpg".into(),
             img_mime: IMAGE_JPEG,
+            source_format: None,
+            encoded: OnceLock::new(),
         };
         let string = "data:image/jpeg;base64,/9j/4AAQSkZJRgABAQAAAQABAAD/2wBDAAMCAgICAgMCAgIDAwMDBAYEBAQEBAgGBgUGCQgKCgkICQkKDA8MCgsOCwkJDRENDg8QEBEQCgwSExIQEw8QEBD/wAALCAACAAIBAREA/8QAFAABAAAAAAAAAAAAAAAAAAAACP/EABwQAAEFAQEBAAAAAAAAAAAAAAIBAwQFBgcIAP/aAAgBAQAAPwBfeevPXAt7wLmm63XD+f6PSaPH01tcXFtmYUydZTpEJp1+TIfdbJx55xwzM3DJSIiUlVVVV+//2Q==".to_string();
-        assert_eq!(base64image.to_string(), string);
+        assert_eq!(base64image.encode().unwrap(), string);
+        assert_eq!(
+            base64image.to_string(),
+            "./test/assets/test_encode_base64.jpg (image/jpeg)"
+        );
+    } //}}}
+
+    #[test]
+    fn encode_base64image() {
+        //{{{
+        let base64image: Base64Image = PathBuf::from("./test/assets/test_encode_base64.jpg")
+            .try_into()
+            .unwrap();
+        let encoded = base64image.encode().unwrap().to_string();
+        assert!(encoded.starts_with("data:image/jpeg;base64,/9j/"));
+        assert_eq!(base64image.encode().unwrap(), encoded);
+
+        // Missing file
+        let base64image: Base64Image = PathBuf::from("./test/assets/missing.png")
+            .try_into()
+            .unwrap();
+        assert!(matches!(base64image.encode(), Err(Base64ImageError::Io(_))));
+        assert_eq!(
+            base64image.to_string(),
+            "./test/assets/missing.png (image/png)"
+        );
+        assert!(serde_json::to_value(&base64image).is_err());
+        assert!(matches!(
+            Base64Image::load("./test/assets/missing.png"),
+            Err(Base64ImageError::Io(_))
+        ));
+
+        // Image in memory
+        let base64image = Base64Image::from_bytes("missing.png", IMAGE_PNG, b"\x89PNG");
+        assert_eq!(
+            base64image.encode().unwrap(),
+            "data:image/png;base64,iVBORw=="
+        );
+        assert_eq!(base64image.path(), Path::new("missing.png"));
+    } //}}}
+
+    #[test]
+    fn encode_once() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scan.jpg");
+        // NOTE: Longer than the buffer of `std::io::copy` and not a multiple of 3
+        let mut data = std::fs::read("./test/assets/test_encode_base64.jpg").unwrap();
+        data.extend((0..20_000u32).map(|i| (i * 7 % 251) as u8));
+        std::fs::write(&path, &data).unwrap();
+
+        let base64image = Base64Image::load(&path).unwrap();
+        let expected = format!("data:image/jpeg;base64,{}", encode(&data));
+        assert_eq!(base64image.encode().unwrap(), expected);
+
+        // The encoding does not change with the file and does not fail without it
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(base64image.encode().unwrap(), expected);
+        assert_eq!(serde_json::to_value(&base64image).unwrap(), json!(expected));
+    } //}}}
+
+    #[test]
+    fn base64image_sniffing() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        let jpeg = std::fs::read("./test/assets/test_encode_base64.jpg").unwrap();
+
+        // Wrong and missing extensions
+        for name in ["photo.png", "photo"] {
+            let path = dir.path().join(name);
+            std::fs::write(&path, &jpeg).unwrap();
+            let base64image = Base64Image::try_from(path).unwrap();
+            assert_eq!(base64image.mime(), &IMAGE_JPEG);
+            assert!(base64image
+                .encode()
+                .unwrap()
+                .starts_with("data:image/jpeg;base64,/9j/"));
+        }
+
+        // Transcoded
+        let path = dir.path().join("figure.gif");
+        std::fs::write(&path, sample(ImageOutputFormat::Gif)).unwrap();
+        let base64image = Base64Image::try_from(path).unwrap();
+        assert_eq!(base64image.mime(), &IMAGE_PNG);
+        let encoded = base64image.encode().unwrap();
+        let png = decode(encoded.strip_prefix("data:image/png;base64,").unwrap()).unwrap();
+        assert_eq!(image::load_from_memory(&png).unwrap().width(), 3);
+
+        // Not an image
+        let path = dir.path().join("notes.png");
+        std::fs::write(&path, "# Notes").unwrap();
+        assert!(matches!(
+            Base64Image::try_from(path),
+            Err(Base64ImageError::UnsupportedFileType(_))
+        ));
+
+        // Broken image
+        let path = dir.path().join("broken.bmp");
+        std::fs::write(&path, b"BM broken").unwrap();
+        let base64image = Base64Image::try_from(path).unwrap();
+        assert!(matches!(
+            base64image.encode(),
+            Err(Base64ImageError::Transcode(_))
+        ));
+    } //}}}
+
+    #[test]
+    fn base64image_frames() {
+        //{{{
+        use tiff::encoder::{colortype, TiffEncoder};
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scan.tiff");
+        let mut encoder = TiffEncoder::new(std::fs::File::create(&path).unwrap()).unwrap();
+        for page in 0..3 {
+            encoder
+                .write_image::<colortype::Gray8>(2, 1, &[page, 255])
+                .unwrap();
+        }
+        drop(encoder);
+
+        let frames = Base64Image::frames(&path).unwrap();
+        let names: Vec<_> = frames.iter().map(|frame| frame.path().to_owned()).collect();
+        assert_eq!(
+            names,
+            vec![
+                dir.path().join("scan-1.png"),
+                dir.path().join("scan-2.png"),
+                dir.path().join("scan-3.png")
+            ]
+        );
+        assert!(frames.iter().all(|frame| frame
+            .encode()
+            .unwrap()
+            .starts_with("data:image/png;base64,")));
+
+        let frames = Base64Image::frames("./test/assets/test_encode_base64.jpg").unwrap();
+        assert_eq!(frames.len(), 1);
+        assert_eq!(frames[0].mime(), &IMAGE_JPEG);
+    } //}}}
+
+    #[test]
+    fn base64image_from_data() {
+        //{{{
+        let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+        let base64image = Base64Image::from_data(png).unwrap();
+        assert_eq!(base64image.mime(), &IMAGE_PNG);
+        assert_eq!(base64image.path(), Path::new("image.png"));
+        let jpeg = std::fs::read("./test/assets/test_encode_base64.jpg").unwrap();
+        assert_eq!(Base64Image::from_data(&jpeg).unwrap().mime(), &IMAGE_JPEG);
+        assert!(matches!(
+            Base64Image::from_data(b"plain text"),
+            Err(Base64ImageError::UnsupportedFileType(_))
+        ));
+        let base64image = Base64Image::from_data(&sample(ImageOutputFormat::WebP)).unwrap();
+        assert_eq!(base64image.path(), Path::new("image.png"));
+        assert!(base64image
+            .encode()
+            .unwrap()
+            .starts_with("data:image/png;base64,iVBOR"));
+    } //}}}
+
+    #[test]
+    fn data_urls() {
+        //{{{
+        assert!(validate_data_url("data:image/png;base64,iVBORw0KGgo=").is_ok());
+        assert!(validate_data_url("DATA:image/jpeg;charset=x;BASE64,/9j/").is_ok());
+        assert!(matches!(
+            validate_data_url("data:image/png,iVBORw0KGgo="),
+            Err(Base64ImageError::InvalidDataUrl(_))
+        ));
+        assert!(matches!(
+            validate_data_url("data:image/png;base64,not base64!"),
+            Err(Base64ImageError::InvalidDataUrl(_))
+        ));
+        assert!(matches!(
+            validate_data_url("data:image/png;base64"),
+            Err(Base64ImageError::InvalidDataUrl(_))
+        ));
+        assert!(matches!(
+            validate_data_url("data:text/plain;base64,aGk="),
+            Err(Base64ImageError::UnsupportedFileType(_))
+        ));
     } //}}}
 
     #[test]
@@ -159,6 +563,8 @@ mod base64image_tests {
         let base64image = Base64Image {
             img_path: "./test/assets/test_encode_base64.jpg".into(),
             img_mime: IMAGE_JPEG,
+            source_format: None,
+            encoded: OnceLock::new(),
         };
         let serialized = serde_json::to_value(&base64image).unwrap();
         let acctual = json!("data:image/jpeg;base64,/9j/4AAQSkZJRgABAQAAAQABAAD/2wBDAAMCAgICAgMCAgIDAwMDBAYEBAQEBAgGBgUGCQgKCgkICQkKDA8MCgsOCwkJDRENDg8QEBEQCgwSExIQEw8QEBD/wAALCAACAAIBAREA/8QAFAABAAAAAAAAAAAAAAAAAAAACP/EABwQAAEFAQEBAAAAAAAAAAAAAAIBAwQFBgcIAP/aAAgBAQAAPwBfeevPXAt7wLmm63XD+f6PSaPH01tcXFtmYUydZTpEJp1+TIfdbJx55xwzM3DJSIiUlVVVV+//2Q==");
@@ -0,0 +1,338 @@
+use regex::{Captures, Regex};
+use reqwest::header::{HeaderName, HeaderValue};
+use reqwest::Url;
+use serde::{Serialize, Serializer};
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CallBackError {
+    #[error("InvalidUrl: {0}")]
+    InvalidUrl(String),
+    #[error("InvalidHeader: {0}")]
+    InvalidHeader(String),
+}
+
+// CallBack {{{
+/**
+Callback that the server makes after the request is processed. The values of the `headers` can
+contain the variables `$date`, `$image` and `$tag` that are expanded with [CallBack::expand]. The
+endpoints expand `$date` (the UTC time of sending the request) and `$image` (the file name of the
+image) on their own when the request is created.
+
+```
+use mathpixapi::endpoint::latex::CallBack;
+use serde_json::json;
+
+let mut callback = CallBack::new("https://example.com/mathpix").unwrap();
+callback
+    .header("X-Image", "$image processed at $date")
+    .unwrap()
+    .header_line("X-Tag: $tag")
+    .unwrap()
+    .reply(json!({"job": 42}));
+```
+*/
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
+pub struct CallBack {
+    /// > URL to which to make POST callback
+    #[serde(serialize_with = "serialize_post")]
+    pub post: Option<Url>,
+    /// > Key value pairs of headers to make POST
+    pub headers: Option<BTreeMap<String, String>>,
+    /// > Sets values of `reply` field of callback response object (see [callback response object](https://docs.mathpix.com/?shell#callback-response-object))
+    pub reply: Option<serde_json::Value>,
+}
+
+impl CallBack {
+    /// Callback posting to the `post` URL. Only `http` and `https` URLs are valid.
+    pub fn new(post: &str) -> Result<Self, CallBackError> {
+        let mut callback = CallBack::default();
+        callback.post(post)?;
+        Ok(callback)
+    }
+
+    /// Set the URL of the callback. Only `http` and `https` URLs are valid.
+    pub fn post(&mut self, post: &str) -> Result<&mut Self, CallBackError> {
+        //{{{
+        let url = Url::parse(post)
+            .map_err(|error| CallBackError::InvalidUrl(format!("{:?}: {}", post, error)))?;
+        if !matches!(url.scheme(), "http" | "https") || url.host().is_none() {
+            return Err(CallBackError::InvalidUrl(format!(
+                "{:?}: the callback has to be an http or https URL with a host",
+                post
+            )));
+        }
+        self.post = Some(url);
+        Ok(self)
+    } //}}}
+
+    /**
+    Add the header `key` with the `value` to the callback. The `value` is checked again after
+    expanding the variables in it with [CallBack::expand].
+    */
+    pub fn header(&mut self, key: &str, value: &str) -> Result<&mut Self, CallBackError> {
+        //{{{
+        HeaderName::from_bytes(key.as_bytes())
+            .map_err(|_| CallBackError::InvalidHeader(format!("{:?} is not a header name", key)))?;
+        HeaderValue::from_str(value).map_err(|_| {
+            CallBackError::InvalidHeader(format!("{:?} is not a header value", value))
+        })?;
+        self.headers
+            .get_or_insert_with(BTreeMap::new)
+            .insert(key.to_string(), value.to_string());
+        Ok(self)
+    } //}}}
+
+    /// Add the header given as `KEY: VALUE`
+    pub fn header_line(&mut self, line: &str) -> Result<&mut Self, CallBackError> {
+        let (key, value) = line.split_once(':').ok_or_else(|| {
+            CallBackError::InvalidHeader(format!("{:?} is not in the form `KEY: VALUE`", line))
+        })?;
+        self.header(key.trim(), value.trim())
+    }
+
+    pub fn reply(&mut self, val: serde_json::Value) -> &mut Self {
+        self.reply = Some(val);
+        self
+    }
+
+    /**
+    The callback with the variables in the values of the `headers` replaced by the values in
+    `vars`. The variables without a value in `vars` and the unknown variables are kept as they are.
+    Fails if an expanded value is not a valid header value (e.g. `$image` is a file name with a
+    newline).
+    */
+    pub fn expand(&self, vars: &TemplateVars) -> Result<CallBack, CallBackError> {
+        //{{{
+        let variable = variable_pattern();
+        let headers = self
+            .headers
+            .as_ref()
+            .map(|headers| {
+                headers
+                    .iter()
+                    .map(|(key, value)| {
+                        let value = variable.replace_all(value, |captures: &Captures| {
+                            let expanded = match &captures[1] {
+                                "date" => &vars.date,
+                                "image" => &vars.image,
+                                _ => &vars.tag,
+                            };
+                            expanded.clone().unwrap_or_else(|| captures[0].to_string())
+                        });
+                        HeaderValue::from_str(&value).map_err(|_| {
+                            CallBackError::InvalidHeader(format!(
+                                "{:?} of {} is not a header value",
+                                value, key
+                            ))
+                        })?;
+                        Ok((key.clone(), value.into_owned()))
+                    })
+                    .collect::<Result<_, CallBackError>>()
+            })
+            .transpose()?;
+        Ok(CallBack {
+            post: self.post.clone(),
+            headers,
+            reply: self.reply.clone(),
+        })
+    } //}}}
+} // }}}
+
+/// Pattern of the variables in the header values
+fn variable_pattern() -> &'static Regex {
+    static VARIABLE: OnceLock<Regex> = OnceLock::new();
+    VARIABLE.get_or_init(|| Regex::new(r"\$(date|image|tag)\b").unwrap())
+}
+
+fn serialize_post<S: Serializer>(post: &Option<Url>, serializer: S) -> Result<S::Ok, S::Error> {
+    post.as_ref().map(Url::as_str).serialize(serializer)
+}
+
+// TemplateVars {{{
+/// Values of the variables in the header templates of a [CallBack]
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct TemplateVars {
+    /// Value of `$date`
+    pub date: Option<String>,
+    /// Value of `$image`
+    pub image: Option<String>,
+    /// Value of `$tag`
+    pub tag: Option<String>,
+}
+
+impl TemplateVars {
+    /// Variables with `$date` set to the current UTC time (e.g. `2021-09-07T10:00:00Z`)
+    pub fn now() -> Self {
+        TemplateVars {
+            date: Some(utc_date(SystemTime::now())),
+            ..Default::default()
+        }
+    }
+
+    pub fn date<S: Into<String>>(&mut self, val: S) -> &mut Self {
+        self.date = Some(val.into());
+        self
+    }
+
+    pub fn image<S: Into<String>>(&mut self, val: S) -> &mut Self {
+        self.image = Some(val.into());
+        self
+    }
+
+    pub fn tag<S: Into<String>>(&mut self, val: S) -> &mut Self {
+        self.tag = Some(val.into());
+        self
+    }
+} // }}}
+
+/**
+The request `body` as JSON with the variables in the `callback` field expanded by `vars`. The
+endpoints with a callback send this instead of serializing themselves directly.
+*/
+pub(crate) fn expand_callback<T, E>(
+    body: &T,
+    callback: Option<&CallBack>,
+    vars: &TemplateVars,
+) -> Result<serde_json::Value, E>
+where
+    T: Serialize,
+    E: From<serde_json::Error> + From<CallBackError>,
+{
+    let mut body = serde_json::to_value(body)?;
+    if let Some(callback) = callback {
+        body["callback"] = serde_json::to_value(callback.expand(vars)?)?;
+    }
+    Ok(body)
+}
+
+/// Format `time` as an RFC 3339 UTC date time with a second precision
+fn utc_date(time: SystemTime) -> String {
+    //{{{
+    let seconds = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, seconds) = ((seconds / 86400) as i64, seconds % 86400);
+    // NOTE: Conversion of the days since the epoch to a civil date from
+    // http://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+mod callback_tests {
+    use super::{utc_date, CallBack, CallBackError, TemplateVars};
+    use serde_json::json;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn validate_callback() {
+        //{{{
+        assert!(CallBack::new("https://example.com/callback").is_ok());
+        for post in &["example.com", "ftp://example.com/", "mailto:me@example.com"] {
+            assert!(matches!(
+                CallBack::new(post),
+                Err(CallBackError::InvalidUrl(_))
+            ));
+        }
+
+        let mut callback = CallBack::default();
+        assert!(callback.header("bad header", "value").is_err());
+        assert!(callback.header("X-Value", "new\nline").is_err());
+        assert!(callback.header_line("no delimiter").is_err());
+        callback.header_line("X-Url: https://example.com/").unwrap();
+        assert_eq!(
+            callback.headers.unwrap()["X-Url"],
+            "https://example.com/".to_string()
+        );
+    } //}}}
+
+    #[test]
+    fn serialize_callback() {
+        //{{{
+        let mut callback = CallBack::new("https://example.com/callback").unwrap();
+        callback
+            .header("X-Image", "$image")
+            .unwrap()
+            .reply(json!({"id": 42, "tags": ["a"]}));
+        assert_eq!(
+            serde_json::to_value(&callback).unwrap(),
+            json!({
+                "post": "https://example.com/callback",
+                "headers": {"X-Image": "$image"},
+                "reply": {"id": 42, "tags": ["a"]}
+            })
+        );
+    } //}}}
+
+    #[test]
+    fn expand_templates() {
+        //{{{
+        let mut callback = CallBack::default();
+        callback
+            .header("X-Info", "$image at $date ($tag)")
+            .unwrap()
+            .header("X-Other", "$dates $unknown")
+            .unwrap();
+        let mut vars = TemplateVars::default();
+        vars.image("scan.png").date("2021-09-07T10:00:00Z");
+        let expanded = callback.expand(&vars).unwrap();
+        let headers = expanded.headers.unwrap();
+        assert_eq!(headers["X-Info"], "scan.png at 2021-09-07T10:00:00Z ($tag)");
+        assert_eq!(headers["X-Other"], "$dates $unknown");
+
+        vars.tag("batch-1");
+        let headers = callback.expand(&vars).unwrap().headers.unwrap();
+        assert_eq!(
+            headers["X-Info"],
+            "scan.png at 2021-09-07T10:00:00Z (batch-1)"
+        );
+
+        vars.image("new\nline.png");
+        assert!(matches!(
+            callback.expand(&vars),
+            Err(CallBackError::InvalidHeader(_))
+        ));
+    } //}}}
+
+    #[test]
+    fn format_dates() {
+        //{{{
+        assert_eq!(utc_date(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+        assert_eq!(
+            utc_date(UNIX_EPOCH + Duration::from_secs(1_630_999_845)),
+            "2021-09-07T07:30:45Z"
+        );
+        assert_eq!(
+            utc_date(UNIX_EPOCH + Duration::from_secs(951_782_400)),
+            "2000-02-29T00:00:00Z"
+        );
+    } //}}}
+}
+// }}}
@@ -2,12 +2,40 @@ use reqwest::Url;
 use serde::{Serialize, Serializer};
 
 mod base64image;
+mod callback;
+mod transcode;
+use base64image::validate_data_url;
 pub use base64image::{Base64Image, Base64ImageError};
+pub(crate) use callback::expand_callback;
+pub use callback::{CallBack, CallBackError, TemplateVars};
 use num_traits::bounds::Bounded;
 use std::convert::TryFrom;
+use std::io::Read;
+use std::path::{Path, PathBuf};
 use thiserror::Error;
 
 // ImageSrc {{{
+/**
+Image that is sent to the server. It is either encoded in the request or given by a URL that the
+server downloads.
+
+A string is a URL when it starts with `http://`, `https://`, `data:` or `file:` and a path to an
+image file otherwise. Images in memory are accepted as bytes and their type is detected from their
+content.
+
+```no_run
+# fn run() -> Result<(), Box<dyn std::error::Error>> {
+use mathpixapi::endpoint::text::Text;
+use mathpixapi::endpoint::MathpixEndpoint;
+use std::path::PathBuf;
+
+let from_path = Text::new(None, PathBuf::from("equation.png"))?;
+let from_url = Text::new(None, "https://mathpix.com/examples/limit.jpg")?;
+let from_bytes = Text::new(None, std::fs::read("screenshot.png")?)?;
+# Ok(())
+# }
+```
+*/
 #[derive(Debug)]
 pub enum ImageSrc {
     Image(Base64Image),
@@ -24,10 +52,156 @@ impl Serialize for ImageSrc {
             ImageSrc::Url(url) => url.to_string().serialize(serializer),
         }
     }
+}
+
+impl ImageSrc {
+    /// File name of the image or the last segment of the URL path
+    pub fn file_name(&self) -> Option<String> {
+        match self {
+            ImageSrc::Image(img) => img
+                .path()
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned()),
+            ImageSrc::Url(url) => url
+                .path_segments()
+                .and_then(|mut segments| segments.next_back())
+                .filter(|segment| !segment.is_empty())
+                .map(str::to_string),
+        }
+    }
+
+    /// The string that the source is sent as. An image is read and encoded (once), so its errors
+    /// are reported before the request body is serialized.
+    pub fn encode(&self) -> Result<&str, Base64ImageError> {
+        match self {
+            ImageSrc::Image(img) => img.encode(),
+            ImageSrc::Url(url) => Ok(url.as_str()),
+        }
+    }
+
+    /// Image read from `reader` to its end, e.g. from `std::io::stdin()`
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, Base64ImageError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::try_from(data)
+    }
+}
+
+impl From<Base64Image> for ImageSrc {
+    fn from(image: Base64Image) -> Self {
+        ImageSrc::Image(image)
+    }
+}
+
+impl TryFrom<PathBuf> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(path: PathBuf) -> Result<Self, Self::Error> {
+        Ok(ImageSrc::Image(Base64Image::try_from(path)?))
+    }
+}
+
+impl TryFrom<&Path> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(path: &Path) -> Result<Self, Self::Error> {
+        Self::try_from(path.to_path_buf())
+    }
+}
+
+impl TryFrom<Url> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(url: Url) -> Result<Self, Self::Error> {
+        //{{{
+        match url.scheme() {
+            "http" | "https" => Ok(ImageSrc::Url(url)),
+            "data" => {
+                validate_data_url(url.as_str())?;
+                Ok(ImageSrc::Url(url))
+            }
+            // NOTE: The server can not read local files, so they are sent encoded
+            "file" => {
+                let path = url.to_file_path().map_err(|_| {
+                    Base64ImageError::UnsupportedUrl(format!("{} is not a local file", url))
+                })?;
+                Self::try_from(path)
+            }
+            scheme => Err(Base64ImageError::UnsupportedUrl(format!(
+                "The scheme {} of {} is not supported. http, https, data and file URLs are supported.",
+                scheme, url
+            ))),
+        }
+    } //}}}
+}
+
+impl TryFrom<&str> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(src: &str) -> Result<Self, Self::Error> {
+        //{{{
+        // NOTE: Only the known schemes are treated as URLs so that e.g. `C:\image.png` is a path
+        let is_url = src.split_once(':').is_some_and(|(scheme, _)| {
+            ["http", "https", "data", "file"]
+                .iter()
+                .any(|known| scheme.eq_ignore_ascii_case(known))
+        });
+        if is_url {
+            let url = Url::parse(src)
+                .map_err(|error| Base64ImageError::UnsupportedUrl(format!("{}: {}", src, error)))?;
+            Self::try_from(url)
+        } else {
+            Self::try_from(PathBuf::from(src))
+        }
+    } //}}}
+}
+
+impl TryFrom<String> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(src: String) -> Result<Self, Self::Error> {
+        Self::try_from(src.as_str())
+    }
+}
+
+impl TryFrom<&[u8]> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+        Ok(ImageSrc::Image(Base64Image::from_data(data)?))
+    }
+}
+
+impl TryFrom<Vec<u8>> for ImageSrc {
+    type Error = Base64ImageError;
+
+    fn try_from(data: Vec<u8>) -> Result<Self, Self::Error> {
+        Self::try_from(data.as_slice())
+    }
 } //}}}
 
+/**
+The `request` with the JSON `body` serialized into a buffer of `capacity` bytes. The body of a
+request with an image is mostly the encoded image, so a capacity of its length (and some more)
+saves the reallocations and the copies of the growing buffer.
+*/
+pub(crate) fn json_body<T: Serialize>(
+    request: reqwest::RequestBuilder,
+    body: &T,
+    capacity: usize,
+) -> Result<reqwest::RequestBuilder, serde_json::Error> {
+    let mut buffer = Vec::with_capacity(capacity);
+    serde_json::to_writer(&mut buffer, body)?;
+    Ok(request
+        .header(reqwest::header::CONTENT_TYPE, "application/json")
+        .body(buffer))
+}
+
+/// Room for the other fields of a request body next to its source
+pub(crate) const BODY_CAPACITY: usize = 4096;
+
 // TODO: Ask mathpix what are the possibilities for MetaData <14-05-21, kunzaatko> //
-#[derive(Debug, Serialize, PartialEq)]
+#[derive(Debug, Serialize, PartialEq, Clone, Default)]
 pub struct MetaData {}
 
 // DataOptions {{{
@@ -53,18 +227,6 @@ impl DataOptions {
 
 //}}}
 
-// CallBack {{{
-#[derive(Debug, Serialize, PartialEq, Clone)]
-pub struct CallBack {
-    /// > URL to which to make POST callback
-    pub post: Option<String>,
-    /// > Key value pairs of headers to make POST
-    pub headers: Option<String>,
-    /// > Sets values of `reply` field of callback response object (see [callback response object](https://docs.mathpix.com/?shell#callback-response-object))
-    pub reply: Option<String>,
-}
-//}}}
-
 // AlphabetsAllowed {{{
 // NOTE: Serialization adds serde_json::Value::Null when None... This may not work with the API. A
 // test is needed. <21-05-21, kunzaatko> //
@@ -191,11 +353,13 @@ pub enum ConfidenceThresholdError {
 #[cfg(test)]
 mod request_shared_objects_tests {
     use super::Base64Image;
-    use super::{DataOptions, ImageSrc};
+    use super::{Base64ImageError, DataOptions, ImageSrc};
     use reqwest::Url;
     use serde_json::{json, Value::Null};
-    use std::convert::TryInto;
-    use std::path::PathBuf;
+    use std::convert::{TryFrom, TryInto};
+    use std::path::{Path, PathBuf};
+
+    const JPEG: &str = "./test/assets/test_encode_base64.jpg";
 
     #[test]
     fn serialize_src_url() {
@@ -219,6 +383,64 @@ mod request_shared_objects_tests {
         assert_eq!(serialized, acctual);
     } //}}}
 
+    #[test]
+    fn src_from_strings() {
+        //{{{
+        let src = ImageSrc::try_from("https://mathpix.com/examples/limit.jpg").unwrap();
+        assert!(matches!(src, ImageSrc::Url(url) if url.path() == "/examples/limit.jpg"));
+        let src = ImageSrc::try_from("HTTP://mathpix.com/limit.jpg".to_string()).unwrap();
+        assert!(matches!(src, ImageSrc::Url(_)));
+        let src = ImageSrc::try_from(JPEG).unwrap();
+        assert!(matches!(&src, ImageSrc::Image(image) if image.path() == Path::new(JPEG)));
+        assert_eq!(src.file_name().as_deref(), Some("test_encode_base64.jpg"));
+        // NOTE: A drive letter is not a scheme
+        let src = ImageSrc::try_from(r"C:\images\limit.png").unwrap();
+        assert!(matches!(src, ImageSrc::Image(_)));
+        assert!(matches!(
+            ImageSrc::try_from("notes.txt"),
+            Err(Base64ImageError::UnsupportedFileType(_))
+        ));
+    } //}}}
+
+    #[test]
+    fn src_from_urls() {
+        //{{{
+        let data = "data:image/png;base64,iVBORw0KGgo=";
+        let src = ImageSrc::try_from(data).unwrap();
+        assert_eq!(serde_json::to_value(&src).unwrap(), json!(data));
+        assert!(matches!(
+            ImageSrc::try_from("data:image/png;base64,???"),
+            Err(Base64ImageError::InvalidDataUrl(_))
+        ));
+
+        let path = std::fs::canonicalize(JPEG).unwrap();
+        let src = ImageSrc::try_from(Url::from_file_path(&path).unwrap()).unwrap();
+        assert!(matches!(src, ImageSrc::Image(image) if image.path() == path));
+
+        let url = Url::parse("ftp://mathpix.com/limit.jpg").unwrap();
+        assert!(matches!(
+            ImageSrc::try_from(url),
+            Err(Base64ImageError::UnsupportedUrl(_))
+        ));
+    } //}}}
+
+    #[test]
+    fn src_from_bytes() {
+        //{{{
+        let data = std::fs::read(JPEG).unwrap();
+        let expected = serde_json::to_value(ImageSrc::try_from(PathBuf::from(JPEG)).unwrap());
+        let src = ImageSrc::try_from(data.clone()).unwrap();
+        assert_eq!(src.file_name().as_deref(), Some("image.jpg"));
+        assert_eq!(serde_json::to_value(&src).unwrap(), expected.unwrap());
+
+        let src = ImageSrc::from_reader(std::io::Cursor::new(&data)).unwrap();
+        assert!(matches!(src, ImageSrc::Image(image) if image.mime() == &mime::IMAGE_JPEG));
+        assert!(matches!(
+            ImageSrc::try_from(&b"%PDF-1.5"[..]),
+            Err(Base64ImageError::UnsupportedFileType(_))
+        ));
+    } //}}}
+
     #[test]
     fn serialize_data_options() {
         //{{{
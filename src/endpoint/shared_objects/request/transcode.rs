@@ -0,0 +1,254 @@
+/*!
+Detection of the image formats and transcoding of the formats that the server does not accept to
+PNG. The server only accepts JPEG and PNG images, the other supported formats are decoded locally.
+*/
+use super::base64image::Base64ImageError;
+use image::{DynamicImage, ImageBuffer, ImageFormat, ImageOutputFormat};
+use mime::{Mime, IMAGE_JPEG, IMAGE_PNG};
+use std::io::Cursor;
+use tiff::decoder::{Decoder, DecodingResult};
+use tiff::ColorType;
+
+const JPEG_EXTENSIONS: &[&str] = &["jpg", "jpeg", "jpe", "jif", "jfif", "jfi"];
+const PNG_EXTENSIONS: &[&str] = &["png"];
+/// Formats that are transcoded to PNG before they are sent
+const TRANSCODED_FORMATS: &[ImageFormat] = &[
+    ImageFormat::Gif,
+    ImageFormat::Bmp,
+    ImageFormat::WebP,
+    ImageFormat::Tiff,
+    ImageFormat::Pnm,
+];
+/// Number of bytes that is enough to detect any of the supported formats
+pub(crate) const SNIFF_LEN: usize = 12;
+pub(crate) const SUPPORTED_FORMATS: &str = "jpg, png, gif, bmp, webp, tiff and pnm";
+
+/// MIME type of the format if the server accepts it as it is
+pub(crate) fn accepted_mime(format: ImageFormat) -> Option<Mime> {
+    match format {
+        ImageFormat::Jpeg => Some(IMAGE_JPEG),
+        ImageFormat::Png => Some(IMAGE_PNG),
+        _ => None,
+    }
+}
+
+/// Format of the image `data` detected from its first bytes
+pub(crate) fn sniff_format(data: &[u8]) -> Option<ImageFormat> {
+    //{{{
+    let pnm = |data: &[u8]| {
+        data.len() > 2
+            && data[0] == b'P'
+            && (b'1'..=b'7').contains(&data[1])
+            && data[2].is_ascii_whitespace()
+    };
+    match data {
+        [0xff, 0xd8, 0xff, ..] => Some(ImageFormat::Jpeg),
+        [0x89, b'P', b'N', b'G', b'\r', b'\n', 0x1a, b'\n', ..] => Some(ImageFormat::Png),
+        [b'G', b'I', b'F', b'8', b'7' | b'9', b'a', ..] => Some(ImageFormat::Gif),
+        [b'B', b'M', ..] => Some(ImageFormat::Bmp),
+        // NOTE: RIFF is a container of other formats as well (e.g. WAV)
+        [b'R', b'I', b'F', b'F', _, _, _, _, b'W', b'E', b'B', b'P', ..] => Some(ImageFormat::WebP),
+        [b'I', b'I', 0x2a, 0x00, ..] | [b'M', b'M', 0x00, 0x2a, ..] => Some(ImageFormat::Tiff),
+        _ if pnm(data) => Some(ImageFormat::Pnm),
+        _ => None,
+    }
+} //}}}
+
+/// Format of an image with the file `extension` (case insensitive)
+pub(crate) fn format_from_extension(extension: &str) -> Option<ImageFormat> {
+    let extension = extension.to_lowercase();
+    if JPEG_EXTENSIONS.contains(&extension.as_str()) {
+        Some(ImageFormat::Jpeg)
+    } else if PNG_EXTENSIONS.contains(&extension.as_str()) {
+        Some(ImageFormat::Png)
+    } else {
+        let format = match extension.as_str() {
+            "pnm" => ImageFormat::Pnm,
+            _ => ImageFormat::from_extension(&extension)?,
+        };
+        Some(format).filter(|format| TRANSCODED_FORMATS.contains(format))
+    }
+}
+
+fn transcode_error<E: std::fmt::Display>(format: ImageFormat) -> impl Fn(E) -> Base64ImageError {
+    move |error| Base64ImageError::Transcode(format!("{:?} image: {}", format, error))
+}
+
+fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, Base64ImageError> {
+    let mut png = Cursor::new(Vec::new());
+    image
+        .write_to(&mut png, ImageOutputFormat::Png)
+        .map_err(transcode_error(ImageFormat::Png))?;
+    Ok(png.into_inner())
+}
+
+/**
+Decode the image `data` in `format` and encode it as PNG. Only the first frame of animated GIF and
+WebP images and of multi-frame TIFF images is transcoded.
+*/
+pub(crate) fn to_png(data: &[u8], format: ImageFormat) -> Result<Vec<u8>, Base64ImageError> {
+    //{{{
+    if format == ImageFormat::Tiff {
+        let decoder = Decoder::new(Cursor::new(data)).map_err(transcode_error(format))?;
+        if decoder.more_images() {
+            log::warn!("Only the first frame of the multi-frame TIFF image is used");
+        }
+    }
+    let image =
+        image::load_from_memory_with_format(data, format).map_err(transcode_error(format))?;
+    encode_png(&image)
+} //}}}
+
+/// Decode every frame of the TIFF image `data` and encode them as PNG
+pub(crate) fn tiff_frames_to_png(data: &[u8]) -> Result<Vec<Vec<u8>>, Base64ImageError> {
+    //{{{
+    let error = transcode_error(ImageFormat::Tiff);
+    let invalid = transcode_error::<String>(ImageFormat::Tiff);
+    let mut decoder = Decoder::new(Cursor::new(data)).map_err(&error)?;
+    let mut frames = Vec::new();
+    loop {
+        let (width, height) = decoder.dimensions().map_err(&error)?;
+        let color = decoder.colortype().map_err(&error)?;
+        let pixels = decoder.read_image().map_err(&error)?;
+        let image = match (color, pixels) {
+            (ColorType::Gray(8), DecodingResult::U8(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageLuma8)
+            }
+            (ColorType::GrayA(8), DecodingResult::U8(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageLumaA8)
+            }
+            (ColorType::RGB(8), DecodingResult::U8(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageRgb8)
+            }
+            (ColorType::RGBA(8), DecodingResult::U8(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageRgba8)
+            }
+            (ColorType::Gray(16), DecodingResult::U16(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageLuma16)
+            }
+            (ColorType::GrayA(16), DecodingResult::U16(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageLumaA16)
+            }
+            (ColorType::RGB(16), DecodingResult::U16(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageRgb16)
+            }
+            (ColorType::RGBA(16), DecodingResult::U16(buffer)) => {
+                ImageBuffer::from_raw(width, height, buffer).map(DynamicImage::ImageRgba16)
+            }
+            (color, _) => {
+                return Err(invalid(format!(
+                    "frame {} has an unsupported color type {:?}",
+                    frames.len() + 1,
+                    color
+                )))
+            }
+        }
+        .ok_or_else(|| invalid(format!("frame {} is truncated", frames.len() + 1)))?;
+        frames.push(encode_png(&image)?);
+        if !decoder.more_images() {
+            return Ok(frames);
+        }
+        decoder.next_image().map_err(&error)?;
+    }
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+pub(crate) mod transcode_tests {
+    use super::*;
+    use image::{Rgb, RgbImage};
+
+    /// Small image with distinct pixels encoded in `format`
+    pub(crate) fn sample(format: ImageOutputFormat) -> Vec<u8> {
+        let image = RgbImage::from_fn(3, 2, |x, y| Rgb([x as u8 * 80, y as u8 * 120, 200]));
+        let mut data = Cursor::new(Vec::new());
+        DynamicImage::ImageRgb8(image)
+            .write_to(&mut data, format)
+            .unwrap();
+        data.into_inner()
+    }
+
+    #[test]
+    fn sniff_formats() {
+        //{{{
+        let jpeg = std::fs::read("./test/assets/test_encode_base64.jpg").unwrap();
+        assert_eq!(sniff_format(&jpeg), Some(ImageFormat::Jpeg));
+        for (format, output) in [
+            (ImageFormat::Png, ImageOutputFormat::Png),
+            (ImageFormat::Gif, ImageOutputFormat::Gif),
+            (ImageFormat::Bmp, ImageOutputFormat::Bmp),
+            (ImageFormat::WebP, ImageOutputFormat::WebP),
+            (ImageFormat::Tiff, ImageOutputFormat::Tiff),
+            (
+                ImageFormat::Pnm,
+                ImageOutputFormat::Pnm(image::codecs::pnm::PnmSubtype::Pixmap(
+                    image::codecs::pnm::SampleEncoding::Binary,
+                )),
+            ),
+        ] {
+            assert_eq!(sniff_format(&sample(output)), Some(format));
+        }
+        assert_eq!(sniff_format(b"RIFF\x24\0\0\0WAVEfmt "), None);
+        assert_eq!(sniff_format(b"PDF-1.5"), None);
+        assert_eq!(sniff_format(b""), None);
+    } //}}}
+
+    #[test]
+    fn formats_from_extensions() {
+        //{{{
+        assert_eq!(format_from_extension("JFIF"), Some(ImageFormat::Jpeg));
+        assert_eq!(format_from_extension("png"), Some(ImageFormat::Png));
+        assert_eq!(format_from_extension("Tif"), Some(ImageFormat::Tiff));
+        assert_eq!(format_from_extension("pnm"), Some(ImageFormat::Pnm));
+        assert_eq!(format_from_extension("pgm"), Some(ImageFormat::Pnm));
+        assert_eq!(format_from_extension("ico"), None);
+        assert_eq!(format_from_extension("txt"), None);
+    } //}}}
+
+    #[test]
+    fn transcode_to_png() {
+        //{{{
+        let png = to_png(&sample(ImageOutputFormat::Bmp), ImageFormat::Bmp).unwrap();
+        assert_eq!(sniff_format(&png), Some(ImageFormat::Png));
+        let decoded = image::load_from_memory(&png).unwrap().to_rgb8();
+        assert_eq!(decoded.dimensions(), (3, 2));
+        assert_eq!(decoded.get_pixel(2, 1), &Rgb([160, 120, 200]));
+
+        assert!(matches!(
+            to_png(b"GIF89a broken", ImageFormat::Gif),
+            Err(Base64ImageError::Transcode(_))
+        ));
+    } //}}}
+
+    #[test]
+    fn tiff_frames() {
+        //{{{
+        use tiff::encoder::{colortype, TiffEncoder};
+        let mut data = Cursor::new(Vec::new());
+        let mut encoder = TiffEncoder::new(&mut data).unwrap();
+        encoder
+            .write_image::<colortype::Gray8>(2, 2, &[0, 50, 100, 150])
+            .unwrap();
+        encoder
+            .write_image::<colortype::RGB8>(1, 1, &[10, 20, 30])
+            .unwrap();
+        let data = data.into_inner();
+
+        let frames = tiff_frames_to_png(&data).unwrap();
+        assert_eq!(frames.len(), 2);
+        let first = image::load_from_memory(&frames[0]).unwrap().to_luma8();
+        assert_eq!(first.as_raw(), &vec![0, 50, 100, 150]);
+        let second = image::load_from_memory(&frames[1]).unwrap().to_rgb8();
+        assert_eq!(second.as_raw(), &vec![10, 20, 30]);
+
+        // NOTE: A single image is the first frame
+        let png = to_png(&data, ImageFormat::Tiff).unwrap();
+        assert_eq!(png, frames[0]);
+
+        assert!(matches!(
+            tiff_frames_to_png(&data[..20]),
+            Err(Base64ImageError::Transcode(_))
+        ));
+    } //}}}
+}
+// }}}
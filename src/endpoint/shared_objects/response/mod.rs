@@ -20,6 +20,8 @@ pub struct LineData {
     /// Countour for line expressed as list of (x,y) pixel coordinate pairs
     pub cnt: Vec<(i32, i32)>,
     /// Whether this line is included in the top level OCR result
+    // NOTE: The lines of PDF results do not have this field and are always included
+    #[serde(default = "included_by_default")]
     pub included: bool,
     /// Error ID, reason why the line is not included in final result
     pub error_id: Option<String>,
@@ -37,6 +39,10 @@ pub struct LineData {
     pub data: Option<Vec<Data>>,
 } //}}}
 
+fn included_by_default() -> bool {
+    true
+}
+
 // pub struct WordData {{{
 /// The _v3/text_ endpoint allows customers to request word by word data by adding a `include_word_data` request parameter to the request. When this parameter is true, the response object then includes a `word_data` field which is a list of WordData objects containing information about all word level elements detected in the image.
 #[derive(Debug, Deserialize, PartialEq)]
@@ -134,6 +140,7 @@ pub struct ErrorInfo {
 
 // TESTS {{{
 #[cfg(test)]
+#[allow(clippy::excessive_precision)]
 mod test {
     use super::*;
     use serde_json::json;
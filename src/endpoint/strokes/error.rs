@@ -1,6 +1,114 @@
+use super::preprocess::PreprocessStep;
+use reqwest;
+use serde_json;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum StrokesError {
-    // TODO:  <21-07-21, kunzaatko> //
+    #[error("SerializationError: {0}")]
+    Serialization(#[from] serde_json::Error),
+    #[error("SrcError: {0}")]
+    Src(#[from] StrokeError),
+    #[error("RequestError: {0}")]
+    Request(#[from] reqwest::Error),
+    #[error("SessionError: {0}")]
+    Session(String),
+}
+
+impl From<std::convert::Infallible> for StrokesError {
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
+    }
+}
+
+#[derive(Debug, Error, PartialEq)]
+pub enum StrokeError {
+    #[error("NoStrokes: there has to be at least one stroke")]
+    NoStrokes,
+    #[error("EmptyStroke: stroke {0} has no points")]
+    EmptyStroke(usize),
+    #[error("LengthMismatch: stroke {stroke} has {x} x and {y} y coordinates")]
+    LengthMismatch { stroke: usize, x: usize, y: usize },
+    #[error("NonFiniteCoordinate: stroke {stroke} has a coordinate {value} at point {point}")]
+    NonFiniteCoordinate {
+        stroke: usize,
+        point: usize,
+        value: f64,
+    },
+    #[error("InvalidTolerance: {value} is not a valid tolerance of {step}")]
+    InvalidTolerance { step: PreprocessStep, value: f64 },
+    #[error("TooManyPoints: resampling stroke {stroke} {spacing} apart places more than {max} points on it")]
+    TooManyPoints {
+        stroke: usize,
+        spacing: f64,
+        max: usize,
+    },
+}
+
+#[derive(Debug, Error)]
+pub enum InkMLError {
+    #[error("XmlError: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("NotInkML: the root element is `{0}` instead of `ink`")]
+    NotInkML(String),
+    #[error("UnknownReference: {0:?} does not reference a context or a trace format")]
+    UnknownReference(String),
+    #[error("CyclicReference: {0:?} references itself through its contexts")]
+    CyclicReference(String),
+    #[error("InvalidTrace: trace {trace}: {message}")]
+    InvalidTrace { trace: usize, message: String },
+    #[error("StrokeError: {0}")]
+    Stroke(#[from] StrokeError),
+}
+
+#[derive(Debug, Error)]
+pub enum XoppError {
+    #[error("IoError: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("XmlError: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("NotXopp: the root element is `{0}` instead of `xournal`")]
+    NotXopp(String),
+    #[error("InvalidStroke: page {page}, stroke {stroke}: {message}")]
+    InvalidStroke {
+        page: usize,
+        stroke: usize,
+        message: String,
+    },
+    #[error("InvalidImage: page {page}, image {image}: {message}")]
+    InvalidImage {
+        page: usize,
+        image: usize,
+        message: String,
+    },
+    #[error("StrokeError: {0}")]
+    Stroke(#[from] StrokeError),
+}
+
+#[derive(Debug, Error)]
+pub enum SvgError {
+    #[error("XmlError: {0}")]
+    Xml(#[from] roxmltree::Error),
+    #[error("NotSvg: the root element is `{0}` instead of `svg`")]
+    NotSvg(String),
+    #[error("InvalidTolerance: the tolerance has to be positive but it is {0}")]
+    InvalidTolerance(f64),
+    #[error("InvalidElement: {name} {element}: {message}")]
+    InvalidElement {
+        element: usize,
+        name: String,
+        message: String,
+    },
+    #[error("StrokeError: {0}")]
+    Stroke(#[from] StrokeError),
+}
+
+#[derive(Debug, Error)]
+pub enum RenderError {
+    #[error("InvalidOption: {0}")]
+    InvalidOption(String),
+    #[error("TooLarge: the image would be {width}x{height} pixels")]
+    TooLarge { width: u64, height: u64 },
+    #[error("EncodingError: {0}")]
+    Encoding(#[from] png::EncodingError),
 }
@@ -0,0 +1,423 @@
+use super::error::InkMLError;
+use super::stroke_set::{Stroke, StrokeSet};
+use regex::Regex;
+use roxmltree::{Document, Node};
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// Channel order when the document does not specify a trace format
+const DEFAULT_CHANNELS: &[&str] = &["X", "Y"];
+
+impl StrokeSet {
+    /**
+    Parse the traces of an [InkML](https://www.w3.org/TR/InkML/) document into strokes. Every
+    `trace` (also inside of `traceGroup`s) is a stroke. The position of the `X` and `Y` channels
+    is taken from the `traceFormat` of the trace context (`contextRef` of the trace or its trace
+    group, or the last `context` before it) and the explicit (`!`), first difference (`'`) and
+    second difference (`"`) encodings of the values are supported.
+
+    ```
+    use mathpixapi::endpoint::strokes::StrokeSet;
+
+    let strokes = StrokeSet::from_inkml(r#"
+        <ink xmlns="http://www.w3.org/2003/InkML">
+          <traceFormat>
+            <channel name="Y"/>
+            <channel name="X"/>
+          </traceFormat>
+          <trace>10 0, 9 14, 8 28</trace>
+          <traceGroup>
+            <trace>130 155, '-1 0, "1 1</trace>
+          </traceGroup>
+        </ink>
+    "#).unwrap();
+    assert_eq!(strokes.strokes()[0].x, vec![0., 14., 28.]);
+    assert_eq!(strokes.strokes()[1].y, vec![130., 129., 129.]);
+    ```
+    */
+    pub fn from_inkml(document: &str) -> Result<StrokeSet, InkMLError> {
+        //{{{
+        let document = Document::parse(document)?;
+        let root = document.root_element();
+        if root.tag_name().name() != "ink" {
+            return Err(InkMLError::NotInkML(root.tag_name().name().to_string()));
+        }
+        let formats = TraceFormats::new(&document)?;
+
+        let mut strokes = Vec::new();
+        let mut current: Vec<String> = channels(DEFAULT_CHANNELS.iter().copied());
+        for node in root.descendants().filter(Node::is_element) {
+            if node
+                .ancestors()
+                .any(|node| node.has_tag_name_local("definitions"))
+            {
+                continue;
+            }
+            match node.tag_name().name() {
+                "traceFormat" if node.parent() == Some(root) => {
+                    current = format_channels(node);
+                }
+                "context" if node.parent() == Some(root) => {
+                    current = formats.context(node)?;
+                }
+                "trace" => {
+                    let format = match trace_context(node) {
+                        Some(reference) => formats.reference(reference)?,
+                        None => current.clone(),
+                    };
+                    let stroke =
+                        parse_trace(node.text().unwrap_or(""), &format).map_err(|message| {
+                            InkMLError::InvalidTrace {
+                                trace: strokes.len(),
+                                message,
+                            }
+                        })?;
+                    if !stroke.is_empty() {
+                        strokes.push(stroke);
+                    }
+                }
+                _ => {}
+            }
+        }
+        Ok(StrokeSet::new(strokes)?)
+    } //}}}
+}
+
+trait LocalName {
+    fn has_tag_name_local(&self, name: &str) -> bool;
+}
+
+impl LocalName for Node<'_, '_> {
+    fn has_tag_name_local(&self, name: &str) -> bool {
+        self.is_element() && self.tag_name().name() == name
+    }
+}
+
+/// The `contextRef` of the trace or of the closest trace group with one
+fn trace_context<'a>(trace: Node<'a, '_>) -> Option<&'a str> {
+    trace
+        .ancestors()
+        .filter(|node| node.has_tag_name_local("trace") || node.has_tag_name_local("traceGroup"))
+        .find_map(|node| node.attribute("contextRef"))
+}
+
+fn channels<'a, I: Iterator<Item = &'a str>>(names: I) -> Vec<String> {
+    names.map(str::to_string).collect()
+}
+
+/// Names of the regular channels of a `traceFormat` (the intermittent channels come after them)
+fn format_channels(format: Node) -> Vec<String> {
+    channels(
+        format
+            .children()
+            .filter(|node| node.has_tag_name_local("channel"))
+            .filter_map(|channel| channel.attribute("name")),
+    )
+}
+
+fn id<'a>(node: Node<'a, '_>) -> Option<&'a str> {
+    node.attribute(("http://www.w3.org/XML/1998/namespace", "id"))
+        .or_else(|| node.attribute("id"))
+}
+
+// TraceFormats {{{
+/// The trace formats and contexts of the document by their `xml:id`
+struct TraceFormats<'a, 'input> {
+    formats: HashMap<String, Node<'a, 'input>>,
+    contexts: HashMap<String, Node<'a, 'input>>,
+}
+
+impl<'a, 'input> TraceFormats<'a, 'input> {
+    fn new(document: &'a Document<'input>) -> Result<Self, InkMLError> {
+        let mut formats = HashMap::new();
+        let mut contexts = HashMap::new();
+        for node in document.root().descendants().filter(Node::is_element) {
+            match (node.tag_name().name(), id(node)) {
+                ("traceFormat", Some(id)) => {
+                    formats.insert(id.to_string(), node);
+                }
+                ("context", Some(id)) => {
+                    contexts.insert(id.to_string(), node);
+                }
+                _ => {}
+            }
+        }
+        Ok(TraceFormats { formats, contexts })
+    }
+
+    /// Channels of the context or trace format referenced by `reference` (`#ID` or `ID`)
+    fn reference(&self, reference: &str) -> Result<Vec<String>, InkMLError> {
+        self.resolve_reference(reference, &mut HashSet::new())
+    }
+
+    /// Channels of the `context` element
+    fn context(&self, context: Node) -> Result<Vec<String>, InkMLError> {
+        self.resolve_context(context, &mut HashSet::new())
+    }
+
+    /// Channels of the `reference` that is not one of the already `visited` references
+    fn resolve_reference(
+        &self,
+        reference: &str,
+        visited: &mut HashSet<String>,
+    ) -> Result<Vec<String>, InkMLError> {
+        //{{{
+        let key = reference.trim_start_matches('#');
+        if !visited.insert(key.to_string()) {
+            return Err(InkMLError::CyclicReference(reference.to_string()));
+        }
+        if let Some(context) = self.contexts.get(key) {
+            self.resolve_context(*context, visited)
+        } else if let Some(format) = self.formats.get(key) {
+            Ok(format_channels(*format))
+        } else {
+            Err(InkMLError::UnknownReference(reference.to_string()))
+        }
+    } //}}}
+
+    /// Channels of the `context` without following the already `visited` references again
+    fn resolve_context(
+        &self,
+        context: Node,
+        visited: &mut HashSet<String>,
+    ) -> Result<Vec<String>, InkMLError> {
+        //{{{
+        if let Some(format) = context
+            .children()
+            .find(|node| node.has_tag_name_local("traceFormat"))
+        {
+            return Ok(format_channels(format));
+        }
+        if let Some(reference) = context.attribute("traceFormatRef") {
+            return self.resolve_reference(reference, visited);
+        }
+        match context.attribute("contextRef") {
+            Some(reference) => self.resolve_reference(reference, visited),
+            None => Ok(channels(DEFAULT_CHANNELS.iter().copied())),
+        }
+    } //}}}
+} // }}}
+
+// parse_trace {{{
+#[derive(Clone, Copy, PartialEq)]
+enum Encoding {
+    Explicit,
+    FirstDifference,
+    SecondDifference,
+}
+
+/// State of the decoding of a channel. The encoding of a value is kept for the following values.
+#[derive(Clone, Copy)]
+struct Channel {
+    encoding: Encoding,
+    value: f64,
+    velocity: f64,
+}
+
+/// Pattern of a value of a point with its optional encoding prefix
+fn value_pattern() -> &'static Regex {
+    static VALUE: OnceLock<Regex> = OnceLock::new();
+    VALUE.get_or_init(|| {
+        Regex::new(r#"([!'"])?\s*([-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?|[TF?*])"#).unwrap()
+    })
+}
+
+/// Decode the points of the trace and take the `X` and `Y` channels of the `format`
+fn parse_trace(text: &str, format: &[String]) -> Result<Stroke, String> {
+    let position = |name: &str| {
+        format
+            .iter()
+            .position(|channel| channel == name)
+            .ok_or_else(|| format!("the trace format has no {} channel", name))
+    };
+    let (x, y) = (position("X")?, position("Y")?);
+    let value = value_pattern();
+
+    let new_channel = || Channel {
+        encoding: Encoding::Explicit,
+        value: 0.,
+        velocity: 0.,
+    };
+    let mut channels = vec![new_channel(); format.len()];
+    let mut stroke = Stroke::default();
+    for point in text
+        .split(',')
+        .map(str::trim)
+        .filter(|point| !point.is_empty())
+    {
+        let mut end = 0;
+        let mut index = 0;
+        for captures in value.captures_iter(point) {
+            let token = captures.get(0).unwrap();
+            if !point[end..token.start()].trim().is_empty() {
+                return Err(format!("invalid point {:?}", point));
+            }
+            // NOTE: Values of channels that are not in the format are decoded and ignored
+            if index == channels.len() {
+                channels.push(new_channel());
+            }
+            end = token.end();
+            let channel = &mut channels[index];
+            index += 1;
+            channel.encoding = match captures.get(1).map(|prefix| prefix.as_str()) {
+                Some("!") => Encoding::Explicit,
+                Some("'") => Encoding::FirstDifference,
+                Some("\"") => Encoding::SecondDifference,
+                _ => channel.encoding,
+            };
+            let number = match &captures[2] {
+                "*" => continue,
+                "T" | "F" | "?" => f64::NAN,
+                number => number.parse::<f64>().unwrap(),
+            };
+            let previous = channel.value;
+            match channel.encoding {
+                Encoding::Explicit => channel.value = number,
+                Encoding::FirstDifference => channel.value += number,
+                Encoding::SecondDifference => channel.value += channel.velocity + number,
+            }
+            channel.velocity = channel.value - previous;
+        }
+        if !point[end..].trim().is_empty() || index <= x.max(y) {
+            return Err(format!("invalid point {:?}", point));
+        }
+        stroke.point(channels[x].value, channels[y].value);
+    }
+    Ok(stroke)
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod inkml_tests {
+    use super::super::error::{InkMLError, StrokeError};
+    use super::StrokeSet;
+
+    #[test]
+    fn parse_traces() {
+        //{{{
+        let strokes = StrokeSet::from_inkml(
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+            <ink xmlns="http://www.w3.org/2003/InkML">
+              <trace>10 0 1, 9 14 2, 8 28 3</trace>
+              <traceGroup>
+                <annotation type="truth">x</annotation>
+                <trace>1.5 -2, 2.5e1 .5</trace>
+              </traceGroup>
+              <trace></trace>
+            </ink>"#,
+        )
+        .unwrap();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes.strokes()[0].x, vec![10., 9., 8.]);
+        assert_eq!(strokes.strokes()[0].y, vec![0., 14., 28.]);
+        assert_eq!(strokes.strokes()[1].x, vec![1.5, 25.]);
+        assert_eq!(strokes.strokes()[1].y, vec![-2., 0.5]);
+    } //}}}
+
+    #[test]
+    fn parse_difference_encoding() {
+        //{{{
+        // Example of the InkML specification
+        let strokes = StrokeSet::from_inkml(
+            r#"<ink xmlns="http://www.w3.org/2003/InkML">
+              <trace>1125 18432,'23'43,"7"-8,3-5,!1130!18500,'2'3</trace>
+            </ink>"#,
+        )
+        .unwrap();
+        let stroke = &strokes.strokes()[0];
+        assert_eq!(stroke.x, vec![1125., 1148., 1178., 1211., 1130., 1132.]);
+        assert_eq!(
+            stroke.y,
+            vec![18432., 18475., 18510., 18540., 18500., 18503.]
+        );
+    } //}}}
+
+    #[test]
+    fn parse_channel_order() {
+        //{{{
+        let strokes = StrokeSet::from_inkml(
+            r##"<ink xmlns="http://www.w3.org/2003/InkML">
+              <definitions>
+                <traceFormat xml:id="tyx">
+                  <channel name="T" type="integer"/>
+                  <channel name="Y" type="decimal"/>
+                  <channel name="X" type="decimal"/>
+                </traceFormat>
+                <context xml:id="ctx" traceFormatRef="#tyx"/>
+                <trace>1 2 3</trace>
+              </definitions>
+              <traceFormat>
+                <channel name="Y"/>
+                <channel name="X"/>
+              </traceFormat>
+              <trace>1 2</trace>
+              <trace contextRef="#ctx">0 1 2, 10 3 4</trace>
+              <traceGroup contextRef="#tyx">
+                <trace>0 5 6</trace>
+              </traceGroup>
+            </ink>"##,
+        )
+        .unwrap();
+        let points: Vec<Vec<(f64, f64)>> = strokes
+            .strokes()
+            .iter()
+            .map(|stroke| stroke.points().collect())
+            .collect();
+        assert_eq!(
+            points,
+            vec![vec![(2., 1.)], vec![(2., 1.), (4., 3.)], vec![(6., 5.)]]
+        );
+    } //}}}
+
+    #[test]
+    fn parse_errors() {
+        //{{{
+        assert!(matches!(
+            StrokeSet::from_inkml("<ink><trace>1 2</ink>"),
+            Err(InkMLError::Xml(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml("<svg/>"),
+            Err(InkMLError::NotInkML(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml("<ink><trace>1 2, 3 x</trace></ink>"),
+            Err(InkMLError::InvalidTrace { trace: 0, .. })
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml("<ink><trace>1 2, 3</trace></ink>"),
+            Err(InkMLError::InvalidTrace { trace: 0, .. })
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml(r##"<ink><trace contextRef="#none">1 2</trace></ink>"##),
+            Err(InkMLError::UnknownReference(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml(
+                r##"<ink>
+                  <context xml:id="a" contextRef="#b"/>
+                  <context xml:id="b" contextRef="#a"/>
+                  <trace contextRef="#a">1 2</trace>
+                </ink>"##
+            ),
+            Err(InkMLError::CyclicReference(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml(
+                r#"<ink><context xml:id="self" contextRef="self"/><trace>1 2</trace></ink>"#
+            ),
+            Err(InkMLError::CyclicReference(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml(
+                r#"<ink><traceFormat><channel name="X"/></traceFormat><trace>1</trace></ink>"#
+            ),
+            Err(InkMLError::InvalidTrace { .. })
+        ));
+        assert!(matches!(
+            StrokeSet::from_inkml("<ink></ink>"),
+            Err(InkMLError::Stroke(StrokeError::NoStrokes))
+        ));
+    } //}}}
+}
+// }}}
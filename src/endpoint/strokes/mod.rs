@@ -1,24 +1,141 @@
 mod error;
+mod inkml;
 mod options;
+mod preprocess;
+mod render;
 mod response;
+mod session;
+mod stroke_set;
+mod svg;
+mod xopp;
 
 pub use super::shared_objects::request::{DataOptions, MetaData};
-use error::StrokesError;
+use super::{super::MATHPIX_APIURL, MathpixEndpoint};
+pub use error::{InkMLError, RenderError, StrokeError, StrokesError, SvgError, XoppError};
 pub use options::{StrokesFormats, StrokesOptions};
-use response::StrokesResponse;
+pub use preprocess::{PreprocessOptions, PreprocessReport, PreprocessStep, StepReport};
+pub use render::RenderOptions;
+use reqwest;
+pub use response::{Data, StrokesResponse};
 use serde::Serialize;
-use serde_json::Value as JsonValue;
+pub use session::{AppToken, StrokesSession};
+use std::convert::TryInto;
+pub use stroke_set::{Stroke, StrokeSet};
+pub use xopp::{read_xopp, XoppImage, XoppOptions, XoppPage};
 
 // Strokes {{{
 #[derive(Serialize, Debug)]
 /// This structs contains the possible items that the _strokes_ endpoint accepts
 pub struct Strokes {
-    // NOTE: on construction, `is_array(&self)` should be used to check whether it is an array.
-    // Also eltype should be Number (`is_number(&self)`)
     /// > Strokes in JSON with appropriate format.
-    pub src: JsonValue,
+    #[serde(rename = "strokes")]
+    pub src: StrokeSet,
     /// Configuration options for the _strokes_ endpoint
     #[serde(flatten)]
     pub options: StrokesOptions,
 }
 // }}}
+
+impl MathpixEndpoint for Strokes {
+    //{{{
+    type Src = StrokeSet;
+    type Error = StrokesError;
+    type Options = StrokesOptions;
+    type Response = StrokesResponse;
+
+    fn new<S, E>(options: Option<Self::Options>, src: S) -> Result<Self, Self::Error>
+    where
+        S: TryInto<StrokeSet, Error = E>,
+        Self::Error: From<E>,
+        Self: Sized,
+    {
+        Ok(Self {
+            src: src.try_into()?,
+            options: options.unwrap_or_default(),
+        })
+    }
+
+    fn url(&self) -> reqwest::Url {
+        let mut url_str = MATHPIX_APIURL.to_string();
+        url_str.push_str("strokes");
+        reqwest::Url::parse(&url_str).unwrap()
+    }
+
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error> {
+        Ok(reqwest::Client::new().post(self.url()).json(self))
+    }
+
+    fn options(&mut self) -> &mut Self::Options {
+        &mut self.options
+    }
+
+    fn src(&mut self) -> Option<&mut Self::Src> {
+        Some(&mut self.src)
+    }
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+mod strokes_endpoint_tests {
+    use super::super::test_server::{Reply, TestServer};
+    use super::{MathpixEndpoint, Stroke, StrokeSet, Strokes, StrokesFormats, StrokesOptions};
+    use crate::header::AuthHeader;
+    use serde_json::{json, Value};
+
+    fn strokes() -> StrokeSet {
+        StrokeSet::new(vec![
+            Stroke::new(vec![131., 131.], vec![213., 212.]),
+            Stroke::new(vec![87.], vec![231.]),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn serialize_strokes_body() {
+        //{{{
+        let mut options = StrokesOptions::default();
+        options.formats(vec![StrokesFormats::Text]);
+        let body = Strokes::new(Some(options), strokes()).unwrap();
+        let serialized = serde_json::to_value(&body).unwrap();
+        assert_eq!(
+            serialized["strokes"],
+            json!({"strokes": {"x": [[131.0, 131.0], [87.0]], "y": [[213.0, 212.0], [231.0]]}})
+        );
+        assert_eq!(serialized["formats"], json!(["text"]));
+        assert!(serialized.get("src").is_none());
+    } //}}}
+
+    #[tokio::test]
+    async fn send_strokes() {
+        //{{{
+        let server = TestServer::spawn(|request| {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            assert_eq!(body["strokes"]["strokes"]["x"][1], json!([87.0]));
+            Reply::json(json!({
+                "request_id": "2021_09_07_abc",
+                "is_handwritten": true,
+                "is_printed": false,
+                "text": "\\( x^{2} \\)",
+                "confidence": 0.9,
+                "confidence_rate": 0.95
+            }))
+        })
+        .await;
+        let body = Strokes::new(None, strokes()).unwrap();
+        let mut request = body.to_request(AuthHeader::new("id", "key")).unwrap();
+        *request.url_mut() = server.url.join("strokes").unwrap();
+        let response: super::StrokesResponse = reqwest::Client::new()
+            .execute(request)
+            .await
+            .unwrap()
+            .json()
+            .await
+            .unwrap();
+        assert_eq!(server.requests()[0].path, "/strokes");
+        assert_eq!(response.request_id, "2021_09_07_abc");
+        assert_eq!(response.is_handwritten, Some(true));
+        assert_eq!(response.text.as_deref(), Some("\\( x^{2} \\)"));
+        assert!(response.error.is_none());
+    } //}}}
+}
+// }}}
@@ -2,7 +2,7 @@ pub use super::super::shared_objects::request::{DataOptions, MetaData};
 use serde::Serialize;
 
 // StrokesOptions {{{
-#[derive(Serialize, Debug, Default)]
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
 pub struct StrokesOptions {
     /// > Key value object
     pub metadata: Option<MetaData>,
@@ -12,11 +12,16 @@ pub struct StrokesOptions {
     pub data_options: Option<DataOptions>,
 }
 
+impl StrokesOptions {
+    field_builder![metadata, MetaData];
+    field_builder![formats, Vec<StrokesFormats>];
+    field_builder![data_options, DataOptions];
+}
 //}}}
 
 // StrokesFormats {{{
 /// Format specifications possible for the _strokes_ endpoint
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, PartialEq, Clone)]
 #[serde(rename_all = "snake_case")]
 pub enum StrokesFormats {
     /// > Mathpix markdown formatted text
@@ -0,0 +1,483 @@
+use super::error::StrokeError;
+use super::stroke_set::{Stroke, StrokeSet};
+use std::fmt;
+
+type Point = (f64, f64);
+
+/// Most points that a stroke can be resampled to
+const MAX_RESAMPLED_POINTS: usize = 100_000;
+
+// PreprocessOptions {{{
+/**
+Preprocessing of the strokes before they are sent. The steps that are set are applied in the order
+- `normalize`: translate the strokes to the origin and scale them so that the larger side of their
+  bounding box is `normalize` long,
+- `dedup`: remove the points that are at most `dedup` away from the previous point of the stroke
+  (`0` removes only the repeated points),
+- `simplify`: [Ramer-Douglas-Peucker](https://en.wikipedia.org/wiki/Ramer%E2%80%93Douglas%E2%80%93Peucker_algorithm)
+  simplification that keeps the strokes at most `simplify` away from the original ones,
+- `resample`: place the points uniformly along the strokes `resample` apart. A spacing that would
+  place more than 100000 points on a stroke is rejected.
+
+The distances are in the coordinates after the normalization when it is set.
+*/
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct PreprocessOptions {
+    pub normalize: Option<f64>,
+    pub dedup: Option<f64>,
+    pub simplify: Option<f64>,
+    pub resample: Option<f64>,
+}
+
+impl PreprocessOptions {
+    field_builder![normalize, f64];
+    field_builder![dedup, f64];
+    field_builder![simplify, f64];
+    field_builder![resample, f64];
+
+    /// None of the steps is set
+    pub fn is_empty(&self) -> bool {
+        self == &PreprocessOptions::default()
+    }
+
+    fn validate(&self) -> Result<(), StrokeError> {
+        //{{{
+        let steps = [
+            (PreprocessStep::Normalize, self.normalize, false),
+            (PreprocessStep::Dedup, self.dedup, true),
+            (PreprocessStep::Simplify, self.simplify, true),
+            (PreprocessStep::Resample, self.resample, false),
+        ];
+        for (step, value, zero_allowed) in steps.iter().copied() {
+            if let Some(value) = value {
+                if !value.is_finite() || value < 0. || (value == 0. && !zero_allowed) {
+                    return Err(StrokeError::InvalidTolerance { step, value });
+                }
+            }
+        }
+        Ok(())
+    } //}}}
+}
+// }}}
+
+// PreprocessReport {{{
+/// A step of the preprocessing
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum PreprocessStep {
+    Normalize,
+    Dedup,
+    Simplify,
+    Resample,
+}
+
+impl fmt::Display for PreprocessStep {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            PreprocessStep::Normalize => "normalize",
+            PreprocessStep::Dedup => "dedup",
+            PreprocessStep::Simplify => "simplify",
+            PreprocessStep::Resample => "resample",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+/// Number of the points before and after a step of the preprocessing
+#[derive(Debug, PartialEq, Clone)]
+pub struct StepReport {
+    pub step: PreprocessStep,
+    pub points_before: usize,
+    pub points_after: usize,
+}
+
+/// How much the preprocessing reduced the strokes
+#[derive(Debug, PartialEq, Clone)]
+pub struct PreprocessReport {
+    /// The steps in the order in which they were applied
+    pub steps: Vec<StepReport>,
+    /// Size of the serialized strokes before the preprocessing
+    pub bytes_before: usize,
+    /// Size of the serialized strokes after the preprocessing
+    pub bytes_after: usize,
+}
+
+impl PreprocessReport {
+    pub fn points_before(&self) -> Option<usize> {
+        self.steps.first().map(|step| step.points_before)
+    }
+
+    pub fn points_after(&self) -> Option<usize> {
+        self.steps.last().map(|step| step.points_after)
+    }
+
+    /// Fraction of the size of the serialized strokes that was saved (e.g. `0.8` when the strokes
+    /// are five times smaller)
+    pub fn reduction(&self) -> f64 {
+        if self.bytes_before == 0 {
+            0.
+        } else {
+            1. - self.bytes_after as f64 / self.bytes_before as f64
+        }
+    }
+}
+
+impl fmt::Display for PreprocessReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for step in &self.steps {
+            write!(
+                f,
+                "{}: {} -> {} points, ",
+                step.step, step.points_before, step.points_after
+            )?;
+        }
+        write!(
+            f,
+            "{} -> {} bytes ({:.1} % smaller)",
+            self.bytes_before,
+            self.bytes_after,
+            self.reduction() * 100.
+        )
+    }
+}
+// }}}
+
+impl StrokeSet {
+    /**
+    Preprocess the strokes with the steps that are set in the `options` and report how much they
+    were reduced. The strokes stay valid, every step keeps at least one point of each stroke. The
+    strokes are left as they are when a step fails.
+
+    ```
+    use mathpixapi::endpoint::strokes::{PreprocessOptions, Stroke, StrokeSet};
+
+    let mut strokes = StrokeSet::new(vec![Stroke::new(
+        (0..=100).map(f64::from).collect(),
+        (0..=100).map(|x| if x % 2 == 0 { 0. } else { 0.01 }).collect(),
+    )])
+    .unwrap();
+    let mut options = PreprocessOptions::default();
+    options.simplify(0.1);
+    let report = strokes.preprocess(&options).unwrap();
+    assert_eq!(strokes.strokes()[0].x, vec![0., 100.]);
+    assert_eq!(report.points_after(), Some(2));
+    assert!(report.reduction() > 0.9);
+    ```
+    */
+    pub fn preprocess(
+        &mut self,
+        options: &PreprocessOptions,
+    ) -> Result<PreprocessReport, StrokeError> {
+        //{{{
+        options.validate()?;
+        let bytes_before = self.serialized_size();
+        let mut strokes = self.strokes.clone();
+        let mut steps = Vec::new();
+        let mut apply_step =
+            |strokes: &mut Vec<Stroke>, step, apply: &dyn Fn(&[Point]) -> Vec<Point>| {
+                let points_before = strokes.iter().map(Stroke::len).sum();
+                for stroke in strokes.iter_mut() {
+                    let points: Vec<Point> = stroke.points().collect();
+                    *stroke = apply(&points).into_iter().collect();
+                }
+                steps.push(StepReport {
+                    step,
+                    points_before,
+                    points_after: strokes.iter().map(Stroke::len).sum(),
+                });
+            };
+        if let Some(size) = options.normalize {
+            let normalize = normalization(&strokes, size);
+            apply_step(&mut strokes, PreprocessStep::Normalize, &|points| {
+                points.iter().map(|&point| normalize(point)).collect()
+            });
+        }
+        if let Some(tolerance) = options.dedup {
+            apply_step(&mut strokes, PreprocessStep::Dedup, &|points| {
+                dedup(points, tolerance)
+            });
+        }
+        if let Some(tolerance) = options.simplify {
+            apply_step(&mut strokes, PreprocessStep::Simplify, &|points| {
+                simplify(points, tolerance)
+            });
+        }
+        if let Some(spacing) = options.resample {
+            for (index, stroke) in strokes.iter().enumerate() {
+                let points: Vec<Point> = stroke.points().collect();
+                let length: f64 = points.windows(2).map(|s| distance(s[0], s[1])).sum();
+                // NOTE: The first and the last point come on top of the evenly placed ones
+                if length / spacing + 2. > MAX_RESAMPLED_POINTS as f64 {
+                    return Err(StrokeError::TooManyPoints {
+                        stroke: index,
+                        spacing,
+                        max: MAX_RESAMPLED_POINTS,
+                    });
+                }
+            }
+            apply_step(&mut strokes, PreprocessStep::Resample, &|points| {
+                resample(points, spacing)
+            });
+        }
+        self.strokes = strokes;
+        Ok(PreprocessReport {
+            steps,
+            bytes_before,
+            bytes_after: self.serialized_size(),
+        })
+    } //}}}
+
+    fn serialized_size(&self) -> usize {
+        serde_json::to_vec(self).map_or(0, |serialized| serialized.len())
+    }
+}
+
+/// Mapping of the points to the origin with the larger side of the bounding box `size` long
+fn normalization(strokes: &[Stroke], size: f64) -> impl Fn(Point) -> Point {
+    //{{{
+    let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+    let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+    for (x, y) in strokes.iter().flat_map(Stroke::points) {
+        min_x = min_x.min(x);
+        min_y = min_y.min(y);
+        max_x = max_x.max(x);
+        max_y = max_y.max(y);
+    }
+    let side = (max_x - min_x).max(max_y - min_y);
+    // NOTE: A single point (or the same point repeated) is only moved to the origin
+    let scale = if side > 0. { size / side } else { 1. };
+    move |(x, y)| ((x - min_x) * scale, (y - min_y) * scale)
+} //}}}
+
+/// The points without those that are at most `tolerance` away from the previous kept point. The
+/// last point is always kept, so that the stroke ends where it ended.
+fn dedup(points: &[Point], tolerance: f64) -> Vec<Point> {
+    //{{{
+    let mut kept: Vec<Point> = Vec::with_capacity(points.len());
+    for (index, &point) in points.iter().enumerate() {
+        match kept.last() {
+            Some(&last) if distance(last, point) <= tolerance => {
+                if index + 1 == points.len() && kept.len() > 1 && last != point {
+                    *kept.last_mut().unwrap() = point;
+                }
+            }
+            _ => kept.push(point),
+        }
+    }
+    kept
+} //}}}
+
+/// Ramer-Douglas-Peucker simplification of the points
+fn simplify(points: &[Point], tolerance: f64) -> Vec<Point> {
+    //{{{
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    // NOTE: An explicit stack instead of recursion so that long strokes do not overflow it
+    let mut ranges = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = ranges.pop() {
+        let farthest = (first + 1..last)
+            .map(|index| {
+                (
+                    index,
+                    distance_to_segment(points[index], points[first], points[last]),
+                )
+            })
+            .fold(
+                None,
+                |farthest: Option<(usize, f64)>, (index, distance)| match farthest {
+                    Some((_, max)) if max >= distance => farthest,
+                    _ => Some((index, distance)),
+                },
+            );
+        if let Some((index, distance)) = farthest {
+            if distance > tolerance {
+                keep[index] = true;
+                ranges.push((first, index));
+                ranges.push((index, last));
+            }
+        }
+    }
+    points
+        .iter()
+        .zip(keep)
+        .filter_map(|(&point, keep)| if keep { Some(point) } else { None })
+        .collect()
+} //}}}
+
+/// Points placed `spacing` apart along the polyline of the points (and its last point)
+fn resample(points: &[Point], spacing: f64) -> Vec<Point> {
+    //{{{
+    let mut resampled = vec![points[0]];
+    // NOTE: Distance along the polyline from the last placed point
+    let mut travelled = 0.;
+    for segment in points.windows(2) {
+        let (start, end) = (segment[0], segment[1]);
+        let length = distance(start, end);
+        let mut position = spacing - travelled;
+        while position <= length {
+            let t = position / length;
+            resampled.push((
+                start.0 + t * (end.0 - start.0),
+                start.1 + t * (end.1 - start.1),
+            ));
+            position += spacing;
+        }
+        travelled = length - (position - spacing);
+    }
+    let last = points[points.len() - 1];
+    // NOTE: The last point is usually placed already unless it is off due to rounding
+    if distance(*resampled.last().unwrap(), last) > spacing * 1e-9 {
+        resampled.push(last);
+    }
+    resampled
+} //}}}
+
+fn distance((ax, ay): Point, (bx, by): Point) -> f64 {
+    (ax - bx).hypot(ay - by)
+}
+
+fn distance_to_segment(point: Point, start: Point, end: Point) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx * dx + dy * dy;
+    if length == 0. {
+        return distance(point, start);
+    }
+    let t = (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length).clamp(0., 1.);
+    distance(point, (start.0 + t * dx, start.1 + t * dy))
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod preprocess_tests {
+    use super::{
+        dedup, resample, simplify, PreprocessOptions, PreprocessStep, MAX_RESAMPLED_POINTS,
+    };
+    use crate::endpoint::strokes::{Stroke, StrokeError, StrokeSet};
+
+    #[test]
+    fn dedup_points() {
+        //{{{
+        let points = vec![
+            (0., 0.),
+            (0., 0.),
+            (1., 0.),
+            (1.05, 0.),
+            (3., 0.),
+            (3.05, 0.),
+        ];
+        assert_eq!(
+            dedup(&points, 0.),
+            vec![(0., 0.), (1., 0.), (1.05, 0.), (3., 0.), (3.05, 0.)]
+        );
+        assert_eq!(dedup(&points, 0.1), vec![(0., 0.), (1., 0.), (3.05, 0.)]);
+        assert_eq!(dedup(&[(1., 1.), (1., 1.)], 0.), vec![(1., 1.)]);
+    } //}}}
+
+    #[test]
+    fn simplify_points() {
+        //{{{
+        let points = vec![
+            (0., 0.),
+            (1., 0.1),
+            (2., -0.1),
+            (3., 5.),
+            (4., 6.),
+            (5., 7.),
+        ];
+        assert_eq!(
+            simplify(&points, 0.5),
+            vec![(0., 0.), (2., -0.1), (3., 5.), (5., 7.)]
+        );
+        assert_eq!(simplify(&points, 100.), vec![(0., 0.), (5., 7.)]);
+        // The points on the lines are removed even without a tolerance
+        assert_eq!(simplify(&points, 0.).len(), 5);
+    } //}}}
+
+    #[test]
+    fn resample_points() {
+        //{{{
+        let resampled = resample(&[(0., 0.), (2.5, 0.), (2.5, 2.5)], 1.);
+        assert_eq!(
+            resampled,
+            vec![
+                (0., 0.),
+                (1., 0.),
+                (2., 0.),
+                (2.5, 0.5),
+                (2.5, 1.5),
+                (2.5, 2.5)
+            ]
+        );
+        assert_eq!(
+            resample(&[(0., 0.), (2., 0.)], 1.),
+            vec![(0., 0.), (1., 0.), (2., 0.)]
+        );
+        assert_eq!(resample(&[(1., 1.), (1., 1.)], 1.), vec![(1., 1.)]);
+        assert_eq!(resample(&[(1., 1.)], 1.), vec![(1., 1.)]);
+    } //}}}
+
+    #[test]
+    fn preprocess_strokes() {
+        //{{{
+        let mut strokes = StrokeSet::new(vec![
+            Stroke::new(vec![10., 10., 20., 30.], vec![10., 10., 10., 10.]),
+            Stroke::new(vec![10.], vec![30.]),
+        ])
+        .unwrap();
+        let mut options = PreprocessOptions::default();
+        assert!(options.is_empty());
+        options.normalize(2.).dedup(0.).simplify(0.01);
+        let report = strokes.preprocess(&options).unwrap();
+        assert_eq!(strokes.strokes()[0].x, vec![0., 2.]);
+        assert_eq!(strokes.strokes()[0].y, vec![0., 0.]);
+        assert_eq!(
+            strokes.strokes()[1].points().collect::<Vec<_>>(),
+            vec![(0., 2.)]
+        );
+        let steps: Vec<_> = report
+            .steps
+            .iter()
+            .map(|step| (step.step, step.points_before, step.points_after))
+            .collect();
+        assert_eq!(
+            steps,
+            vec![
+                (PreprocessStep::Normalize, 5, 5),
+                (PreprocessStep::Dedup, 5, 4),
+                (PreprocessStep::Simplify, 4, 3),
+            ]
+        );
+        assert!(report.bytes_after < report.bytes_before);
+        assert!(report.reduction() > 0.);
+        assert!(report.to_string().contains("simplify: 4 -> 3 points"));
+
+        options.resample(0.);
+        assert_eq!(
+            strokes.preprocess(&options),
+            Err(StrokeError::InvalidTolerance {
+                step: PreprocessStep::Resample,
+                value: 0.
+            })
+        );
+
+        // The stroke is 2 long after the normalization
+        let before = strokes.clone();
+        options.resample(2. / MAX_RESAMPLED_POINTS as f64);
+        assert_eq!(
+            strokes.preprocess(&options),
+            Err(StrokeError::TooManyPoints {
+                stroke: 0,
+                spacing: 2. / MAX_RESAMPLED_POINTS as f64,
+                max: MAX_RESAMPLED_POINTS
+            })
+        );
+        assert_eq!(strokes, before);
+        options.resample(0.5);
+        let report = strokes.preprocess(&options).unwrap();
+        assert_eq!(report.points_after(), Some(6));
+    } //}}}
+}
+// }}}
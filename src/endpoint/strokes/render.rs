@@ -0,0 +1,310 @@
+use super::error::RenderError;
+use super::stroke_set::StrokeSet;
+use crate::endpoint::shared_objects::request::{Base64Image, ImageSrc};
+use mime::IMAGE_PNG;
+
+/// Maximal width and height of the rendered image in pixels
+const MAX_DIMENSION: u32 = 8192;
+
+type Point = (f64, f64);
+
+// RenderOptions {{{
+/// How the strokes are drawn to an image
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenderOptions {
+    /// Width of the lines in pixels
+    pub line_width: f64,
+    /// Empty space around the strokes in pixels
+    pub padding: u32,
+    /// Pixels per a unit of the coordinates of the strokes
+    pub scale: f64,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions {
+            line_width: 3.,
+            padding: 10,
+            scale: 1.,
+        }
+    }
+}
+
+impl RenderOptions {
+    pub fn line_width(&mut self, val: f64) -> &mut Self {
+        self.line_width = val;
+        self
+    }
+
+    pub fn padding(&mut self, val: u32) -> &mut Self {
+        self.padding = val;
+        self
+    }
+
+    pub fn scale(&mut self, val: f64) -> &mut Self {
+        self.scale = val;
+        self
+    }
+}
+// }}}
+
+impl StrokeSet {
+    /**
+    Draw the strokes as black anti-aliased lines on a white background and encode the drawing as a
+    grayscale PNG. The drawing is moved so that the strokes start `padding` pixels from the edges
+    of the image. When the strokes do not fit into an image of 8192x8192 pixels at the `scale`,
+    they are drawn at the largest scale at which they fit.
+    */
+    pub fn render(&self, options: &RenderOptions) -> Result<Vec<u8>, RenderError> {
+        //{{{
+        if !(options.line_width.is_finite() && options.line_width > 0.) {
+            return Err(RenderError::InvalidOption(format!(
+                "the line width has to be positive but it is {}",
+                options.line_width
+            )));
+        }
+        if !(options.scale.is_finite() && options.scale > 0.) {
+            return Err(RenderError::InvalidOption(format!(
+                "the scale has to be positive but it is {}",
+                options.scale
+            )));
+        }
+        let points = self.strokes().iter().flat_map(|stroke| stroke.points());
+        let (mut min_x, mut min_y) = (f64::INFINITY, f64::INFINITY);
+        let (mut max_x, mut max_y) = (f64::NEG_INFINITY, f64::NEG_INFINITY);
+        for (x, y) in points {
+            min_x = min_x.min(x);
+            min_y = min_y.min(y);
+            max_x = max_x.max(x);
+            max_y = max_y.max(y);
+        }
+        let offset = f64::from(options.padding) + options.line_width / 2.;
+        // NOTE: Only the padding and the line width can make the image too large
+        let available = f64::from(MAX_DIMENSION) - 2. * offset;
+        if available < 1. {
+            let side = (2. * offset).ceil() as u64;
+            return Err(RenderError::TooLarge {
+                width: side,
+                height: side,
+            });
+        }
+        let extent = (max_x - min_x).max(max_y - min_y);
+        let scale = if extent * options.scale > available {
+            log::warn!(
+                "The strokes are drawn at the scale {} instead of {} to fit into the image",
+                available / extent,
+                options.scale
+            );
+            available / extent
+        } else {
+            options.scale
+        };
+        let dimension = |extent: f64| {
+            (extent * scale + 2. * offset)
+                .ceil()
+                .clamp(1., f64::from(MAX_DIMENSION))
+        };
+        let (width, height) = (dimension(max_x - min_x), dimension(max_y - min_y));
+        let mut canvas = Canvas::new(width as u32, height as u32, options.line_width / 2.);
+
+        let pixel = |(x, y): Point| ((x - min_x) * scale + offset, (y - min_y) * scale + offset);
+        for stroke in self.strokes() {
+            let mut points = stroke.points().map(pixel);
+            let mut previous = points.next().unwrap();
+            // NOTE: A stroke with a single point is drawn as a dot
+            canvas.segment(previous, previous);
+            for point in points {
+                canvas.segment(previous, point);
+                previous = point;
+            }
+        }
+        canvas.encode()
+    } //}}}
+
+    /**
+    The rendered strokes (see [StrokeSet::render]) as an image for the _text_ endpoint. It can be
+    used when the _strokes_ endpoint rejects the strokes or does not recognize them well.
+
+    ```no_run
+    # async fn run(strokes: mathpixapi::endpoint::strokes::StrokeSet) -> anyhow::Result<()> {
+    use mathpixapi::endpoint::strokes::{RenderOptions, Strokes};
+    use mathpixapi::endpoint::text::Text;
+    use mathpixapi::endpoint::MathpixEndpoint;
+    use mathpixapi::header::AuthHeader;
+
+    let header = AuthHeader::new("ID", "KEY");
+    let image = strokes.to_image_src(&RenderOptions::default())?;
+    let mut text = Strokes::new(None, strokes)?
+        .send_request(header.clone())
+        .await?
+        .text;
+    if text.is_none() {
+        text = Text::new(None, image)?.send_request(header).await?.text;
+    }
+    # Ok(())
+    # }
+    ```
+    */
+    pub fn to_image_src(&self, options: &RenderOptions) -> Result<ImageSrc, RenderError> {
+        let png = self.render(options)?;
+        Ok(ImageSrc::Image(Base64Image::from_bytes(
+            "strokes.png",
+            IMAGE_PNG,
+            &png,
+        )))
+    }
+}
+
+// Canvas {{{
+/// Grayscale pixels that the lines are drawn to
+struct Canvas {
+    width: u32,
+    height: u32,
+    radius: f64,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: u32, height: u32, radius: f64) -> Self {
+        Canvas {
+            width,
+            height,
+            radius,
+            pixels: vec![u8::MAX; width as usize * height as usize],
+        }
+    }
+
+    /// Draw the line from `start` to `end` with round caps. The pixels are covered by the line as
+    /// far as their centres are from its edge (up to half a pixel).
+    fn segment(&mut self, start: Point, end: Point) {
+        //{{{
+        let reach = self.radius + 0.5;
+        let range = |from: f64, to: f64, size: u32| {
+            let first = (from.min(to) - reach).floor().max(0.) as u32;
+            let last = ((from.max(to) + reach).ceil().max(0.) as u32).min(size);
+            first..last
+        };
+        for row in range(start.1, end.1, self.height) {
+            for column in range(start.0, end.0, self.width) {
+                let center = (f64::from(column) + 0.5, f64::from(row) + 0.5);
+                let coverage = (reach - distance_to_segment(center, start, end)).clamp(0., 1.);
+                let value = (f64::from(u8::MAX) * (1. - coverage)).round() as u8;
+                let pixel = &mut self.pixels[(row * self.width + column) as usize];
+                *pixel = (*pixel).min(value);
+            }
+        }
+    } //}}}
+
+    fn encode(&self) -> Result<Vec<u8>, RenderError> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, self.width, self.height);
+        encoder.set_color(png::ColorType::Grayscale);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(png)
+    }
+}
+// }}}
+
+fn distance_to_segment(point: Point, start: Point, end: Point) -> f64 {
+    let (dx, dy) = (end.0 - start.0, end.1 - start.1);
+    let length = dx * dx + dy * dy;
+    let t = if length == 0. {
+        0.
+    } else {
+        (((point.0 - start.0) * dx + (point.1 - start.1) * dy) / length).clamp(0., 1.)
+    };
+    (point.0 - start.0 - t * dx).hypot(point.1 - start.1 - t * dy)
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod render_tests {
+    use super::{RenderError, RenderOptions, MAX_DIMENSION};
+    use crate::endpoint::shared_objects::request::ImageSrc;
+    use crate::endpoint::strokes::{Stroke, StrokeSet};
+
+    /// Decode the PNG into its dimensions and grayscale pixels
+    fn decode(png: &[u8]) -> (u32, u32, Vec<u8>) {
+        let decoder = png::Decoder::new(png);
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(info.color_type, png::ColorType::Grayscale);
+        pixels.truncate(info.buffer_size());
+        (info.width, info.height, pixels)
+    }
+
+    #[test]
+    fn render_strokes() {
+        //{{{
+        let strokes = StrokeSet::new(vec![
+            Stroke::new(vec![0., 20.], vec![0., 0.]),
+            Stroke::new(vec![10.], vec![10.]),
+        ])
+        .unwrap();
+        let mut options = RenderOptions::default();
+        options.line_width(2.).padding(4);
+        let (width, height, pixels) = decode(&strokes.render(&options).unwrap());
+        assert_eq!((width, height), (30, 20));
+        let pixel = |x: u32, y: u32| pixels[(y * width + x) as usize];
+        // The horizontal line covers the rows 4 and 5
+        assert_eq!(pixel(10, 4), 0);
+        assert_eq!(pixel(10, 5), 0);
+        assert_eq!(pixel(10, 6), u8::MAX);
+        assert_eq!(pixel(0, 0), u8::MAX);
+        // The dot at the corner of the pixels (15, 15) covers them partially
+        assert!(pixel(15, 15) > 0 && pixel(15, 15) < u8::MAX);
+        assert_eq!(pixel(14, 14), pixel(15, 15));
+
+        options.scale(2.);
+        let (width, height, _) = decode(&strokes.render(&options).unwrap());
+        assert_eq!((width, height), (50, 30));
+
+        // The strokes are scaled down to fit into the image
+        let strokes = StrokeSet::new(vec![Stroke::new(vec![0., 1e6], vec![0., 5e5])]).unwrap();
+        let (width, height, _) = decode(&strokes.render(&RenderOptions::default()).unwrap());
+        assert_eq!((width, height), (MAX_DIMENSION, 4108));
+    } //}}}
+
+    #[test]
+    fn render_errors() {
+        //{{{
+        let strokes = StrokeSet::new(vec![Stroke::new(vec![0., 1e6], vec![0., 0.])]).unwrap();
+        let mut options = RenderOptions::default();
+        options.padding(5000);
+        assert!(matches!(
+            strokes.render(&options),
+            Err(RenderError::TooLarge { .. })
+        ));
+        options.padding(10);
+        options.scale(0.);
+        assert!(matches!(
+            strokes.render(&options),
+            Err(RenderError::InvalidOption(_))
+        ));
+        options.scale(0.001).line_width(f64::NAN);
+        assert!(matches!(
+            strokes.render(&options),
+            Err(RenderError::InvalidOption(_))
+        ));
+    } //}}}
+
+    #[test]
+    fn strokes_image_src() {
+        //{{{
+        let strokes = StrokeSet::new(vec![Stroke::new(vec![0., 5.], vec![0., 5.])]).unwrap();
+        let src = strokes.to_image_src(&RenderOptions::default()).unwrap();
+        assert_eq!(src.file_name().as_deref(), Some("strokes.png"));
+        match src {
+            ImageSrc::Image(image) => {
+                assert!(image
+                    .encode()
+                    .unwrap()
+                    .starts_with("data:image/png;base64,iVBORw0KGgo"));
+            }
+            ImageSrc::Url(_) => panic!("the strokes are rendered to an image"),
+        }
+    } //}}}
+}
+// }}}
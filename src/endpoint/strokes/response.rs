@@ -1,11 +1,15 @@
-pub use super::super::shared_objects::response::Data;
+pub use super::super::shared_objects::response::{Data, ErrorInfo};
 use serde::Deserialize;
 
-// pub struct ResponseStrokes {{{
+// pub struct StrokesResponse {{{
 #[derive(Debug, Deserialize)]
 pub struct StrokesResponse {
+    /// Request ID, for debugging purposes
+    pub request_id: String,
     /// Recognized `text` format, if such is found
     pub text: Option<String>,
+    /// Math Latex string of math equation, if the strokes are of a single equation
+    pub latex_styled: Option<String>,
     /// Estimated probability 100% correct
     pub confidence: Option<f32>,
     /// Estimated confidence of input quality
@@ -14,5 +18,16 @@ pub struct StrokesResponse {
     pub data: Option<Vec<Data>>,
     /// Annotated HTML output
     pub html: Option<String>,
-}
-// }}}
+    /// Specifies if handwritten content was detected in the strokes
+    pub is_handwritten: Option<bool>,
+    /// Specifies if printed content was detected in the strokes
+    pub is_printed: Option<bool>,
+    /// Estimated probability that the strokes need to be rotated
+    pub auto_rotate_confidence: Option<f32>,
+    /// Estimated angle of rotation in degrees to put the strokes in correct orientation
+    pub auto_rotate_degrees: Option<i16>,
+    /// US locale error message
+    pub error: Option<String>,
+    /// Error info object
+    pub error_info: Option<ErrorInfo>,
+} // }}}
@@ -0,0 +1,380 @@
+use super::error::StrokesError;
+use super::options::StrokesOptions;
+use super::response::StrokesResponse;
+use super::stroke_set::{Stroke, StrokeSet};
+use crate::header::AuthHeader;
+use crate::MATHPIX_APIURL;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::{StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Lifetime of the app tokens that the server uses when it is not requested
+const DEFAULT_EXPIRES: Duration = Duration::from_secs(300);
+/// Limits of the lifetime of the app tokens
+const MIN_EXPIRES: Duration = Duration::from_secs(30);
+const MAX_EXPIRES: Duration = Duration::from_secs(43200);
+/// A token that expires sooner than this is renewed before it is used
+const EXPIRY_MARGIN: Duration = Duration::from_secs(5);
+
+// AppToken {{{
+/**
+> App tokens are short lived tokens that can be used in client side code instead of the API
+> keys. They can also include a strokes session ID for live digital ink updates.
+*/
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct AppToken {
+    /// > App token to be used in headers of v3/text, v3/latex, or v3/strokes requests
+    pub app_token: String,
+    /// > ID of the strokes session (only when the session ID was requested)
+    pub strokes_session_id: Option<String>,
+    /// > Specifies the time the app token expires (milliseconds since the epoch)
+    pub app_token_expires_at: u64,
+}
+
+impl AppToken {
+    /// Time when the token expires
+    pub fn expires_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.app_token_expires_at)
+    }
+
+    /// The token expires in less than `margin` from now
+    fn expires_within(&self, margin: Duration) -> bool {
+        SystemTime::now() + margin >= self.expires_at()
+    }
+}
+
+#[derive(Serialize)]
+struct AppTokenBody {
+    include_strokes_session_id: bool,
+    expires: u64,
+}
+// }}}
+
+// StrokesSession {{{
+/**
+A live strokes session for recognizing the handwriting while it is being written. The strokes are
+added to the session as they are drawn and after every update the server recognizes all the strokes
+of the session. The updates of one session are billed as a single request.
+
+The session is started with an app token (obtained with the API keys) on the first update. When the
+token expires (or is about to), a new session is started and it receives all the strokes that were
+added so far, so the recognition continues seamlessly.
+
+```no_run
+# async fn run() -> Result<(), mathpixapi::endpoint::strokes::StrokesError> {
+use mathpixapi::endpoint::strokes::{Stroke, StrokesSession};
+use mathpixapi::header::AuthHeader;
+use std::time::Duration;
+
+let mut session =
+    StrokesSession::new(AuthHeader::new("ID", "KEY"), None).expires(Duration::from_secs(600));
+let response = session
+    .add(vec![Stroke::new(vec![131., 131., 130.], vec![213., 212., 211.])])
+    .await?;
+println!("{:?}", response.text);
+let response = session
+    .add(vec![Stroke::new(vec![87., 88.], vec![231., 232.])])
+    .await?;
+println!("{:?}", response.text);
+# Ok(())
+# }
+```
+*/
+#[derive(Debug, Clone)]
+pub struct StrokesSession {
+    header: AuthHeader,
+    options: StrokesOptions,
+    expires: Duration,
+    base_url: Url,
+    client: reqwest::Client,
+    token: Option<AppToken>,
+    strokes: Option<StrokeSet>,
+}
+
+impl StrokesSession {
+    /// Session authenticated by the API keys in the `header` and recognizing with the `options`
+    pub fn new<H: Into<AuthHeader>>(header: H, options: Option<StrokesOptions>) -> Self {
+        StrokesSession {
+            header: header.into(),
+            options: options.unwrap_or_default(),
+            expires: DEFAULT_EXPIRES,
+            base_url: Url::parse(MATHPIX_APIURL).unwrap(),
+            client: reqwest::Client::new(),
+            token: None,
+            strokes: None,
+        }
+    }
+
+    /// Lifetime of the app tokens of the sessions (between 30 seconds and 12 hours)
+    pub fn expires(mut self, expires: Duration) -> Self {
+        self.expires = expires.clamp(MIN_EXPIRES, MAX_EXPIRES);
+        self
+    }
+
+    /// Use a different server than the Mathpix API. The URL should end with a `/`.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    /// The app token of the current session (`None` before the first update)
+    pub fn token(&self) -> Option<&AppToken> {
+        self.token.as_ref()
+    }
+
+    /// ID of the current session (`None` before the first update)
+    pub fn session_id(&self) -> Option<&str> {
+        self.token
+            .as_ref()
+            .and_then(|token| token.strokes_session_id.as_deref())
+    }
+
+    /// All the strokes that were added to the session
+    pub fn strokes(&self) -> &[Stroke] {
+        self.strokes.as_ref().map_or(&[], StrokeSet::strokes)
+    }
+
+    /// Forget the strokes, e.g. when the writing area is cleared. The next update starts over
+    /// within the same session.
+    pub fn clear(&mut self) {
+        self.strokes = None;
+    }
+
+    /**
+    Start a new session with a new app token. It is done automatically by [StrokesSession::add]
+    when there is no session or its token expires.
+    */
+    pub async fn start(&mut self) -> Result<&AppToken, StrokesError> {
+        //{{{
+        let body = AppTokenBody {
+            include_strokes_session_id: true,
+            expires: self.expires.as_secs(),
+        };
+        let headers: HeaderMap = self.header.clone().into();
+        let token: AppToken = self
+            .client
+            .post(self.base_url.join("app-tokens").unwrap())
+            .headers(headers)
+            .json(&body)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        if token.strokes_session_id.is_none() {
+            return Err(StrokesError::Session(
+                "the app token does not include a strokes session ID".to_string(),
+            ));
+        }
+        Ok(self.token.insert(token))
+    } //}}}
+
+    /**
+    Add the `strokes` to the session and recognize all the strokes of the session. The strokes are
+    validated before they are added and nothing is added when any of them is invalid.
+    */
+    pub async fn add<I>(&mut self, strokes: I) -> Result<StrokesResponse, StrokesError>
+    where
+        I: IntoIterator<Item = Stroke>,
+    {
+        //{{{
+        let mut updated = self.strokes.clone();
+        for stroke in strokes {
+            match &mut updated {
+                Some(set) => {
+                    set.push(stroke)?;
+                }
+                None => updated = Some(StrokeSet::new(vec![stroke])?),
+            }
+        }
+        let updated = updated.ok_or(super::StrokeError::NoStrokes)?;
+        let response = self.send(&updated).await?;
+        self.strokes = Some(updated);
+        Ok(response)
+    } //}}}
+
+    /// Send the `strokes` in the session renewing it when the token is expired
+    async fn send(&mut self, strokes: &StrokeSet) -> Result<StrokesResponse, StrokesError> {
+        //{{{
+        let expired = self
+            .token
+            .as_ref()
+            .is_none_or(|token| token.expires_within(EXPIRY_MARGIN));
+        if expired {
+            self.start().await?;
+        }
+        let mut renewed = expired;
+        loop {
+            let token = self.token.as_ref().unwrap();
+            let body = SessionBody {
+                strokes,
+                strokes_session_id: token.strokes_session_id.as_deref().unwrap_or_default(),
+                options: &self.options,
+            };
+            let mut headers: HeaderMap = self.header.clone().into();
+            headers.remove("app_key");
+            headers.insert(
+                "app_token",
+                HeaderValue::from_str(&token.app_token).map_err(|_| {
+                    StrokesError::Session("the app token is not a header value".to_string())
+                })?,
+            );
+            let response = self
+                .client
+                .post(self.base_url.join("strokes").unwrap())
+                .headers(headers)
+                .json(&body)
+                .send()
+                .await?;
+            // NOTE: The token can be revoked or expire sooner than the server announced
+            if response.status() == StatusCode::UNAUTHORIZED && !renewed {
+                self.start().await?;
+                renewed = true;
+                continue;
+            }
+            return Ok(response.error_for_status()?.json().await?);
+        }
+    } //}}}
+} // }}}
+
+/// Body of the _strokes_ request in a session
+#[derive(Serialize)]
+struct SessionBody<'a> {
+    strokes: &'a StrokeSet,
+    strokes_session_id: &'a str,
+    #[serde(flatten)]
+    options: &'a StrokesOptions,
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod session_tests {
+    use super::{StrokesSession, MAX_EXPIRES};
+    use crate::endpoint::strokes::{Stroke, StrokeError, StrokesError};
+    use crate::endpoint::test_server::{Reply, TestServer};
+    use crate::header::AuthHeader;
+    use serde_json::{json, Value};
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    fn millis_from_now(seconds: i64) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as i64;
+        (now + seconds * 1000) as u64
+    }
+
+    /// Server issuing the sessions `session-1`, `session-2`, ... The first token is already expired
+    /// when `expired` is set.
+    async fn server(expired: bool, unauthorized: Option<u32>) -> TestServer {
+        let sessions = Arc::new(AtomicU32::new(0));
+        let updates = Arc::new(AtomicU32::new(0));
+        TestServer::spawn(move |request| {
+            let body: Value = serde_json::from_slice(&request.body).unwrap();
+            match request.path.as_str() {
+                "/app-tokens" => {
+                    assert_eq!(request.header("app_key"), Some("key"));
+                    assert_eq!(body["include_strokes_session_id"], json!(true));
+                    let session = sessions.fetch_add(1, Ordering::SeqCst) + 1;
+                    let seconds = if expired && session == 1 { -10 } else { 300 };
+                    Reply::json(json!({
+                        "app_token": format!("token-{}", session),
+                        "strokes_session_id": format!("session-{}", session),
+                        "app_token_expires_at": millis_from_now(seconds)
+                    }))
+                }
+                "/strokes" => {
+                    assert_eq!(request.header("app_key"), None);
+                    let update = updates.fetch_add(1, Ordering::SeqCst) + 1;
+                    if Some(update) == unauthorized {
+                        return Reply::status(401);
+                    }
+                    let session = body["strokes_session_id"].as_str().unwrap();
+                    assert_eq!(
+                        request.header("app_token"),
+                        Some(session.replace("session", "token").as_str())
+                    );
+                    let strokes = body["strokes"]["strokes"]["x"].as_array().unwrap().len();
+                    Reply::json(json!({
+                        "request_id": session,
+                        "text": format!("{} strokes", strokes)
+                    }))
+                }
+                path => panic!("unexpected request to {}", path),
+            }
+        })
+        .await
+    }
+
+    fn stroke(x: f64) -> Stroke {
+        Stroke::new(vec![x, x + 1.], vec![0., 1.])
+    }
+
+    #[tokio::test]
+    async fn incremental_updates() {
+        //{{{
+        let server = server(false, None).await;
+        let mut session = StrokesSession::new(AuthHeader::new("id", "key"), None)
+            .with_base_url(server.url.clone());
+        assert_eq!(session.session_id(), None);
+
+        let response = session.add(vec![stroke(0.)]).await.unwrap();
+        assert_eq!(response.text.as_deref(), Some("1 strokes"));
+        let response = session.add(vec![stroke(1.), stroke(2.)]).await.unwrap();
+        assert_eq!(response.text.as_deref(), Some("3 strokes"));
+        assert_eq!(session.session_id(), Some("session-1"));
+        assert_eq!(session.strokes().len(), 3);
+
+        // Invalid strokes are not added
+        let invalid = Stroke::new(vec![f64::NAN], vec![0.]);
+        assert!(matches!(
+            session.add(vec![stroke(3.), invalid]).await,
+            Err(StrokesError::Src(StrokeError::NonFiniteCoordinate { .. }))
+        ));
+        assert_eq!(session.strokes().len(), 3);
+
+        session.clear();
+        let response = session.add(vec![stroke(4.)]).await.unwrap();
+        assert_eq!(response.text.as_deref(), Some("1 strokes"));
+        let paths: Vec<_> = server.requests().into_iter().map(|r| r.path).collect();
+        assert_eq!(
+            paths.iter().filter(|path| *path == "/app-tokens").count(),
+            1
+        );
+    } //}}}
+
+    #[tokio::test]
+    async fn renew_expired_sessions() {
+        //{{{
+        let server = server(true, None).await;
+        let mut session = StrokesSession::new(AuthHeader::new("id", "key"), None)
+            .with_base_url(server.url.clone())
+            .expires(Duration::from_secs(100_000));
+        session.add(vec![stroke(0.)]).await.unwrap();
+        assert_eq!(session.session_id(), Some("session-1"));
+        // The first token is expired, so the second update starts a new session with all strokes
+        let response = session.add(vec![stroke(1.)]).await.unwrap();
+        assert_eq!(session.session_id(), Some("session-2"));
+        assert_eq!(response.request_id, "session-2");
+        assert_eq!(response.text.as_deref(), Some("2 strokes"));
+
+        let body: Value = serde_json::from_slice(&server.requests()[0].body).unwrap();
+        assert_eq!(body["expires"], json!(MAX_EXPIRES.as_secs()));
+    } //}}}
+
+    #[tokio::test]
+    async fn renew_revoked_sessions() {
+        //{{{
+        let server = server(false, Some(2)).await;
+        let mut session = StrokesSession::new(AuthHeader::new("id", "key"), None)
+            .with_base_url(server.url.clone());
+        session.add(vec![stroke(0.)]).await.unwrap();
+        let response = session.add(vec![stroke(1.)]).await.unwrap();
+        assert_eq!(session.session_id(), Some("session-2"));
+        assert_eq!(response.text.as_deref(), Some("2 strokes"));
+    } //}}}
+}
+// }}}
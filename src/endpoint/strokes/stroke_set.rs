@@ -0,0 +1,287 @@
+use super::error::StrokeError;
+use serde::{Deserialize, Serialize, Serializer};
+use std::convert::TryFrom;
+use std::iter::FromIterator;
+
+// Stroke {{{
+/// A single stroke of the pen as the `x` and `y` coordinates of its points in the drawing order
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Stroke {
+    pub x: Vec<f64>,
+    pub y: Vec<f64>,
+}
+
+impl Stroke {
+    pub fn new(x: Vec<f64>, y: Vec<f64>) -> Self {
+        Stroke { x, y }
+    }
+
+    /// Add the point (`x`, `y`) to the end of the stroke
+    pub fn point(&mut self, x: f64, y: f64) -> &mut Self {
+        self.x.push(x);
+        self.y.push(y);
+        self
+    }
+
+    /// Iterate over the points of the stroke as (x, y) pairs
+    pub fn points(&self) -> impl Iterator<Item = (f64, f64)> + '_ {
+        self.x.iter().copied().zip(self.y.iter().copied())
+    }
+
+    /// Number of the points of the stroke
+    pub fn len(&self) -> usize {
+        self.x.len().min(self.y.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Check that the stroke (with the index `index` in its set) has points with finite coordinates
+    fn validate(&self, index: usize) -> Result<(), StrokeError> {
+        //{{{
+        if self.x.len() != self.y.len() {
+            return Err(StrokeError::LengthMismatch {
+                stroke: index,
+                x: self.x.len(),
+                y: self.y.len(),
+            });
+        }
+        if self.x.is_empty() {
+            return Err(StrokeError::EmptyStroke(index));
+        }
+        let coordinates = self.x.iter().enumerate().chain(self.y.iter().enumerate());
+        for (point, &value) in coordinates {
+            if !value.is_finite() {
+                return Err(StrokeError::NonFiniteCoordinate {
+                    stroke: index,
+                    point,
+                    value,
+                });
+            }
+        }
+        Ok(())
+    } //}}}
+}
+
+impl FromIterator<(f64, f64)> for Stroke {
+    fn from_iter<I: IntoIterator<Item = (f64, f64)>>(points: I) -> Self {
+        let (x, y) = points.into_iter().unzip();
+        Stroke { x, y }
+    }
+} // }}}
+
+// StrokeSet {{{
+/**
+The handwriting that is sent to the _strokes_ endpoint. It consists of at least one stroke and every
+stroke has at least one point with finite coordinates. The strokes are validated when the set is
+created, so a `StrokeSet` is always valid.
+
+It is serialized to the `strokes` format of the endpoint:
+```json
+{"strokes": {"x": [[131, 131, 130], [87, 88]], "y": [[213, 212, 211], [231, 232]]}}
+```
+
+```
+use mathpixapi::endpoint::strokes::{Stroke, StrokeSet};
+
+let strokes = StrokeSet::new(vec![
+    Stroke::new(vec![131., 131., 130.], vec![213., 212., 211.]),
+    vec![(87., 231.), (88., 232.)].into_iter().collect(),
+])
+.unwrap();
+assert_eq!(strokes.len(), 2);
+assert!(StrokeSet::new(vec![Stroke::new(vec![1.], vec![])]).is_err());
+```
+*/
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(try_from = "WireStrokeSet")]
+pub struct StrokeSet {
+    pub(super) strokes: Vec<Stroke>,
+}
+
+impl StrokeSet {
+    /// Validate the `strokes` and create the set of them
+    pub fn new(strokes: Vec<Stroke>) -> Result<Self, StrokeError> {
+        if strokes.is_empty() {
+            return Err(StrokeError::NoStrokes);
+        }
+        for (index, stroke) in strokes.iter().enumerate() {
+            stroke.validate(index)?;
+        }
+        Ok(StrokeSet { strokes })
+    }
+
+    /// Validate the `stroke` and add it to the end of the set
+    pub fn push(&mut self, stroke: Stroke) -> Result<&mut Self, StrokeError> {
+        stroke.validate(self.strokes.len())?;
+        self.strokes.push(stroke);
+        Ok(self)
+    }
+
+    pub fn strokes(&self) -> &[Stroke] {
+        &self.strokes
+    }
+
+    pub fn into_strokes(self) -> Vec<Stroke> {
+        self.strokes
+    }
+
+    /// Number of the strokes
+    pub fn len(&self) -> usize {
+        self.strokes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.strokes.is_empty()
+    }
+}
+
+impl TryFrom<Vec<Stroke>> for StrokeSet {
+    type Error = StrokeError;
+    fn try_from(strokes: Vec<Stroke>) -> Result<Self, Self::Error> {
+        StrokeSet::new(strokes)
+    }
+}
+
+impl TryFrom<serde_json::Value> for StrokeSet {
+    type Error = serde_json::Error;
+    /// Parse the `strokes` format of the endpoint
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        serde_json::from_value(value)
+    }
+}
+
+impl Serialize for StrokeSet {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        #[derive(Serialize)]
+        struct Coordinates<'a> {
+            x: Vec<&'a [f64]>,
+            y: Vec<&'a [f64]>,
+        }
+        #[derive(Serialize)]
+        struct Wire<'a> {
+            strokes: Coordinates<'a>,
+        }
+        Wire {
+            strokes: Coordinates {
+                x: self.strokes.iter().map(|stroke| &stroke.x[..]).collect(),
+                y: self.strokes.iter().map(|stroke| &stroke.y[..]).collect(),
+            },
+        }
+        .serialize(serializer)
+    }
+}
+
+/// The `strokes` format of the endpoint
+#[derive(Deserialize)]
+struct WireStrokeSet {
+    strokes: WireCoordinates,
+}
+
+#[derive(Deserialize)]
+struct WireCoordinates {
+    x: Vec<Vec<f64>>,
+    y: Vec<Vec<f64>>,
+}
+
+impl TryFrom<WireStrokeSet> for StrokeSet {
+    type Error = StrokeError;
+    fn try_from(wire: WireStrokeSet) -> Result<Self, Self::Error> {
+        let WireCoordinates { x, y } = wire.strokes;
+        if x.len() != y.len() {
+            // NOTE: The missing strokes are reported as empty
+            let stroke = x.len().min(y.len());
+            return Err(StrokeError::LengthMismatch {
+                stroke,
+                x: x.get(stroke).map_or(0, Vec::len),
+                y: y.get(stroke).map_or(0, Vec::len),
+            });
+        }
+        StrokeSet::new(x.into_iter().zip(y).map(|(x, y)| Stroke { x, y }).collect())
+    }
+} // }}}
+
+// TESTS {{{
+#[cfg(test)]
+mod stroke_set_tests {
+    use super::{Stroke, StrokeError, StrokeSet};
+    use serde_json::json;
+    use std::convert::TryFrom;
+
+    #[test]
+    fn validate_strokes() {
+        //{{{
+        assert_eq!(StrokeSet::new(vec![]), Err(StrokeError::NoStrokes));
+        assert_eq!(
+            StrokeSet::new(vec![
+                Stroke::new(vec![1., 2.], vec![1., 2.]),
+                Stroke::default()
+            ]),
+            Err(StrokeError::EmptyStroke(1))
+        );
+        assert_eq!(
+            StrokeSet::new(vec![Stroke::new(vec![1., 2.], vec![1.])]),
+            Err(StrokeError::LengthMismatch {
+                stroke: 0,
+                x: 2,
+                y: 1
+            })
+        );
+        assert!(matches!(
+            StrokeSet::new(vec![Stroke::new(vec![1., 2.], vec![1., f64::NAN])]),
+            Err(StrokeError::NonFiniteCoordinate {
+                stroke: 0,
+                point: 1,
+                ..
+            })
+        ));
+
+        let mut strokes = StrokeSet::new(vec![Stroke::new(vec![1.], vec![2.])]).unwrap();
+        assert!(strokes
+            .push(Stroke::new(vec![f64::INFINITY], vec![0.]))
+            .is_err());
+        let mut stroke = Stroke::default();
+        stroke.point(3., 4.).point(5., 6.);
+        strokes.push(stroke).unwrap();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(
+            strokes.strokes()[1].points().collect::<Vec<_>>(),
+            vec![(3., 4.), (5., 6.)]
+        );
+    } //}}}
+
+    #[test]
+    fn serialize_strokes() {
+        //{{{
+        let strokes = StrokeSet::new(vec![
+            Stroke::new(vec![131., 131.5], vec![213., 212.]),
+            vec![(87., 231.)].into_iter().collect(),
+        ])
+        .unwrap();
+        let serialized = serde_json::to_value(&strokes).unwrap();
+        let expected = json!({"strokes": {
+            "x": [[131.0, 131.5], [87.0]],
+            "y": [[213.0, 212.0], [231.0]]
+        }});
+        assert_eq!(serialized, expected);
+        assert_eq!(StrokeSet::try_from(serialized).unwrap(), strokes);
+    } //}}}
+
+    #[test]
+    fn deserialize_invalid_strokes() {
+        //{{{
+        for value in &[
+            json!({"strokes": {"x": [[1, 2]], "y": [[1]]}}),
+            json!({"strokes": {"x": [[1]], "y": []}}),
+            json!({"strokes": {"x": [], "y": []}}),
+            json!({"strokes": {"x": [["a"]], "y": [[1]]}}),
+        ] {
+            assert!(StrokeSet::try_from(value.clone()).is_err());
+        }
+    } //}}}
+}
+// }}}
@@ -0,0 +1,727 @@
+use super::error::SvgError;
+use super::stroke_set::{Stroke, StrokeSet};
+use regex::Regex;
+use roxmltree::{Document, Node};
+use std::f64::consts::PI;
+use std::sync::OnceLock;
+
+/// Maximal depth of the subdivision of a curve when it is flattened
+const MAX_SUBDIVISION: usize = 16;
+
+/// Elements whose content is not drawn directly
+const NOT_RENDERED: &[&str] = &[
+    "defs", "clipPath", "mask", "marker", "pattern", "symbol", "metadata",
+];
+
+type Point = (f64, f64);
+
+impl StrokeSet {
+    /**
+    Convert the `path`, `polyline`, `polygon` and `line` elements of an SVG document into strokes.
+    Every subpath of a `path` is a stroke. The curves (Bézier curves and elliptical arcs) are
+    flattened into lines that are at most `tolerance` away from the curve and the `transform`s of
+    the elements and their groups are applied, so the coordinates of the strokes are in the
+    coordinate system of the root `svg` element. The elements in `defs` and similar elements that
+    are not rendered directly are skipped.
+
+    ```
+    use mathpixapi::endpoint::strokes::StrokeSet;
+
+    let strokes = StrokeSet::from_svg(r#"
+        <svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 100 100">
+          <g transform="translate(10 0)">
+            <path d="M 0 0 L 10 10 M 20 0 h 5 v 5"/>
+          </g>
+          <line x1="0" y1="50" x2="100" y2="50"/>
+        </svg>
+    "#, 0.5).unwrap();
+    assert_eq!(strokes.len(), 3);
+    assert_eq!(strokes.strokes()[1].x, vec![30., 35., 35.]);
+    assert_eq!(strokes.strokes()[2].y, vec![50., 50.]);
+    ```
+    */
+    pub fn from_svg(document: &str, tolerance: f64) -> Result<StrokeSet, SvgError> {
+        //{{{
+        if !(tolerance.is_finite() && tolerance > 0.) {
+            return Err(SvgError::InvalidTolerance(tolerance));
+        }
+        let document = Document::parse(document)?;
+        let root = document.root_element();
+        if root.tag_name().name() != "svg" {
+            return Err(SvgError::NotSvg(root.tag_name().name().to_string()));
+        }
+
+        let mut strokes = Vec::new();
+        for (index, node) in root
+            .descendants()
+            .filter(|node| {
+                matches!(
+                    node.tag_name().name(),
+                    "path" | "polyline" | "polygon" | "line"
+                )
+            })
+            .filter(|node| node.is_element() && is_rendered(*node))
+            .enumerate()
+        {
+            let invalid = |message: String| SvgError::InvalidElement {
+                element: index,
+                name: node.tag_name().name().to_string(),
+                message,
+            };
+            let matrix = transform(node).map_err(invalid)?;
+            let mut path = PathBuilder::new(matrix, tolerance);
+            match node.tag_name().name() {
+                "path" => parse_path(node.attribute("d").unwrap_or(""), &mut path),
+                "line" => parse_line(node, &mut path),
+                name => parse_points(
+                    node.attribute("points").unwrap_or(""),
+                    name == "polygon",
+                    &mut path,
+                ),
+            }
+            .map_err(invalid)?;
+            strokes.extend(path.finish());
+        }
+        Ok(StrokeSet::new(strokes)?)
+    } //}}}
+}
+
+/// The `node` is not inside of an element that is not rendered
+fn is_rendered(node: Node) -> bool {
+    !node
+        .ancestors()
+        .any(|node| NOT_RENDERED.contains(&node.tag_name().name()))
+}
+
+// Matrix {{{
+/// Affine transformation `(x, y) -> (a x + c y + e, b x + d y + f)` stored as `[a, b, c, d, e, f]`
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Matrix([f64; 6]);
+
+impl Matrix {
+    const IDENTITY: Matrix = Matrix([1., 0., 0., 1., 0., 0.]);
+
+    /// The transformation that applies `other` first and then `self`
+    fn then(self, other: Matrix) -> Matrix {
+        let [a, b, c, d, e, f] = self.0;
+        let [g, h, i, j, k, l] = other.0;
+        Matrix([
+            a * g + c * h,
+            b * g + d * h,
+            a * i + c * j,
+            b * i + d * j,
+            a * k + c * l + e,
+            b * k + d * l + f,
+        ])
+    }
+
+    fn apply(&self, (x, y): Point) -> Point {
+        let [a, b, c, d, e, f] = self.0;
+        (a * x + c * y + e, b * x + d * y + f)
+    }
+}
+// }}}
+
+/// The transformation of the `node` and all its ancestors to the coordinates of the document
+fn transform(node: Node) -> Result<Matrix, String> {
+    let mut matrix = Matrix::IDENTITY;
+    for node in node.ancestors().filter(Node::is_element) {
+        if let Some(transform) = node.attribute("transform") {
+            matrix = parse_transform(transform)?.then(matrix);
+        }
+    }
+    Ok(matrix)
+}
+
+/// Pattern of a transformation with its arguments in a `transform` attribute
+fn function_pattern() -> &'static Regex {
+    static FUNCTION: OnceLock<Regex> = OnceLock::new();
+    FUNCTION.get_or_init(|| Regex::new(r"^[\s,]*([a-zA-Z]+)\s*\(([^)]*)\)").unwrap())
+}
+
+/// Parse a `transform` attribute (a list of `matrix`, `translate`, `scale`, `rotate`, `skewX`
+/// and `skewY` transformations)
+fn parse_transform(transform: &str) -> Result<Matrix, String> {
+    //{{{
+    let function = function_pattern();
+    let mut matrix = Matrix::IDENTITY;
+    let mut rest = transform;
+    while !rest
+        .trim_matches(|c: char| c.is_whitespace() || c == ',')
+        .is_empty()
+    {
+        let captures = function
+            .captures(rest)
+            .ok_or_else(|| format!("invalid transform {:?}", transform))?;
+        let mut arguments = Numbers::new(&captures[2]);
+        let mut values = Vec::new();
+        while let Some(value) = arguments.number_opt()? {
+            values.push(value);
+        }
+        let invalid = || format!("invalid arguments of {:?}", &captures[0].trim());
+        let next = match (&captures[1], values.as_slice()) {
+            ("matrix", &[a, b, c, d, e, f]) => Matrix([a, b, c, d, e, f]),
+            ("translate", &[x]) => Matrix([1., 0., 0., 1., x, 0.]),
+            ("translate", &[x, y]) => Matrix([1., 0., 0., 1., x, y]),
+            ("scale", &[s]) => Matrix([s, 0., 0., s, 0., 0.]),
+            ("scale", &[x, y]) => Matrix([x, 0., 0., y, 0., 0.]),
+            ("rotate", &[angle]) => rotation(angle),
+            ("rotate", &[angle, x, y]) => Matrix([1., 0., 0., 1., x, y])
+                .then(rotation(angle))
+                .then(Matrix([1., 0., 0., 1., -x, -y])),
+            ("skewX", &[angle]) => Matrix([1., 0., angle.to_radians().tan(), 1., 0., 0.]),
+            ("skewY", &[angle]) => Matrix([1., angle.to_radians().tan(), 0., 1., 0., 0.]),
+            _ => return Err(invalid()),
+        };
+        matrix = matrix.then(next);
+        rest = &rest[captures[0].len()..];
+    }
+    Ok(matrix)
+} //}}}
+
+fn rotation(angle: f64) -> Matrix {
+    let (sin, cos) = angle.to_radians().sin_cos();
+    Matrix([cos, sin, -sin, cos, 0., 0.])
+}
+
+// Numbers {{{
+/// Reader of the numbers in the path data, in the `points` and in the transform arguments
+struct Numbers<'a> {
+    rest: &'a str,
+    number: &'static Regex,
+}
+
+impl<'a> Numbers<'a> {
+    fn new(text: &'a str) -> Self {
+        static NUMBER: OnceLock<Regex> = OnceLock::new();
+        Numbers {
+            rest: text,
+            number: NUMBER
+                .get_or_init(|| Regex::new(r"^[-+]?(?:\d+\.?\d*|\.\d+)(?:[eE][-+]?\d+)?").unwrap()),
+        }
+    }
+
+    fn skip_separators(&mut self) {
+        self.rest = self
+            .rest
+            .trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+    }
+
+    /// The next number or `None` when the next item is not a number
+    fn number_opt(&mut self) -> Result<Option<f64>, String> {
+        self.skip_separators();
+        match self.number.find(self.rest) {
+            Some(found) => {
+                self.rest = &self.rest[found.end()..];
+                found
+                    .as_str()
+                    .parse()
+                    .map(Some)
+                    .map_err(|_| format!("{:?} is not a number", found.as_str()))
+            }
+            None => Ok(None),
+        }
+    }
+
+    fn number(&mut self) -> Result<f64, String> {
+        self.number_opt()?
+            .ok_or_else(|| format!("expected a number at {:?}", self.excerpt()))
+    }
+
+    fn point(&mut self) -> Result<Point, String> {
+        Ok((self.number()?, self.number()?))
+    }
+
+    /// Arc flags can be written without separators (e.g. `a 5 5 0 01 10 0`)
+    fn flag(&mut self) -> Result<bool, String> {
+        self.skip_separators();
+        let flag = match self.rest.chars().next() {
+            Some('0') => false,
+            Some('1') => true,
+            _ => return Err(format!("expected a flag at {:?}", self.excerpt())),
+        };
+        self.rest = &self.rest[1..];
+        Ok(flag)
+    }
+
+    /// The next command letter of the path data
+    fn command(&mut self) -> Option<char> {
+        self.skip_separators();
+        let command = self.rest.chars().next().filter(char::is_ascii_alphabetic)?;
+        self.rest = &self.rest[1..];
+        Some(command)
+    }
+
+    fn at_number(&mut self) -> bool {
+        self.skip_separators();
+        self.number.is_match(self.rest)
+    }
+
+    fn is_empty(&mut self) -> bool {
+        self.skip_separators();
+        self.rest.is_empty()
+    }
+
+    fn excerpt(&self) -> String {
+        self.rest.chars().take(16).collect()
+    }
+}
+// }}}
+
+// PathBuilder {{{
+/// Collects the flattened and transformed subpaths as strokes
+struct PathBuilder {
+    matrix: Matrix,
+    tolerance: f64,
+    strokes: Vec<Stroke>,
+    current: Vec<Point>,
+}
+
+impl PathBuilder {
+    fn new(matrix: Matrix, tolerance: f64) -> Self {
+        PathBuilder {
+            matrix,
+            tolerance,
+            strokes: Vec::new(),
+            current: Vec::new(),
+        }
+    }
+
+    fn move_to(&mut self, point: Point) {
+        self.end_subpath();
+        self.current.push(self.matrix.apply(point));
+    }
+
+    fn line_to(&mut self, point: Point) {
+        self.current.push(self.matrix.apply(point));
+    }
+
+    fn cubic_to(&mut self, first: Point, second: Point, end: Point) {
+        let start = *self.current.last().unwrap();
+        let [first, second, end] = [first, second, end].map(|point| self.matrix.apply(point));
+        flatten_cubic(
+            [start, first, second, end],
+            self.tolerance,
+            MAX_SUBDIVISION,
+            &mut self.current,
+        );
+    }
+
+    /// A subpath with only the starting point is not drawn
+    fn end_subpath(&mut self) {
+        let points = std::mem::take(&mut self.current);
+        if points.len() > 1 {
+            self.strokes.push(points.into_iter().collect());
+        }
+    }
+
+    fn finish(mut self) -> Vec<Stroke> {
+        self.end_subpath();
+        self.strokes
+    }
+}
+// }}}
+
+/// Parse the path data (the `d` attribute) of a `path`
+fn parse_path(data: &str, path: &mut PathBuilder) -> Result<(), String> {
+    //{{{
+    let mut numbers = Numbers::new(data);
+    let mut current = (0., 0.);
+    let mut start = (0., 0.);
+    // NOTE: The control point of the previous curve that is reflected by `S` and `T`
+    let mut control: Option<(char, Point)> = None;
+    let mut command = match numbers.command() {
+        Some(command) if command == 'M' || command == 'm' => command,
+        _ if numbers.is_empty() => return Ok(()),
+        _ => return Err("the path data has to start with a moveto".to_string()),
+    };
+    loop {
+        let relative = command.is_ascii_lowercase();
+        let offset = |(x, y): Point| {
+            if relative {
+                (current.0 + x, current.1 + y)
+            } else {
+                (x, y)
+            }
+        };
+        let reflected = |kinds: &[char]| match control {
+            Some((kind, (x, y))) if kinds.contains(&kind) => {
+                (2. * current.0 - x, 2. * current.1 - y)
+            }
+            _ => current,
+        };
+        let mut next_control = None;
+        match command.to_ascii_uppercase() {
+            'M' => {
+                current = offset(numbers.point()?);
+                start = current;
+                path.move_to(current);
+                // NOTE: The following coordinate pairs are implicit lineto commands
+                command = if relative { 'l' } else { 'L' };
+            }
+            'L' => {
+                current = offset(numbers.point()?);
+                path.line_to(current);
+            }
+            'H' => {
+                let x = numbers.number()?;
+                current = (if relative { current.0 + x } else { x }, current.1);
+                path.line_to(current);
+            }
+            'V' => {
+                let y = numbers.number()?;
+                current = (current.0, if relative { current.1 + y } else { y });
+                path.line_to(current);
+            }
+            'C' | 'S' => {
+                let first = if command.eq_ignore_ascii_case(&'C') {
+                    offset(numbers.point()?)
+                } else {
+                    reflected(&['C', 'S'])
+                };
+                let second = offset(numbers.point()?);
+                let end = offset(numbers.point()?);
+                path.cubic_to(first, second, end);
+                next_control = Some(('C', second));
+                current = end;
+            }
+            'Q' | 'T' => {
+                let control = if command.eq_ignore_ascii_case(&'Q') {
+                    offset(numbers.point()?)
+                } else {
+                    reflected(&['Q'])
+                };
+                let end = offset(numbers.point()?);
+                // NOTE: Degree elevation of the quadratic curve to a cubic one
+                let elevate =
+                    |(x, y): Point| (x + 2. / 3. * (control.0 - x), y + 2. / 3. * (control.1 - y));
+                path.cubic_to(elevate(current), elevate(end), end);
+                next_control = Some(('Q', control));
+                current = end;
+            }
+            'A' => {
+                let radii = (numbers.number()?, numbers.number()?);
+                let rotation = numbers.number()?;
+                let large_arc = numbers.flag()?;
+                let sweep = numbers.flag()?;
+                let end = offset(numbers.point()?);
+                for [first, second, end] in
+                    arc_to_cubics(current, radii, rotation, large_arc, sweep, end)
+                {
+                    path.cubic_to(first, second, end);
+                }
+                current = end;
+            }
+            'Z' => {
+                if current != start {
+                    path.line_to(start);
+                }
+                current = start;
+                path.end_subpath();
+                // NOTE: A drawing command after closing starts a new subpath at its start
+                if !numbers.is_empty() && !numbers.at_number() {
+                    path.move_to(start);
+                }
+            }
+            _ => return Err(format!("unknown path command {:?}", command)),
+        }
+        control = next_control;
+        if numbers.at_number() && !command.eq_ignore_ascii_case(&'Z') {
+            continue;
+        }
+        match numbers.command() {
+            Some(next) => command = next,
+            None if numbers.is_empty() => return Ok(()),
+            None => return Err(format!("invalid path data at {:?}", numbers.excerpt())),
+        }
+    }
+} //}}}
+
+/// The `points` of a `polyline` (or a `polygon` when `closed`)
+fn parse_points(points: &str, closed: bool, path: &mut PathBuilder) -> Result<(), String> {
+    //{{{
+    let mut numbers = Numbers::new(points);
+    let first = match numbers.number_opt()? {
+        Some(x) => (x, numbers.number()?),
+        None if numbers.is_empty() => return Ok(()),
+        None => return Err(format!("invalid points at {:?}", numbers.excerpt())),
+    };
+    path.move_to(first);
+    while let Some(x) = numbers.number_opt()? {
+        path.line_to((x, numbers.number()?));
+    }
+    if !numbers.is_empty() {
+        return Err(format!("invalid points at {:?}", numbers.excerpt()));
+    }
+    if closed {
+        path.line_to(first);
+    }
+    Ok(())
+} //}}}
+
+/// The end points of a `line`
+fn parse_line(node: Node, path: &mut PathBuilder) -> Result<(), String> {
+    let coordinate = |name: &str| -> Result<f64, String> {
+        match node.attribute(name) {
+            Some(value) => value
+                .trim()
+                .trim_end_matches("px")
+                .parse()
+                .map_err(|_| format!("{:?} is not a coordinate", value)),
+            None => Ok(0.),
+        }
+    };
+    path.move_to((coordinate("x1")?, coordinate("y1")?));
+    path.line_to((coordinate("x2")?, coordinate("y2")?));
+    Ok(())
+}
+
+/**
+Add the points of the cubic Bézier curve (without its start) to `points`. The curve is subdivided
+until its control points are at most `tolerance` away from its chord.
+*/
+fn flatten_cubic(curve: [Point; 4], tolerance: f64, depth: usize, points: &mut Vec<Point>) {
+    //{{{
+    let [start, first, second, end] = curve;
+    if depth == 0
+        || distance_to_chord(first, start, end).max(distance_to_chord(second, start, end))
+            <= tolerance
+    {
+        points.push(end);
+        return;
+    }
+    // NOTE: de Casteljau subdivision in the middle of the curve
+    let middle = |(ax, ay): Point, (bx, by): Point| ((ax + bx) / 2., (ay + by) / 2.);
+    let (ab, bc, cd) = (
+        middle(start, first),
+        middle(first, second),
+        middle(second, end),
+    );
+    let (abc, bcd) = (middle(ab, bc), middle(bc, cd));
+    let center = middle(abc, bcd);
+    flatten_cubic([start, ab, abc, center], tolerance, depth - 1, points);
+    flatten_cubic([center, bcd, cd, end], tolerance, depth - 1, points);
+} //}}}
+
+fn distance_to_chord((x, y): Point, (ax, ay): Point, (bx, by): Point) -> f64 {
+    let (dx, dy) = (bx - ax, by - ay);
+    let length = dx.hypot(dy);
+    if length == 0. {
+        (x - ax).hypot(y - ay)
+    } else {
+        ((x - ax) * dy - (y - ay) * dx).abs() / length
+    }
+}
+
+/**
+Approximate the elliptical arc from `start` to `end` by cubic Bézier curves (at most a quarter of
+the ellipse each) following the
+[implementation notes](https://www.w3.org/TR/SVG11/implnote.html#ArcImplementationNotes) of SVG.
+*/
+fn arc_to_cubics(
+    start: Point,
+    (rx, ry): Point,
+    rotation: f64,
+    large_arc: bool,
+    sweep: bool,
+    end: Point,
+) -> Vec<[Point; 3]> {
+    //{{{
+    if start == end {
+        return Vec::new();
+    }
+    let (mut rx, mut ry) = (rx.abs(), ry.abs());
+    if rx == 0. || ry == 0. {
+        return vec![[start, end, end]];
+    }
+    let (sin, cos) = rotation.to_radians().sin_cos();
+    let (dx, dy) = ((start.0 - end.0) / 2., (start.1 - end.1) / 2.);
+    let (x, y) = (cos * dx + sin * dy, -sin * dx + cos * dy);
+    let scale = (x * x) / (rx * rx) + (y * y) / (ry * ry);
+    if scale > 1. {
+        rx *= scale.sqrt();
+        ry *= scale.sqrt();
+    }
+    let numerator = rx * rx * ry * ry - rx * rx * y * y - ry * ry * x * x;
+    let denominator = rx * rx * y * y + ry * ry * x * x;
+    let sign = if large_arc == sweep { -1. } else { 1. };
+    let coefficient = sign * (numerator.max(0.) / denominator).sqrt();
+    let (center_x, center_y) = (coefficient * rx * y / ry, -coefficient * ry * x / rx);
+    let center = (
+        cos * center_x - sin * center_y + (start.0 + end.0) / 2.,
+        sin * center_x + cos * center_y + (start.1 + end.1) / 2.,
+    );
+
+    let angle = |(ux, uy): Point, (vx, vy): Point| (ux * vy - uy * vx).atan2(ux * vx + uy * vy);
+    let from = ((x - center_x) / rx, (y - center_y) / ry);
+    let to = ((-x - center_x) / rx, (-y - center_y) / ry);
+    let theta = angle((1., 0.), from);
+    let mut delta = angle(from, to);
+    if !sweep && delta > 0. {
+        delta -= 2. * PI;
+    } else if sweep && delta < 0. {
+        delta += 2. * PI;
+    }
+
+    let segments = (delta.abs() / (PI / 2.)).ceil().max(1.) as usize;
+    let step = delta / segments as f64;
+    let handle = 4. / 3. * (step / 4.).tan();
+    // NOTE: Point of the unit circle mapped onto the ellipse
+    let ellipse = |(u, v): Point| {
+        (
+            cos * rx * u - sin * ry * v + center.0,
+            sin * rx * u + cos * ry * v + center.1,
+        )
+    };
+    (0..segments)
+        .map(|segment| {
+            let from = theta + step * segment as f64;
+            let to = from + step;
+            let (from_sin, from_cos) = from.sin_cos();
+            let (to_sin, to_cos) = to.sin_cos();
+            let last = if segment + 1 == segments {
+                end
+            } else {
+                ellipse((to_cos, to_sin))
+            };
+            [
+                ellipse((from_cos - handle * from_sin, from_sin + handle * from_cos)),
+                ellipse((to_cos + handle * to_sin, to_sin - handle * to_cos)),
+                last,
+            ]
+        })
+        .collect()
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+mod svg_tests {
+    use super::{parse_transform, Matrix, StrokeSet, SvgError};
+
+    fn svg(content: &str) -> String {
+        format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg">{}</svg>"#,
+            content
+        )
+    }
+
+    fn close(a: &[f64], b: &[f64]) -> bool {
+        a.len() == b.len() && a.iter().zip(b).all(|(a, b)| (a - b).abs() < 1e-9)
+    }
+
+    #[test]
+    fn parse_paths() {
+        //{{{
+        let strokes = StrokeSet::from_svg(
+            &svg(r#"<path d="M10,10 20,10 l0-10 H5 v5 z m 5 5 h1 M1 1 L1.5.5"/>"#),
+            0.1,
+        )
+        .unwrap();
+        let strokes = strokes.strokes();
+        assert_eq!(strokes.len(), 3);
+        assert_eq!(strokes[0].x, vec![10., 20., 20., 5., 5., 10.]);
+        assert_eq!(strokes[0].y, vec![10., 10., 0., 0., 5., 10.]);
+        assert_eq!(strokes[1].x, vec![15., 16.]);
+        assert_eq!(strokes[1].y, vec![15., 15.]);
+        assert_eq!(strokes[2].y, vec![1., 0.5]);
+
+        let strokes = StrokeSet::from_svg(
+            &svg(r#"<polyline points="0,0 10,0 10,10"/><polygon points="0 0 1 0 1 1"/>"#),
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(strokes.strokes()[0].x, vec![0., 10., 10.]);
+        assert_eq!(strokes.strokes()[1].y, vec![0., 0., 1., 0.]);
+
+        // Elements that are not rendered are skipped
+        let strokes = StrokeSet::from_svg(
+            &svg(r#"<defs><path d="M0 0 L5 5"/></defs><line x2="3" y2="4"/>"#),
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(strokes.len(), 1);
+    } //}}}
+
+    #[test]
+    fn flatten_curves() {
+        //{{{
+        // Quarter circles with the radius 10 around (0, 0)
+        for path in &[
+            "M10 0 A10 10 0 0 1 0 10",
+            "M10 0 a10 10 0 10-10 10 A 10 10 0 0 0 10 0",
+            "M10 0 C10 5.5228 5.5228 10 0 10 S-10 5.5228 -10 0",
+            "M10 0 Q10 10 0 10 T-10 0",
+        ] {
+            let strokes =
+                StrokeSet::from_svg(&svg(&format!(r#"<path d="{}"/>"#, path)), 0.01).unwrap();
+            let stroke = &strokes.strokes()[0];
+            assert!(stroke.len() > 8, "{}: {} points", path, stroke.len());
+            assert_eq!(stroke.points().next(), Some((10., 0.)));
+            if !path.contains('Q') {
+                for (x, y) in stroke.points() {
+                    assert!((x.hypot(y) - 10.).abs() < 0.02, "{}: ({}, {})", path, x, y);
+                }
+            }
+        }
+
+        let coarse =
+            StrokeSet::from_svg(&svg(r#"<path d="M10 0 A10 10 0 0 1 0 10"/>"#), 1.).unwrap();
+        let fine =
+            StrokeSet::from_svg(&svg(r#"<path d="M10 0 A10 10 0 0 1 0 10"/>"#), 0.001).unwrap();
+        assert!(coarse.strokes()[0].len() < fine.strokes()[0].len());
+    } //}}}
+
+    #[test]
+    fn apply_transforms() {
+        //{{{
+        assert_eq!(
+            parse_transform("translate(1, 2) scale(2)").unwrap(),
+            Matrix([2., 0., 0., 2., 1., 2.])
+        );
+        let rotated = parse_transform("rotate(90 1 1)").unwrap().apply((2., 1.));
+        assert!(close(&[rotated.0, rotated.1], &[1., 2.]));
+        assert!(parse_transform("scale(1 2 3)").is_err());
+        assert!(parse_transform("spin(90)").is_err());
+
+        let strokes = StrokeSet::from_svg(
+            &svg(concat!(
+                r#"<g transform="translate(100 0)"><g transform="scale(2)">"#,
+                r#"<line x1="1" y1="1" x2="2" y2="3" transform="matrix(1 0 0 1 0 10)"/>"#,
+                r#"</g></g>"#
+            )),
+            0.1,
+        )
+        .unwrap();
+        assert_eq!(strokes.strokes()[0].x, vec![102., 104.]);
+        assert_eq!(strokes.strokes()[0].y, vec![22., 26.]);
+    } //}}}
+
+    #[test]
+    fn parse_errors() {
+        //{{{
+        assert!(matches!(
+            StrokeSet::from_svg("<ink/>", 0.1),
+            Err(SvgError::NotSvg(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_svg(&svg(""), 0.),
+            Err(SvgError::InvalidTolerance(_))
+        ));
+        assert!(matches!(
+            StrokeSet::from_svg(&svg(""), 0.1),
+            Err(SvgError::Stroke(_))
+        ));
+        for path in &["L 1 1", "M 1", "M 1 1 X 2 2", "M 0 0 A 1 1 0 2 0 1 1"] {
+            assert!(
+                matches!(
+                    StrokeSet::from_svg(&svg(&format!(r#"<path d="{}"/>"#, path)), 0.1),
+                    Err(SvgError::InvalidElement { element: 0, .. })
+                ),
+                "{}",
+                path
+            );
+        }
+        assert!(StrokeSet::from_svg(&svg(r#"<polyline points="0 0 1"/>"#), 0.1).is_err());
+    } //}}}
+}
+// }}}
@@ -0,0 +1,323 @@
+use super::error::XoppError;
+use super::stroke_set::{Stroke, StrokeSet};
+use flate2::read::GzDecoder;
+use roxmltree::{Document, Node};
+use std::io::Read;
+
+/// The first bytes of a gzip compressed file
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+
+// XoppOptions {{{
+/// What is read from the pages of a Xournal++ document
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct XoppOptions {
+    /// Numbers of the pages to read (starting from 1). All the pages are read when `None`.
+    pub pages: Option<Vec<usize>>,
+    /// Numbers of the layers to read on every page (starting from 1 for the bottom layer). All the
+    /// layers are read when `None`.
+    pub layers: Option<Vec<usize>>,
+    /// Read the strokes of the highlighter along with the strokes of the pen
+    pub highlighter: bool,
+    /// Read the images (also the rendered LaTeX) that are placed on the pages
+    pub images: bool,
+}
+
+impl XoppOptions {
+    field_builder![pages, Vec<usize>];
+    field_builder![layers, Vec<usize>];
+
+    pub fn highlighter(&mut self, val: bool) -> &mut Self {
+        self.highlighter = val;
+        self
+    }
+
+    pub fn images(&mut self, val: bool) -> &mut Self {
+        self.images = val;
+        self
+    }
+}
+// }}}
+
+// XoppPage {{{
+/// A page of a Xournal++ document
+#[derive(Debug, PartialEq, Clone)]
+pub struct XoppPage {
+    /// Number of the page in the document (starting from 1)
+    pub number: usize,
+    /// Strokes on the page or `None` when there are none in the selected layers
+    pub strokes: Option<StrokeSet>,
+    /// Images on the page (only when [XoppOptions::images] is set)
+    pub images: Vec<XoppImage>,
+}
+
+/// An image placed on a page of a Xournal++ document
+#[derive(Debug, PartialEq, Clone)]
+pub struct XoppImage {
+    pub left: f64,
+    pub top: f64,
+    pub right: f64,
+    pub bottom: f64,
+    /// The PNG data of the image
+    pub png: Vec<u8>,
+}
+// }}}
+
+/**
+Read the pages of a [Xournal++](https://xournalpp.github.io/) document (`.xopp`, or the older
+`.xoj`). The document may be gzip compressed (as Xournal++ saves it) or plain XML. The strokes of
+the pen in the selected layers of every selected page become the [StrokeSet] of the page in the
+order in which they were drawn. The strokes of the eraser are never read.
+
+```no_run
+use mathpixapi::endpoint::strokes::{read_xopp, XoppOptions};
+
+let mut options = XoppOptions::default();
+options.pages(vec![1, 2]).highlighter(true);
+let pages = read_xopp(&std::fs::read("lecture.xopp").unwrap(), &options).unwrap();
+for page in pages {
+    println!("page {}: {:?} strokes", page.number, page.strokes.map(|strokes| strokes.len()));
+}
+```
+*/
+pub fn read_xopp(content: &[u8], options: &XoppOptions) -> Result<Vec<XoppPage>, XoppError> {
+    //{{{
+    let mut document = String::new();
+    if content.starts_with(GZIP_MAGIC) {
+        GzDecoder::new(content).read_to_string(&mut document)?;
+    } else {
+        document = String::from_utf8(content.to_vec())
+            .map_err(|error| std::io::Error::new(std::io::ErrorKind::InvalidData, error))?;
+    }
+    let document = Document::parse(&document)?;
+    let root = document.root_element();
+    if root.tag_name().name() != "xournal" {
+        return Err(XoppError::NotXopp(root.tag_name().name().to_string()));
+    }
+
+    let selected = |selection: &Option<Vec<usize>>, number: usize| {
+        selection
+            .as_ref()
+            .is_none_or(|numbers| numbers.contains(&number))
+    };
+    let mut pages = Vec::new();
+    for (index, page) in elements(root, "page").enumerate() {
+        let number = index + 1;
+        if !selected(&options.pages, number) {
+            continue;
+        }
+        let mut strokes = Vec::new();
+        let mut images = Vec::new();
+        for (index, layer) in elements(page, "layer").enumerate() {
+            if !selected(&options.layers, index + 1) {
+                continue;
+            }
+            for node in layer.children().filter(Node::is_element) {
+                match node.tag_name().name() {
+                    "stroke" => {
+                        let read = match node.attribute("tool").unwrap_or("pen") {
+                            "highlighter" => options.highlighter,
+                            "eraser" => false,
+                            _ => true,
+                        };
+                        if read {
+                            let stroke =
+                                parse_stroke(node.text().unwrap_or("")).map_err(|message| {
+                                    XoppError::InvalidStroke {
+                                        page: number,
+                                        stroke: strokes.len(),
+                                        message,
+                                    }
+                                })?;
+                            strokes.push(stroke);
+                        }
+                    }
+                    "image" | "teximage" if options.images => {
+                        let image =
+                            parse_image(node).map_err(|message| XoppError::InvalidImage {
+                                page: number,
+                                image: images.len(),
+                                message,
+                            })?;
+                        images.push(image);
+                    }
+                    _ => {}
+                }
+            }
+        }
+        let strokes = if strokes.is_empty() {
+            None
+        } else {
+            Some(StrokeSet::new(strokes)?)
+        };
+        pages.push(XoppPage {
+            number,
+            strokes,
+            images,
+        });
+    }
+    Ok(pages)
+} //}}}
+
+/// Child elements of the `node` with the `name`
+fn elements<'a, 'input: 'a>(
+    node: Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = Node<'a, 'input>> + 'a {
+    node.children()
+        .filter(move |node| node.is_element() && node.has_tag_name(name))
+}
+
+/// Parse the coordinates of the stroke given as `x1 y1 x2 y2 ...`
+fn parse_stroke(text: &str) -> Result<Stroke, String> {
+    //{{{
+    let values = text
+        .split_whitespace()
+        .map(|value| {
+            value
+                .parse::<f64>()
+                .map_err(|_| format!("{:?} is not a coordinate", value))
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
+    if values.is_empty() || values.len() % 2 != 0 {
+        return Err(format!(
+            "expected pairs of coordinates but got {} values",
+            values.len()
+        ));
+    }
+    Ok(values.chunks(2).map(|point| (point[0], point[1])).collect())
+} //}}}
+
+/// Parse the position and the base64 encoded PNG data of the image
+fn parse_image(node: Node) -> Result<XoppImage, String> {
+    //{{{
+    let position = |name: &str| {
+        node.attribute(name)
+            .and_then(|value| value.parse::<f64>().ok())
+            .ok_or_else(|| format!("missing or invalid `{}` of the image", name))
+    };
+    let data: String = node
+        .text()
+        .unwrap_or("")
+        .chars()
+        .filter(|character| !character.is_whitespace())
+        .collect();
+    Ok(XoppImage {
+        left: position("left")?,
+        top: position("top")?,
+        right: position("right")?,
+        bottom: position("bottom")?,
+        png: base64::decode(data).map_err(|error| error.to_string())?,
+    })
+} //}}}
+
+// TESTS {{{
+#[cfg(test)]
+mod xopp_tests {
+    use super::{read_xopp, XoppError, XoppOptions};
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    const DOCUMENT: &str = r##"<?xml version="1.0" standalone="no"?>
+<xournal creator="Xournal++ 1.1.0" fileversion="4">
+  <title>Xournal++ document</title>
+  <page width="595.27" height="841.89">
+    <background type="solid" color="#ffffffff" style="lined"/>
+    <layer>
+      <stroke tool="pen" color="#000000ff" width="1.41 1.2 1.3">10 20 11 21.5 12 23</stroke>
+      <stroke tool="highlighter" color="#ffff00ff" width="8.5">0 0 100 0</stroke>
+      <stroke tool="eraser" color="#ffffffff" width="5">1 1 2 2</stroke>
+      <text font="Sans" size="12" x="10" y="10" color="#000000ff">title</text>
+      <image left="50" top="60" right="70" bottom="80">iVBORw0KGgo=</image>
+    </layer>
+    <layer name="Layer 2">
+      <stroke tool="pen" color="#000000ff" width="1.41">30 40 31 41</stroke>
+    </layer>
+  </page>
+  <page width="595.27" height="841.89">
+    <background type="solid" color="#ffffffff" style="plain"/>
+    <layer/>
+  </page>
+</xournal>"##;
+
+    fn gzip(document: &str) -> Vec<u8> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(document.as_bytes()).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn read_pages() {
+        //{{{
+        let pages = read_xopp(&gzip(DOCUMENT), &XoppOptions::default()).unwrap();
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[0].number, 1);
+        let strokes = pages[0].strokes.as_ref().unwrap().strokes();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[0].x, vec![10., 11., 12.]);
+        assert_eq!(strokes[0].y, vec![20., 21.5, 23.]);
+        assert_eq!(strokes[1].x, vec![30., 31.]);
+        assert!(pages[0].images.is_empty());
+        assert_eq!(pages[1].strokes, None);
+
+        // The plain XML is read as well
+        assert_eq!(
+            read_xopp(DOCUMENT.as_bytes(), &XoppOptions::default()).unwrap(),
+            pages
+        );
+    } //}}}
+
+    #[test]
+    fn select_pages_and_layers() {
+        //{{{
+        let mut options = XoppOptions::default();
+        options
+            .pages(vec![1])
+            .layers(vec![1])
+            .highlighter(true)
+            .images(true);
+        let pages = read_xopp(&gzip(DOCUMENT), &options).unwrap();
+        assert_eq!(pages.len(), 1);
+        let strokes = pages[0].strokes.as_ref().unwrap().strokes();
+        assert_eq!(strokes.len(), 2);
+        assert_eq!(strokes[1].x, vec![0., 100.]);
+        assert_eq!(pages[0].images.len(), 1);
+        let image = &pages[0].images[0];
+        assert_eq!((image.left, image.bottom), (50., 80.));
+        assert_eq!(image.png, b"\x89PNG\r\n\x1a\n".to_vec());
+
+        options.pages(vec![2]);
+        assert_eq!(
+            read_xopp(DOCUMENT.as_bytes(), &options).unwrap()[0].number,
+            2
+        );
+    } //}}}
+
+    #[test]
+    fn read_errors() {
+        //{{{
+        let options = XoppOptions::default();
+        assert!(matches!(
+            read_xopp(b"<ink/>", &options),
+            Err(XoppError::NotXopp(_))
+        ));
+        assert!(matches!(
+            read_xopp(&gzip("<xournal><page></xournal>"), &options),
+            Err(XoppError::Xml(_))
+        ));
+        assert!(matches!(
+            read_xopp(&[0x1f, 0x8b, 0, 1], &options),
+            Err(XoppError::Io(_))
+        ));
+        let document = r#"<xournal><page><layer><stroke>1 2 3</stroke></layer></page></xournal>"#;
+        assert!(matches!(
+            read_xopp(document.as_bytes(), &options),
+            Err(XoppError::InvalidStroke {
+                page: 1,
+                stroke: 0,
+                ..
+            })
+        ));
+    } //}}}
+}
+// }}}
@@ -0,0 +1,198 @@
+//! A minimal HTTP server answering with canned responses. It is used for testing the requests
+//! of the endpoints without the Mathpix server.
+use reqwest::Url;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+
+/// A request that was received by the [TestServer]
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub method: String,
+    /// Path including the query string
+    pub path: String,
+    /// Header lines of the request in lowercase
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Response that the [TestServer] should reply with
+pub enum Reply {
+    /// Whole body sent at once with a `Content-Length`
+    Full {
+        status: u16,
+        content_type: &'static str,
+        body: Vec<u8>,
+    },
+    /// Body sent with `Transfer-Encoding: chunked`, one chunk after another with a delay in between
+    Chunked {
+        content_type: &'static str,
+        chunks: Vec<Vec<u8>>,
+        delay: Duration,
+    },
+}
+
+impl Reply {
+    pub fn json(body: serde_json::Value) -> Self {
+        Reply::Full {
+            status: 200,
+            content_type: "application/json",
+            body: body.to_string().into_bytes(),
+        }
+    }
+
+    pub fn bytes<B: Into<Vec<u8>>>(body: B) -> Self {
+        Reply::Full {
+            status: 200,
+            content_type: "application/octet-stream",
+            body: body.into(),
+        }
+    }
+
+    pub fn status(status: u16) -> Self {
+        Reply::Full {
+            status,
+            content_type: "text/plain",
+            body: Vec::new(),
+        }
+    }
+}
+
+pub struct TestServer {
+    /// Base URL of the server ending with `/`
+    pub url: Url,
+    requests: Arc<Mutex<Vec<RecordedRequest>>>,
+}
+
+impl TestServer {
+    /// Start the server on a random local port. Every request is answered by `handler`.
+    pub async fn spawn<F>(handler: F) -> Self
+    where
+        F: Fn(&RecordedRequest) -> Reply + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = Url::parse(&format!("http://{}/", listener.local_addr().unwrap())).unwrap();
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let handler = Arc::new(handler);
+        let recorded = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let handler = handler.clone();
+                let recorded = recorded.clone();
+                tokio::spawn(async move {
+                    let mut stream = stream;
+                    handle_connection(&mut stream, &handler, &recorded).await;
+                });
+            }
+        });
+        TestServer { url, requests }
+    }
+
+    /// All of the requests received until now
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+}
+
+async fn handle_connection<F>(
+    stream: &mut TcpStream,
+    handler: &Arc<F>,
+    recorded: &Arc<Mutex<Vec<RecordedRequest>>>,
+) -> Option<()>
+where
+    F: Fn(&RecordedRequest) -> Reply,
+{
+    let mut buffer = Vec::new();
+    let header_end = loop {
+        let mut chunk = [0; 4096];
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            return None;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(position) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break position + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).into_owned();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    let content_length: usize = headers
+        .iter()
+        .find(|(key, _)| key == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let mut body = buffer[header_end..].to_vec();
+    while body.len() < content_length {
+        let mut chunk = [0; 4096];
+        let read = stream.read(&mut chunk).await.ok()?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    let request = RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    };
+    let reply = handler(&request);
+    recorded.lock().unwrap().push(request);
+
+    match reply {
+        Reply::Full {
+            status,
+            content_type,
+            body,
+        } => {
+            let head = format!(
+                "HTTP/1.1 {} Test\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                content_type,
+                body.len()
+            );
+            stream.write_all(head.as_bytes()).await.ok()?;
+            stream.write_all(&body).await.ok()?;
+        }
+        Reply::Chunked {
+            content_type,
+            chunks,
+            delay,
+        } => {
+            let head = format!(
+                "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nTransfer-Encoding: chunked\r\nConnection: close\r\n\r\n",
+                content_type
+            );
+            stream.write_all(head.as_bytes()).await.ok()?;
+            for chunk in chunks {
+                stream
+                    .write_all(format!("{:x}\r\n", chunk.len()).as_bytes())
+                    .await
+                    .ok()?;
+                stream.write_all(&chunk).await.ok()?;
+                stream.write_all(b"\r\n").await.ok()?;
+                stream.flush().await.ok()?;
+                tokio::time::sleep(delay).await;
+            }
+            stream.write_all(b"0\r\n\r\n").await.ok()?;
+        }
+    }
+    stream.shutdown().await.ok()
+}
@@ -1,4 +1,6 @@
-pub use super::super::shared_objects::request::{Base64ImageError, ConfidenceThresholdError};
+pub use super::super::shared_objects::request::{
+    Base64ImageError, CallBackError, ConfidenceThresholdError,
+};
 use reqwest;
 use serde_json;
 use thiserror::Error;
@@ -13,11 +15,13 @@ pub enum TextError {
     Request(#[from] reqwest::Error),
     #[error("OptionsError: {0}")]
     Options(#[from] TextOptionsError),
+    #[error("CallBackError: {0}")]
+    CallBack(#[from] CallBackError),
 }
 
 impl From<std::convert::Infallible> for TextError {
-    fn from(_: std::convert::Infallible) -> Self {
-        unreachable!()
+    fn from(never: std::convert::Infallible) -> Self {
+        match never {}
     }
 }
 
@@ -62,6 +66,8 @@ pub enum BadOptionError {
     DataOption(String),
     #[error("BadAlphabetAllowed: {0} is not available as an allowed alphabet. Possible options are {:?}.", ALPHABETS_ALLOWED)]
     AlphabetAllowed(String),
+    #[error("BadDelimiters: ({0:?}, {1:?}) are not valid delimiters. Both of the delimiters must be non-empty.")]
+    Delimiters(String, String),
     #[error("BadConfidenceThreshold: {0}")]
     ConfidenceThreshold(#[from] ConfidenceThresholdError),
 }
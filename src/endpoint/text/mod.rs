@@ -5,15 +5,15 @@ mod error;
 mod options;
 mod response;
 
+use super::shared_objects::request::{expand_callback, json_body, TemplateVars, BODY_CAPACITY};
 pub use super::shared_objects::request::{
-    AlphabetsAllowed, Base64Image, DataOptions, ImageSrc, MetaData,
+    AlphabetsAllowed, Base64Image, CallBack, DataOptions, ImageSrc, MetaData,
 };
 use super::{super::MATHPIX_APIURL, MathpixEndpoint};
-use async_trait::async_trait;
-use error::TextError;
+pub use error::TextError;
 pub use options::{TextFormats, TextOptions};
 use reqwest;
-use response::TextResponse;
+pub use response::TextResponse;
 
 // Text {{{
 #[derive(Serialize, Debug)]
@@ -26,7 +26,6 @@ pub struct Text {
     pub options: TextOptions,
 } //}}}
 
-#[async_trait]
 impl MathpixEndpoint for Text {
     //{{{
     type Src = ImageSrc;
@@ -40,11 +39,7 @@ impl MathpixEndpoint for Text {
         Self::Error: From<E>,
         Self: Sized,
     {
-        let text_options = if let Some(options) = options {
-            options
-        } else {
-            Self::Options::default()
-        };
+        let text_options = options.unwrap_or_default();
         let text_src: Self::Src = src.try_into()?;
         Ok(Self {
             src: Some(text_src),
@@ -58,29 +53,23 @@ impl MathpixEndpoint for Text {
         reqwest::Url::parse(&url_str).unwrap()
     }
 
-    async fn send_request<H, Fut, F>(&self, header: H) -> Result<Self::Response, Self::Error>
-    where
-        H: Into<super::AuthHeader> + std::marker::Send,
-        reqwest::Response: Into<Self::Response>,
-        reqwest::Error: Into<Self::Error>,
-    {
-        let client = reqwest::Client::new();
-        let request = self.to_request(header)?;
-        client.execute(request).await.map(|f| Ok(f.into()))?
-    }
-
-    fn to_request<H: Into<super::AuthHeader>>(
-        &self,
-        header: H,
-    ) -> Result<reqwest::Request, Self::Error> {
-        Ok(self
-            .to_request_builder()
-            .headers(header.into().into())
-            .build()?)
-    }
-
-    fn to_request_builder(&self) -> reqwest::RequestBuilder {
-        reqwest::Client::new().post(self.url()).json(self)
+    fn to_request_builder(&self) -> Result<reqwest::RequestBuilder, Self::Error> {
+        // NOTE: The image is encoded first so that a missing file is a `Src` error
+        let src_len = match &self.src {
+            Some(src) => src.encode()?.len(),
+            None => 0,
+        };
+        let request = reqwest::Client::new().post(self.url());
+        let capacity = src_len + BODY_CAPACITY;
+        // NOTE: The body is only converted to a JSON value (a copy of the encoded image) to
+        // expand the callback
+        if self.options.callback.is_none() {
+            return Ok(json_body(request, self, capacity)?);
+        }
+        let mut vars = TemplateVars::now();
+        vars.image = self.src.as_ref().and_then(ImageSrc::file_name);
+        let body = expand_callback::<_, Self::Error>(self, self.options.callback.as_ref(), &vars)?;
+        Ok(json_body(request, &body, capacity)?)
     }
 
     fn options(&mut self) -> &mut Self::Options {
@@ -98,5 +87,72 @@ impl MathpixEndpoint for Text {
 
 // TESTS {{{
 #[cfg(test)]
-mod test {}
+mod test {
+    use super::{CallBack, ImageSrc, MathpixEndpoint, Text, TextError, TextOptions};
+    use crate::endpoint::shared_objects::request::{Base64ImageError, CallBackError};
+    use crate::header::AuthHeader;
+    use reqwest::Url;
+    use serde_json::{json, Value};
+
+    #[test]
+    fn serialize_callback_and_tags() {
+        //{{{
+        let mut callback = CallBack::new("https://example.com/callback").unwrap();
+        callback.header_line("X-Image: $image").unwrap();
+        let mut options = TextOptions::default();
+        options
+            .callback(callback)
+            .add_tags(["exam"])
+            .enable_spell_check(true);
+        let src = ImageSrc::Url(Url::parse("https://mathpix.com/examples/limit.jpg").unwrap());
+        let text = Text::new(Some(options), src).unwrap();
+
+        let request = text.to_request(AuthHeader::new("id", "key")).unwrap();
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(
+            body["callback"]["post"],
+            json!("https://example.com/callback")
+        );
+        assert_eq!(body["callback"]["headers"]["X-Image"], json!("limit.jpg"));
+        assert_eq!(body["tags"], json!(["exam"]));
+        assert_eq!(body["enable_spell_check"], json!(true));
+    } //}}}
+
+    #[test]
+    fn missing_image() {
+        //{{{
+        let text = Text::new(None, "./test/assets/missing.png").unwrap();
+        assert!(matches!(
+            text.to_request(AuthHeader::new("id", "key")),
+            Err(TextError::Src(Base64ImageError::Io(_)))
+        ));
+
+        let text = Text::new(None, "./test/assets/test_encode_base64.jpg").unwrap();
+        let request = text.to_request(AuthHeader::new("id", "key")).unwrap();
+        let body: Value =
+            serde_json::from_slice(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert!(body["src"]
+            .as_str()
+            .unwrap()
+            .starts_with("data:image/jpeg;base64,/9j/"));
+    } //}}}
+
+    #[test]
+    fn invalid_expanded_header() {
+        //{{{
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("scan\n1.jpg");
+        std::fs::copy("./test/assets/test_encode_base64.jpg", &path).unwrap();
+        let mut callback = CallBack::new("https://example.com/callback").unwrap();
+        callback.header_line("X-Image: $image").unwrap();
+        let mut options = TextOptions::default();
+        options.callback(callback);
+        let text = Text::new(Some(options), path).unwrap();
+        assert!(matches!(
+            text.to_request(AuthHeader::new("id", "key")),
+            Err(TextError::CallBack(CallBackError::InvalidHeader(_)))
+        ));
+    } //}}}
+}
 //}}}
@@ -1,5 +1,5 @@
 pub use super::super::shared_objects::request::{
-    AlphabetsAllowed, Base64Image, ConfidenceThreshold, DataOptions, ImageSrc, MetaData,
+    AlphabetsAllowed, Base64Image, CallBack, ConfidenceThreshold, DataOptions, ImageSrc, MetaData,
 };
 use super::error::{
     BadOptionError, ConfidenceThresholdError, LogicalFallacyError, TextOptionsError,
@@ -9,8 +9,9 @@ use rayon::prelude::*;
 use serde::{ser::SerializeSeq, Serialize, Serializer};
 use std::collections::HashSet;
 use std::convert::TryInto;
+use std::fmt;
 
-#[derive(Serialize, Debug, PartialEq, Default)]
+#[derive(Serialize, Debug, PartialEq, Clone, Default)]
 pub struct TextOptions {
     // {{{
     /// > Key value object
@@ -46,6 +47,28 @@ pub struct TextOptions {
     pub rm_fonts: Option<bool>,
     /// > Specifies whether numbers are always math, e.g., `Answer: \( 17 \)` instead of `Answer: 17`. Default is `false`.
     pub numbers_default_to_math: Option<bool>,
+    /// > Specifies begin inline math and end inline math delimiters for `text` outputs
+    pub math_inline_delimiters: Option<(String, String)>,
+    /// > Specifies begin display math and end display math delimiters for `text` outputs
+    pub math_display_delimiters: Option<(String, String)>,
+    /// > Specifies whether to include equation number tags inside equations LaTeX in the form of `\tag{eq_number}`, where `eq_number` is an equation number (e.g. `1.12`). When set to `true`, it sets `"idiomatic_eqn_arrays": true`, because equation numbering works better in those environments compared to the array environment.
+    pub include_equation_tags: Option<bool>,
+    /// > Enables advanced table processing algorithm that supports very large and complex tables. Default is `false`.
+    pub enable_tables_fallback: Option<bool>,
+    /// > Controls if punctuation will be fullwidth Unicode (default for text in CJK) or halfwidth Unicode (default for text in Latin scripts)
+    pub fullwidth_punctuation: Option<bool>,
+    /// > Specifies whether to use `aligned`, `gathered` or `cases` instead of an `array` environment for a list of equations. Default is `false`.
+    pub idiomatic_eqn_arrays: Option<bool>,
+    /// > Specifies whether to return text from diagrams (in `line_data` and `word_data`). Default is `false`.
+    pub include_diagram_text: Option<bool>,
+    /// > Specifies whether to return the HTML of the tables in `line_data`. Default is `false`.
+    pub include_table_html: Option<bool>,
+    /// > Callback object, see [Callback](https://docs.mathpix.com/?shell#callback-object) section
+    pub callback: Option<CallBack>,
+    /// > Tags are lists of strings that can be used to identify results, see [query image results](https://docs.mathpix.com/?shell#query-image-results)
+    pub tags: Option<Vec<String>>,
+    /// > Enables a predictive mode for English handwriting that takes word frequencies into account; this option is skipped when the language is not detected as English; incorrectly spelled words that are clearly legible will not be fixed. Default is `false`.
+    pub enable_spell_check: Option<bool>,
 } // }}}
 
 pub fn ser_set<S>(set: &Option<HashSet<TextFormats>>, s: S) -> Result<S::Ok, S::Error>
@@ -88,7 +111,6 @@ impl TextOptions {
     /// TextFormats::Html]);
     /// assert_eq!(options, expected);
     /// ```
-
     pub fn add_formats_from_strings<S, I: IntoIterator<Item = S>>(
         &mut self,
         formats: I,
@@ -97,7 +119,7 @@ impl TextOptions {
         S: AsRef<str>,
     {
         //{{{
-        if self.formats == None {
+        if self.formats.is_none() {
             self.formats = Some(HashSet::new());
         }
         if let Some(self_formats) = &mut self.formats {
@@ -155,7 +177,7 @@ impl TextOptions {
         S: AsRef<str>,
     {
         //{{{
-        if self.data_options == None {
+        if self.data_options.is_none() {
             self.data_options = Some(DataOptions::default());
         }
         if let Some(self_data_options) = &mut self.data_options {
@@ -207,7 +229,7 @@ impl TextOptions {
         alphabets: &[S],
     ) -> Result<&mut Self, TextOptionsError> {
         // {{{
-        if self.alphabets_allowed == None && !alphabets.is_empty() {
+        if self.alphabets_allowed.is_none() && !alphabets.is_empty() {
             self.alphabets_allowed = Some(AlphabetsAllowed::default());
         }
 
@@ -374,8 +396,78 @@ impl TextOptions {
         self.numbers_default_to_math = Some(val);
         self
     }
+
+    pub fn math_inline_delimiters<S: Into<String>>(
+        &mut self,
+        begin: S,
+        end: S,
+    ) -> Result<&mut Self, TextOptionsError> {
+        self.math_inline_delimiters = Some(delimiters(begin.into(), end.into())?);
+        Ok(self)
+    }
+
+    pub fn math_display_delimiters<S: Into<String>>(
+        &mut self,
+        begin: S,
+        end: S,
+    ) -> Result<&mut Self, TextOptionsError> {
+        self.math_display_delimiters = Some(delimiters(begin.into(), end.into())?);
+        Ok(self)
+    }
+
+    pub fn include_equation_tags(&mut self, val: bool) -> &mut Self {
+        self.include_equation_tags = Some(val);
+        self
+    }
+
+    pub fn enable_tables_fallback(&mut self, val: bool) -> &mut Self {
+        self.enable_tables_fallback = Some(val);
+        self
+    }
+
+    pub fn fullwidth_punctuation(&mut self, val: bool) -> &mut Self {
+        self.fullwidth_punctuation = Some(val);
+        self
+    }
+
+    pub fn idiomatic_eqn_arrays(&mut self, val: bool) -> &mut Self {
+        self.idiomatic_eqn_arrays = Some(val);
+        self
+    }
+
+    pub fn include_diagram_text(&mut self, val: bool) -> &mut Self {
+        self.include_diagram_text = Some(val);
+        self
+    }
+
+    pub fn include_table_html(&mut self, val: bool) -> &mut Self {
+        self.include_table_html = Some(val);
+        self
+    }
+
+    field_builder![callback, CallBack];
+
+    /// Add tags to identify the result of the request with
+    pub fn add_tags<S: Into<String>, I: IntoIterator<Item = S>>(&mut self, tags: I) -> &mut Self {
+        self.tags
+            .get_or_insert_with(Vec::new)
+            .extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn enable_spell_check(&mut self, val: bool) -> &mut Self {
+        self.enable_spell_check = Some(val);
+        self
+    }
 } //}}}
 
+fn delimiters(begin: String, end: String) -> Result<(String, String), TextOptionsError> {
+    if begin.is_empty() || end.is_empty() {
+        return Err(BadOptionError::Delimiters(begin, end).into());
+    }
+    Ok((begin, end))
+}
+
 /// Format specifications possible for the _text_ endpoint
 #[derive(Debug, Serialize, Eq, Clone, Hash, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -395,24 +487,25 @@ pub enum TextFormats {
     LaTeXStyled,
 }
 
-impl ToString for TextFormats {
-    fn to_string(&self) -> String {
-        match self {
-            TextFormats::Text => "text".to_string(),
-            TextFormats::Html => "html".to_string(),
-            TextFormats::Data => "data".to_string(),
-            TextFormats::LaTeXStyled => "latex_styled".to_string(),
-        }
+impl fmt::Display for TextFormats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            TextFormats::Text => "text",
+            TextFormats::Html => "html",
+            TextFormats::Data => "data",
+            TextFormats::LaTeXStyled => "latex_styled",
+        })
     }
 }
 //}}}
 
 // TESTS {{{
 #[cfg(test)]
+#[allow(clippy::manual_contains, clippy::field_reassign_with_default)]
 mod text_options_tests {
     use super::super::super::shared_objects::request::Base64Image;
     use super::super::{AlphabetsAllowed, DataOptions, ImageSrc, Text, TextFormats, TextOptions};
-    use super::TextOptionsError;
+    use super::{BadOptionError, TextOptionsError};
     use serde_json::{json, Value::Null};
     use std::convert::TryInto;
     use std::path::PathBuf;
@@ -714,6 +807,10 @@ mod text_options_tests {
             rm_fonts: Some(true),
             rm_spaces: Some(false),
             numbers_default_to_math: None,
+            math_inline_delimiters: Some(("$".to_string(), "$".to_string())),
+            include_equation_tags: Some(true),
+            tags: Some(vec!["exam".to_string()]),
+            ..Default::default()
         };
 
         let text = Text {
@@ -756,6 +853,17 @@ mod text_options_tests {
             "rm_fonts": true,
             "rm_spaces": false,
             "numbers_default_to_math": Null,
+            "math_inline_delimiters": ["$", "$"],
+            "math_display_delimiters": Null,
+            "include_equation_tags": true,
+            "enable_tables_fallback": Null,
+            "fullwidth_punctuation": Null,
+            "idiomatic_eqn_arrays": Null,
+            "include_diagram_text": Null,
+            "include_table_html": Null,
+            "callback": Null,
+            "tags": ["exam"],
+            "enable_spell_check": Null,
         });
 
         let expected_2 = json!({
@@ -791,10 +899,56 @@ mod text_options_tests {
             "rm_fonts": true,
             "rm_spaces": false,
             "numbers_default_to_math": Null,
+            "math_inline_delimiters": ["$", "$"],
+            "math_display_delimiters": Null,
+            "include_equation_tags": true,
+            "enable_tables_fallback": Null,
+            "fullwidth_punctuation": Null,
+            "idiomatic_eqn_arrays": Null,
+            "include_diagram_text": Null,
+            "include_table_html": Null,
+            "callback": Null,
+            "tags": ["exam"],
+            "enable_spell_check": Null,
         });
         assert!([expected_1, expected_2].iter().any(|r| *r == serialized));
     } //}}}
 
+    #[test]
+    fn builder_text_options() {
+        //{{{
+        let mut options = TextOptions::default();
+        options
+            .math_display_delimiters("$$", "$$")
+            .unwrap()
+            .idiomatic_eqn_arrays(true)
+            .fullwidth_punctuation(false)
+            .add_tags(["exam", "page-1"])
+            .add_tags(vec!["student".to_string()]);
+        assert_eq!(
+            options.math_display_delimiters,
+            Some(("$$".to_string(), "$$".to_string()))
+        );
+        assert_eq!(options.idiomatic_eqn_arrays, Some(true));
+        assert_eq!(options.fullwidth_punctuation, Some(false));
+        assert_eq!(
+            options.tags,
+            Some(vec![
+                "exam".to_string(),
+                "page-1".to_string(),
+                "student".to_string()
+            ])
+        );
+        assert!(matches!(
+            options.math_inline_delimiters("", "$"),
+            Err(TextOptionsError::BadOption(BadOptionError::Delimiters(
+                _,
+                _
+            )))
+        ));
+        assert_eq!(options.math_inline_delimiters, None);
+    } //}}}
+
     #[test]
     fn builder_formats_text_options() {
         //{{{
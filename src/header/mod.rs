@@ -64,6 +64,7 @@ impl From<AuthHeader> for HeaderMap {
 
 // TESTS {{{
 #[cfg(test)]
+#[allow(clippy::unnecessary_fallible_conversions, clippy::into_iter_on_ref)]
 mod header_tests {
     use super::AuthHeader;
     use reqwest::header::{HeaderMap, HeaderValue};
@@ -46,3 +46,14 @@ Module for creating the header of requests.
 > ```
 */
 pub mod header; //}}}
+
+// pub mod receiver; {{{
+/**
+Module for receiving the callbacks of the requests on a local HTTP server instead of polling for
+the results. It is available with the `callback-receiver` feature.
+
+> Callbacks can be used to receive results of requests asynchronously. The server makes a POST
+> request to the `post` URL of the callback object with the result when the request is processed.
+*/
+#[cfg(feature = "callback-receiver")]
+pub mod receiver; //}}}
@@ -0,0 +1,400 @@
+use crate::endpoint::latex::CallBack;
+use futures::channel::oneshot;
+use hyper::body::HttpBody;
+use hyper::header::CONTENT_LENGTH;
+use hyper::service::{make_service_fn, service_fn};
+use hyper::{Body, Method, Request, Response, Server, StatusCode};
+use reqwest::Url;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{BTreeMap, HashMap};
+use std::convert::Infallible;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::net::SocketAddr;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use thiserror::Error;
+
+/// Header with the secret of the receiver that the callbacks of [CallBackReceiver::callback] carry
+pub const SECRET_HEADER: &str = "X-Callback-Secret";
+/// Size of the biggest callback body that is accepted
+pub const MAX_BODY_SIZE: usize = 8 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum ReceiverError {
+    #[error("ServerError: {0}")]
+    Server(#[from] hyper::Error),
+    #[error("DeserializationError: {0}")]
+    Deserialization(#[from] serde_json::Error),
+    #[error("RandomError: {0}")]
+    Random(#[from] getrandom::Error),
+    #[error("Closed: the receiver was shut down before the callback arrived")]
+    Closed,
+}
+
+// CallBackResponse {{{
+/// > Callback response object that the server posts to the `post` URL of the [CallBack]
+#[derive(Debug, Deserialize, Clone, PartialEq)]
+pub struct CallBackResponse<R> {
+    /// ID of the request that the callback is for
+    pub request_id: Option<String>,
+    /// The `reply` of the [CallBack] of the request
+    pub reply: Option<Value>,
+    /// Result of the request, e.g. a `LaTeXResponse` for the _latex_ endpoint
+    pub result: R,
+} // }}}
+
+/// Callbacks that are waited for by their reply keys
+#[derive(Default)]
+struct State {
+    waiting: HashMap<String, oneshot::Sender<CallBackResponse<Value>>>,
+}
+
+/// Callbacks are matched by their `reply`
+fn reply_key(reply: &Option<Value>) -> String {
+    serde_json::to_string(reply).unwrap()
+}
+
+/// Random secret of a receiver from the random source of the operating system
+fn new_secret() -> Result<String, getrandom::Error> {
+    let mut bytes = [0u8; 16];
+    getrandom::fill(&mut bytes)?;
+    Ok(bytes.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// Compare the secrets in a constant time, so the time of a rejection does not leak the secret
+fn same_secret(given: &[u8], secret: &[u8]) -> bool {
+    given.len() == secret.len()
+        && given
+            .iter()
+            .zip(secret)
+            .fold(0, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+// CallBackReceiver {{{
+/**
+A small HTTP server that receives the callback POSTs of the Mathpix server. The callbacks are
+matched to the requests by their `reply` and delivered as a [PendingCallBack] future. The server
+runs in the background until the receiver is dropped.
+
+Only the callbacks that are waited for are accepted, every other POST is rejected. The callbacks
+created by [CallBackReceiver::callback] carry the secret of the receiver in the [SECRET_HEADER]
+header and the POSTs without it are rejected as well. The bodies are limited to [MAX_BODY_SIZE].
+
+The Mathpix server has to be able to reach the receiver. When it is behind a proxy or a tunnel,
+set the URL under which it is reachable with [CallBackReceiver::with_public_url].
+
+```no_run
+# async fn run() -> Result<(), Box<dyn std::error::Error>> {
+use mathpixapi::endpoint::latex::{ImageSrc, LaTeX, LaTeXOptions, LaTeXResponse};
+use mathpixapi::endpoint::MathpixEndpoint;
+use mathpixapi::header::AuthHeader;
+use mathpixapi::receiver::CallBackReceiver;
+use reqwest::Url;
+
+// NOTE: The tunnel forwards https://callbacks.example.com/ to the local port
+let receiver = CallBackReceiver::bind(([127, 0, 0, 1], 8080).into())
+    .await?
+    .with_public_url(Url::parse("https://callbacks.example.com/")?);
+let (callback, pending) = receiver.callback::<LaTeXResponse>();
+let mut options = LaTeXOptions::default();
+options.callback(callback);
+let src = ImageSrc::Url(Url::parse("https://mathpix.com/examples/limit.jpg")?);
+LaTeX::new(Some(options), src)?
+    .send_request(AuthHeader::new("ID", "KEY"))
+    .await?;
+println!("{:?}", pending.await?.result.text);
+# Ok(())
+# }
+```
+*/
+pub struct CallBackReceiver {
+    url: Url,
+    local_addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    secret: Arc<str>,
+    next_id: AtomicU64,
+    shutdown: Option<oneshot::Sender<()>>,
+}
+
+impl CallBackReceiver {
+    /// Start receiving the callbacks on `addr` (the port `0` picks a free port)
+    pub async fn bind(addr: SocketAddr) -> Result<Self, ReceiverError> {
+        //{{{
+        let state = Arc::new(Mutex::new(State::default()));
+        let secret: Arc<str> = new_secret()?.into();
+        let (service_state, service_secret) = (state.clone(), secret.clone());
+        let make_service = make_service_fn(move |_| {
+            let (state, secret) = (service_state.clone(), service_secret.clone());
+            let service = service_fn(move |request| handle(state.clone(), secret.clone(), request));
+            async move { Ok::<_, Infallible>(service) }
+        });
+        let server = Server::try_bind(&addr)?.serve(make_service);
+        let local_addr = server.local_addr();
+        let (shutdown, signal) = oneshot::channel::<()>();
+        tokio::spawn(server.with_graceful_shutdown(async {
+            let _ = signal.await;
+        }));
+        Ok(CallBackReceiver {
+            url: Url::parse(&format!("http://{}/", local_addr)).unwrap(),
+            local_addr,
+            state,
+            secret,
+            next_id: AtomicU64::new(1),
+            shutdown: Some(shutdown),
+        })
+    } //}}}
+
+    /// URL under which the Mathpix server reaches the receiver (the callbacks are posted to it)
+    pub fn with_public_url(mut self, url: Url) -> Self {
+        self.url = url;
+        self
+    }
+
+    /// URL that the callbacks are posted to
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    /// Address that the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Value of the [SECRET_HEADER] that the callbacks have to carry
+    pub fn secret(&self) -> &str {
+        &self.secret
+    }
+
+    /**
+    Wait for the callback with the `reply`. The callback has to carry the secret of the receiver
+    in the [SECRET_HEADER] header and it is only accepted after it is expected, so the request
+    with the callback has to be sent after calling this.
+    */
+    pub fn expect<R: DeserializeOwned>(&self, reply: Value) -> PendingCallBack<R> {
+        //{{{
+        let (sender, receiver) = oneshot::channel();
+        let key = reply_key(&Some(reply));
+        self.state.lock().unwrap().waiting.insert(key, sender);
+        PendingCallBack {
+            receiver,
+            result: PhantomData,
+        }
+    } //}}}
+
+    /**
+    A [CallBack] posting to the receiver with a unique `reply` and the secret of the receiver in
+    its headers and the [PendingCallBack] for it. Headers can be added to the callback before it is
+    used in a request.
+    */
+    pub fn callback<R: DeserializeOwned>(&self) -> (CallBack, PendingCallBack<R>) {
+        let reply = json!({ "callback_id": self.next_id.fetch_add(1, Ordering::Relaxed) });
+        let mut headers = BTreeMap::new();
+        headers.insert(SECRET_HEADER.to_string(), self.secret.to_string());
+        let callback = CallBack {
+            post: Some(self.url.clone()),
+            headers: Some(headers),
+            reply: Some(reply.clone()),
+        };
+        (callback, self.expect(reply))
+    }
+}
+
+impl Drop for CallBackReceiver {
+    fn drop(&mut self) {
+        if let Some(shutdown) = self.shutdown.take() {
+            let _ = shutdown.send(());
+        }
+        // NOTE: Open connections can keep the server running for a while after the shutdown
+        self.state.lock().unwrap().waiting.clear();
+    }
+} // }}}
+
+// PendingCallBack {{{
+/// Future of a callback that resolves when the callback is received
+pub struct PendingCallBack<R> {
+    receiver: oneshot::Receiver<CallBackResponse<Value>>,
+    result: PhantomData<fn() -> R>,
+}
+
+impl<R: DeserializeOwned> Future for PendingCallBack<R> {
+    type Output = Result<CallBackResponse<R>, ReceiverError>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.receiver).poll(cx).map(|response| {
+            let response = response.map_err(|_| ReceiverError::Closed)?;
+            Ok(CallBackResponse {
+                request_id: response.request_id,
+                reply: response.reply,
+                result: serde_json::from_value(response.result)?,
+            })
+        })
+    }
+} // }}}
+
+/// Read the `body` unless it is bigger than [MAX_BODY_SIZE]
+async fn read_body(mut body: Body) -> Result<Vec<u8>, StatusCode> {
+    //{{{
+    let mut data = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|_| StatusCode::BAD_REQUEST)?;
+        if data.len() + chunk.len() > MAX_BODY_SIZE {
+            return Err(StatusCode::PAYLOAD_TOO_LARGE);
+        }
+        data.extend_from_slice(&chunk);
+    }
+    Ok(data)
+} //}}}
+
+/// Accept a callback POST and deliver it to the [PendingCallBack] that waits for it
+async fn handle(
+    state: Arc<Mutex<State>>,
+    secret: Arc<str>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    //{{{
+    if request.method() != Method::POST {
+        return Ok(status(StatusCode::METHOD_NOT_ALLOWED));
+    }
+    let headers = request.headers();
+    let given = headers
+        .get(SECRET_HEADER)
+        .map_or(&[][..], |value| value.as_bytes());
+    if !same_secret(given, secret.as_bytes()) {
+        return Ok(status(StatusCode::UNAUTHORIZED));
+    }
+    let declared_size = headers
+        .get(CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok()?.parse::<usize>().ok());
+    if declared_size.is_some_and(|size| size > MAX_BODY_SIZE) {
+        return Ok(status(StatusCode::PAYLOAD_TOO_LARGE));
+    }
+    let body = match read_body(request.into_body()).await {
+        Ok(body) => body,
+        Err(code) => return Ok(status(code)),
+    };
+    let response = match serde_json::from_slice::<CallBackResponse<Value>>(&body) {
+        Ok(response) => response,
+        Err(_) => return Ok(status(StatusCode::BAD_REQUEST)),
+    };
+
+    // NOTE: Only the replies that are waited for are accepted, so nothing piles up in the state
+    let sender = state
+        .lock()
+        .unwrap()
+        .waiting
+        .remove(&reply_key(&response.reply));
+    match sender {
+        Some(sender) => {
+            let _ = sender.send(response);
+            Ok(status(StatusCode::OK))
+        }
+        None => Ok(status(StatusCode::NOT_FOUND)),
+    }
+} //}}}
+
+fn status(status: StatusCode) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = status;
+    response
+}
+
+// TESTS {{{
+#[cfg(test)]
+mod receiver_tests {
+    use super::{CallBackReceiver, ReceiverError, MAX_BODY_SIZE, SECRET_HEADER};
+    use crate::endpoint::latex::LaTeXResponse;
+    use serde_json::{json, Value};
+
+    async fn post_with_secret(receiver: &CallBackReceiver, body: &str, secret: &str) -> u16 {
+        reqwest::Client::new()
+            .post(receiver.url().clone())
+            .header("content-type", "application/json")
+            .header(SECRET_HEADER, secret)
+            .body(body.to_string())
+            .send()
+            .await
+            .unwrap()
+            .status()
+            .as_u16()
+    }
+
+    async fn post(receiver: &CallBackReceiver, body: &str) -> u16 {
+        post_with_secret(receiver, body, receiver.secret()).await
+    }
+
+    #[tokio::test]
+    async fn receive_callbacks() {
+        //{{{
+        let receiver = CallBackReceiver::bind(([127, 0, 0, 1], 0).into())
+            .await
+            .unwrap();
+        let (callback, pending) = receiver.callback::<LaTeXResponse>();
+        assert_eq!(callback.post.as_ref(), Some(receiver.url()));
+        assert_eq!(
+            callback.headers.as_ref().unwrap()[SECRET_HEADER],
+            receiver.secret()
+        );
+        let reply = callback.reply.clone().unwrap();
+
+        let payload = json!({
+            "request_id": "2021_09_07_abc",
+            "reply": reply,
+            "result": {"text": "\\( x^{2} \\)", "latex_confidence": 0.99}
+        });
+        assert_eq!(post(&receiver, &payload.to_string()).await, 200);
+        let response = pending.await.unwrap();
+        assert_eq!(response.request_id.as_deref(), Some("2021_09_07_abc"));
+        assert_eq!(response.reply, Some(reply));
+        assert_eq!(response.result.text.as_deref(), Some("\\( x^{2} \\)"));
+
+        // Callback that arrives before the pending callback is polled
+        let pending = receiver.expect::<Value>(json!({"job": "early"}));
+        let payload = json!({"reply": {"job": "early"}, "result": {"keys": ["a"]}});
+        assert_eq!(post(&receiver, &payload.to_string()).await, 200);
+        assert_eq!(pending.await.unwrap().result, json!({"keys": ["a"]}));
+        // ... and is not delivered twice
+        assert_eq!(post(&receiver, &payload.to_string()).await, 404);
+    } //}}}
+
+    #[tokio::test]
+    async fn receive_errors() {
+        //{{{
+        let receiver = CallBackReceiver::bind(([127, 0, 0, 1], 0).into())
+            .await
+            .unwrap();
+        assert_eq!(post(&receiver, "not json").await, 400);
+        let payload = json!({"reply": {"job": "unknown"}, "result": {}}).to_string();
+        assert_eq!(post(&receiver, &payload).await, 404);
+        assert_eq!(post_with_secret(&receiver, &payload, "guess").await, 401);
+        let mut wrong = receiver.secret().to_string();
+        wrong.replace_range(..1, if wrong.starts_with('0') { "1" } else { "0" });
+        assert_eq!(post_with_secret(&receiver, &payload, &wrong).await, 401);
+        let big = format!(
+            "{{\"reply\": 0, \"result\": \"{}\"}}",
+            "a".repeat(MAX_BODY_SIZE)
+        );
+        assert_eq!(post(&receiver, &big).await, 413);
+        let status = reqwest::get(receiver.url().clone()).await.unwrap().status();
+        assert_eq!(status.as_u16(), 405);
+
+        // Result that does not match the expected type
+        let pending = receiver.expect::<LaTeXResponse>(json!(1));
+        let payload = json!({"reply": 1, "result": {"text": 42}});
+        assert_eq!(post(&receiver, &payload.to_string()).await, 200);
+        assert!(matches!(
+            pending.await,
+            Err(ReceiverError::Deserialization(_))
+        ));
+
+        let pending = receiver.expect::<LaTeXResponse>(json!(2));
+        drop(receiver);
+        assert!(matches!(pending.await, Err(ReceiverError::Closed)));
+    } //}}}
+}
+// }}}